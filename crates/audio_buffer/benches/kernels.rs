@@ -0,0 +1,72 @@
+//! Mixing through the frame by frame path that every buffer supports against
+//! the kernels used for interleaved buffers, both on the same interleaved buffers.
+
+use std::{hint::black_box, num::NonZero};
+
+use audio_buffer::{
+    buffers::interleaved::InterleavedBuffer,
+    core::{Buffer, BufferMut, io::mix_buffers},
+    dasp::Sample,
+    kernels,
+};
+use criterion::{Criterion, criterion_group, criterion_main};
+use time::FrameTime;
+
+const FRAMES: usize = 1024;
+
+fn mix(c: &mut Criterion) {
+    let channels = NonZero::new(2).unwrap();
+    let input = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(FRAMES as u64));
+    let mut output = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(FRAMES as u64));
+    let mut group = c.benchmark_group("mix_buffers");
+
+    // what `mix_buffers` does for buffers that aren't interleaved
+    group.bench_function("frame_by_frame", |b| {
+        b.iter(|| {
+            let input = black_box(&input);
+            output.map_frames_mut(
+                |out_frame, index| {
+                    let in_frame = input.get_frame(index)?;
+                    for (out, &sample) in out_frame.iter_mut().zip(in_frame) {
+                        *out = out.add_amp(sample.to_signed_sample());
+                    }
+                    Some(())
+                },
+                None,
+            )
+        })
+    });
+    group.bench_function("kernel", |b| {
+        b.iter(|| mix_buffers(black_box(&input), &mut output, None))
+    });
+
+    group.finish();
+}
+
+fn mix_with_gain_ramp(c: &mut Criterion) {
+    let input: Vec<f32> = (0..FRAMES).map(|i| (i as f32).sin()).collect();
+    let mut output = vec![0.0f32; FRAMES];
+    let mut group = c.benchmark_group("mix_with_gain_ramp");
+
+    group.bench_function("per_sample", |b| {
+        b.iter(|| {
+            let (start, end) = black_box((0.0f32, 1.0f32));
+            for (index, (out, &sample)) in output.iter_mut().zip(&input).enumerate() {
+                let gain = start + (end - start) * index as f32 / FRAMES as f32;
+                let sample = sample.to_float_sample() * gain;
+                *out = out.add_amp(sample.to_signed_sample());
+            }
+        })
+    });
+    group.bench_function("kernel", |b| {
+        b.iter(|| {
+            let (start, end) = black_box((0.0, 1.0));
+            kernels::mix_with_gain_ramp(&mut output, &input, start, end);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, mix, mix_with_gain_ramp);
+criterion_main!(benches);
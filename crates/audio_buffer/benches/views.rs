@@ -0,0 +1,71 @@
+//! Channel access through the boxed `View`/`MutableView` against the strided
+//! views the buffers now hand out. The views are passed through `black_box` so
+//! the mapping closure can't be inlined, as is the case behind `Buffer`.
+
+use std::hint::black_box;
+
+use audio_buffer::{
+    buffers::view::{InjectiveFn, MutableView, StridedView, StridedViewMut, View},
+    core::axis::BufferAxisMut,
+};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+const CHANNELS: usize = 2;
+const FRAMES: usize = 1024;
+
+fn interleaved() -> Vec<f32> {
+    (0..CHANNELS * FRAMES).map(|i| i as f32 * 1e-3).collect()
+}
+
+fn read_channel(c: &mut Criterion) {
+    let data = interleaved();
+    let mut group = c.benchmark_group("read_channel");
+
+    group.bench_function("boxed_view", |b| {
+        b.iter(|| {
+            let view = black_box(View::with_stride(data.as_slice(), CHANNELS, 1));
+            (0..FRAMES).map(|i| *view.get(i).unwrap()).sum::<f32>()
+        })
+    });
+    group.bench_function("strided_view", |b| {
+        b.iter(|| {
+            black_box(StridedView::channel(data.as_slice(), CHANNELS, 1))
+                .iter()
+                .sum::<f32>()
+        })
+    });
+
+    group.finish();
+}
+
+fn scale_channel(c: &mut Criterion) {
+    let mut data = interleaved();
+    let mut group = c.benchmark_group("scale_channel");
+
+    group.bench_function("boxed_view", |b| {
+        let gain = black_box(0.5);
+        b.iter(|| {
+            let mapper = InjectiveFn(Box::new(|i: usize| i * CHANNELS + 1));
+            // SAFETY: the mapper is injective and `data` outlives the view
+            let mut view =
+                unsafe { MutableView::from_raw(data.as_mut_slice() as *mut [f32], mapper) };
+            for sample in view.iter_samples_mut() {
+                *sample *= gain;
+            }
+        })
+    });
+    group.bench_function("strided_view", |b| {
+        let gain = black_box(0.5);
+        b.iter(|| {
+            let mut view = black_box(StridedViewMut::channel(data.as_mut_slice(), CHANNELS, 1));
+            for sample in view.iter_samples_mut() {
+                *sample *= gain;
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, read_channel, scale_channel);
+criterion_main!(benches);
@@ -1,46 +1,49 @@
 use crate::{
-    buffers::view::{Index, View},
-    core::Buffer,
+    buffers::view::{Index, IndexMut, StridedView, StridedViewMut},
+    core::{
+        Buffer, BufferMut, BufferMutTypes, ChannelMut, FrameMut,
+        axis::{BufferAxis, BufferAxisMut},
+    },
 };
 
-pub struct ChannelIter<'a, T: dasp::Sample> {
-    buffer: &'a WrapInterleaved<'a, T>,
+pub struct ChannelIter<'a, T> {
+    data: &'a [T],
+    channels: usize,
     position: usize,
 }
 
-impl<'a, T: dasp::Sample> ChannelIter<'a, T> {
-    pub fn new(buffer: &'a WrapInterleaved<T>, position: usize) -> Self {
-        Self { buffer, position }
+impl<'a, T> ChannelIter<'a, T> {
+    pub fn new(data: &'a [T], channels: usize, position: usize) -> Self {
+        Self {
+            data,
+            channels,
+            position,
+        }
     }
 }
 
-impl<'a, T: dasp::Sample> Iterator for ChannelIter<'a, T> {
-    type Item = View<'a, &'a [T], usize, usize>;
+impl<'a, T> Iterator for ChannelIter<'a, T> {
+    type Item = StridedView<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let position = self.position;
-        let channels = self.buffer.channels();
-        if self.position < channels {
-            let channel = View::new(
-                &self.buffer.data,
-                Box::new(move |sample| sample * channels + position),
-            );
+        if self.position < self.channels {
             self.position += 1;
-            Some(channel)
+            Some(StridedView::channel(self.data, self.channels, position))
         } else {
             None
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.buffer.channels() - self.position;
+        let remaining = self.channels - self.position;
         (remaining, Some(remaining))
     }
 }
 
-impl<'a, T: dasp::Sample> ExactSizeIterator for ChannelIter<'a, T> {
+impl<'a, T> ExactSizeIterator for ChannelIter<'a, T> {
     fn len(&self) -> usize {
-        self.buffer.channels() - self.position
+        self.channels - self.position
     }
 }
 
@@ -56,15 +59,19 @@ impl<T> Index<usize> for &[T] {
     }
 }
 
-// impl<T> IndexMut<usize> for [T] {
-//     fn get_indexed_mut(&mut self, index: usize) -> Option<&mut Self::Output> {
-//         if index < self.len() {
-//             Some(&mut self[index])
-//         } else {
-//             None
-//         }
-//     }
-// }
+impl<T> Index<usize> for [T] {
+    type Output = T;
+
+    fn get_indexed(&self, index: usize) -> Option<&Self::Output> {
+        self.get(index)
+    }
+}
+
+impl<T> IndexMut<usize> for [T] {
+    fn get_indexed_mut(&mut self, index: usize) -> Option<&mut Self::Output> {
+        self.get_mut(index)
+    }
+}
 
 pub struct WrapInterleaved<'a, T> {
     data: &'a [T],
@@ -86,7 +93,7 @@ impl<'a, T: dasp::Sample> Buffer for WrapInterleaved<'a, T> {
         Self: 'this;
 
     type Channel<'this>
-        = View<'this, &'this [T], usize, usize>
+        = StridedView<'this, T>
     where
         Self: 'this;
 
@@ -101,13 +108,14 @@ impl<'a, T: dasp::Sample> Buffer for WrapInterleaved<'a, T> {
         Self: 'this;
 
     fn get_frame(&self, index: usize) -> Option<Self::Frame<'_>> {
-        self.data.get(index..index + self.channels())
+        let channels = self.channels();
+        self.data.get(index * channels..(index + 1) * channels)
     }
 
     fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
         let channels = self.channels();
         if index < channels {
-            Some(View::with_stride(&self.data, channels, index))
+            Some(StridedView::channel(self.data, channels, index))
         } else {
             None
         }
@@ -118,146 +126,396 @@ impl<'a, T: dasp::Sample> Buffer for WrapInterleaved<'a, T> {
     }
 
     fn iter_channels(&self) -> Self::IterChannels<'_> {
-        ChannelIter::new(&self, 0)
+        ChannelIter::new(self.data, self.channels, 0)
     }
 
     fn channels(&self) -> usize {
         self.channels
     }
 
+    fn as_interleaved(&self) -> Option<&[T]> {
+        Some(self.data)
+    }
+
     fn samples(&self) -> usize {
         self.data.len()
     }
 }
 
-// pub struct WrapInterleavedMut<'a, T> {
-//     data: &'a mut [T],
-//     channels: usize,
-//     sample_rate: usize,
-// }
-
-// impl<'a, T> WrapInterleavedMut<'a, T> {
-//     pub fn new(data: &'a mut [T], channels: usize, sample_rate: usize) -> Self {
-//         Self {
-//             data,
-//             channels,
-//             sample_rate,
-//         }
-//     }
-// }
-
-// impl<'a, T: dasp::Sample> Buffer for WrapInterleavedMut<'a, T> {
-//     type Sample = T;
-
-//     type Frame<'this>
-//         = &'this [Self::Sample]
-//     where
-//         Self: 'this;
-
-//     type Channel<'this>
-//         = StridedSlice<'this, Self::Sample>
-//     where
-//         Self: 'this;
-
-//     type IterFrames<'this>
-//         = FrameIter<'this, Self::Sample>
-//     where
-//         Self: 'this;
-
-//     type IterChannels<'this>
-//         = ChannelIter<'this, Self::Sample>
-//     where
-//         Self: 'this;
-
-//     fn get_frame(&self, index: usize) -> Option<Self::Frame<'_>> {
-//         self.data.get(index..index + self.channels())
-//     }
-
-//     fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
-//         if index < self.channels() {
-//             Some(unsafe {
-//                 StridedSlice::new(
-//                     &self.data,
-//                     index,
-//                     self.samples() / self.channels(),
-//                     self.channels(),
-//                 )
-//             })
-//         } else {
-//             None
-//         }
-//     }
-
-//     fn iter_frames(&self) -> Self::IterFrames<'_> {
-//         FrameIter::new(self.data.chunks_exact(self.channels()))
-//     }
-
-//     fn iter_channels(&self) -> Self::IterChannels<'_> {
-//         ChannelIter::new(
-//             &self.data,
-//             0,
-//             self.samples() / self.channels(),
-//             self.channels(),
-//         )
-//     }
-
-//     fn channels(&self) -> usize {
-//         self.channels
-//     }
-
-//     fn samples(&self) -> usize {
-//         self.data.len()
-//     }
-
-//     fn sample_rate(&self) -> usize {
-//         self.sample_rate
-//     }
-// }
-
-// impl<'a, T: dasp::Sample + 'static> BufferMut for WrapInterleavedMut<'a, T> {
-//     type FrameMut<'this>
-//         = &'this mut [T]
-//     where
-//         Self: 'this;
-
-//     type ChannelMut<'this>
-//         = StridedSliceMut<'this, T>
-//     where
-//         Self: 'this;
-
-//     type IterFramesMut<'this>
-//         = FrameIterMut<'this, T>
-//     where
-//         Self: 'this;
-
-//     type IterChannelsMut<'this>
-//         = ChannelIterMut<'this, T>
-//     where
-//         Self: 'this;
-
-//     fn with_frame_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
-//     where
-//         F: FnOnce(Self::FrameMut<'this>) -> R,
-//     {
-//         let channels = self.channels();
-//         match self.data.get_mut(index..index + channels) {
-//             Some(frame) => Some(f(frame)),
-//             None => None,
-//         }
-//     }
-
-//     fn with_channel_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
-//     where
-//         F: FnOnce(Self::ChannelMut<'this>) -> R,
-//     {
-//         let samples = self.samples();
-//         let channels = self.channels();
-//         if index < self.channels() {
-//             Some(f(unsafe {
-//                 StridedSliceMut::new(&mut self.data, index, samples / channels, channels)
-//             }))
-//         } else {
-//             None
-//         }
-//     }
-// }
+/// An interleaved buffer over borrowed samples, e.g. the buffer of a device callback
+pub struct WrapInterleavedMut<'a, T> {
+    data: &'a mut [T],
+    channels: usize,
+}
+
+impl<'a, T> WrapInterleavedMut<'a, T> {
+    pub fn new(data: &'a mut [T], channels: usize) -> Self {
+        Self { data, channels }
+    }
+
+    /// A shorter lived wrapper over the same samples
+    pub fn reborrow(&mut self) -> WrapInterleavedMut<'_, T> {
+        WrapInterleavedMut::new(self.data, self.channels)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.data
+    }
+}
+
+impl<'a, T: dasp::Sample> Buffer for WrapInterleavedMut<'a, T> {
+    type Sample = T;
+
+    type Frame<'this>
+        = &'this [Self::Sample]
+    where
+        Self: 'this;
+
+    type Channel<'this>
+        = StridedView<'this, T>
+    where
+        Self: 'this;
+
+    type IterFrames<'this>
+        = std::slice::ChunksExact<'this, T>
+    where
+        Self: 'this;
+
+    type IterChannels<'this>
+        = ChannelIter<'this, Self::Sample>
+    where
+        Self: 'this;
+
+    fn get_frame(&self, index: usize) -> Option<Self::Frame<'_>> {
+        let channels = self.channels();
+        self.data.get(index * channels..(index + 1) * channels)
+    }
+
+    fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
+        let channels = self.channels();
+        if index < channels {
+            Some(StridedView::channel(self.data, channels, index))
+        } else {
+            None
+        }
+    }
+
+    fn iter_frames(&self) -> Self::IterFrames<'_> {
+        self.data.chunks_exact(self.channels())
+    }
+
+    fn iter_channels(&self) -> Self::IterChannels<'_> {
+        ChannelIter::new(self.data, self.channels, 0)
+    }
+
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn as_interleaved(&self) -> Option<&[T]> {
+        Some(self.data)
+    }
+
+    fn samples(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl<'this, 'a, T: dasp::Sample + 'static> BufferMutTypes<'this> for WrapInterleavedMut<'a, T> {
+    type FrameMut = &'this mut [T];
+    type ChannelMut = StridedViewMut<'this, T>;
+}
+
+impl<'a, T: dasp::Sample + 'static> BufferMut for WrapInterleavedMut<'a, T> {
+    fn with_frame_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(FrameMut<'this, Self>) -> R,
+    {
+        let channels = self.channels();
+        self.data
+            .get_mut(index * channels..(index + 1) * channels)
+            .map(f)
+    }
+
+    fn with_channel_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(ChannelMut<'this, Self>) -> R,
+    {
+        let channels = self.channels();
+        if index < channels {
+            Some(f(StridedViewMut::channel(self.data, channels, index)))
+        } else {
+            None
+        }
+    }
+
+    fn set_to_equilibrium(&mut self) {
+        self.data.fill(T::EQUILIBRIUM);
+    }
+
+    fn as_interleaved_mut(&mut self) -> Option<&mut [T]> {
+        Some(&mut self.data)
+    }
+}
+
+/// A frame of a `WrapPlanarMut`, indexed by channel
+pub struct PlanarFrame<'a, T> {
+    channels: &'a [&'a mut [T]],
+    index: usize,
+}
+
+impl<'a, T> BufferAxis<T> for PlanarFrame<'a, T> {
+    fn get_sample(&self, channel: usize) -> Option<&T> {
+        self.channels.get(channel)?.get(self.index)
+    }
+}
+
+/// A mutable frame of a `WrapPlanarMut`, indexed by channel
+pub struct PlanarFrameMut<'this, 'a, T> {
+    channels: &'this mut [&'a mut [T]],
+    index: usize,
+}
+
+impl<'this, 'a, T> BufferAxis<T> for PlanarFrameMut<'this, 'a, T> {
+    fn get_sample(&self, channel: usize) -> Option<&T> {
+        self.channels.get(channel)?.get(self.index)
+    }
+}
+
+impl<'this, 'a, T> BufferAxisMut<'this, T> for PlanarFrameMut<'this, 'a, T> {
+    fn get_sample_mut(&mut self, channel: usize) -> Option<&mut T> {
+        self.channels.get_mut(channel)?.get_mut(self.index)
+    }
+
+    fn iter_samples_mut<'s>(&'s mut self) -> impl Iterator<Item = &'s mut T>
+    where
+        T: 's,
+    {
+        let index = self.index;
+        self.channels
+            .iter_mut()
+            .map(move |channel| &mut channel[index])
+    }
+}
+
+pub struct PlanarFrameIter<'a, T> {
+    channels: &'a [&'a mut [T]],
+    frames: usize,
+    position: usize,
+}
+
+impl<'a, T> Iterator for PlanarFrameIter<'a, T> {
+    type Item = PlanarFrame<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.position;
+        if index < self.frames {
+            self.position += 1;
+            Some(PlanarFrame {
+                channels: self.channels,
+                index,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.frames - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for PlanarFrameIter<'a, T> {
+    fn len(&self) -> usize {
+        self.frames - self.position
+    }
+}
+
+/// A planar buffer over borrowed channels, e.g. the buffer of a plugin host.
+/// Every channel must have the same length.
+pub struct WrapPlanarMut<'a, 'b, T> {
+    channels: &'a mut [&'b mut [T]],
+    frames: usize,
+}
+
+impl<'a, 'b, T> WrapPlanarMut<'a, 'b, T> {
+    /// # Panics
+    /// If there are no channels or they differ in length
+    pub fn new(channels: &'a mut [&'b mut [T]]) -> Self {
+        let frames = channels.first().expect("at least one channel").len();
+        assert!(
+            channels.iter().all(|channel| channel.len() == frames),
+            "channels differ in length"
+        );
+        Self { channels, frames }
+    }
+}
+
+impl<'a, 'b, T: dasp::Sample> Buffer for WrapPlanarMut<'a, 'b, T> {
+    type Sample = T;
+
+    type Frame<'this>
+        = PlanarFrame<'this, T>
+    where
+        Self: 'this;
+
+    type Channel<'this>
+        = &'this [T]
+    where
+        Self: 'this;
+
+    type IterFrames<'this>
+        = PlanarFrameIter<'this, T>
+    where
+        Self: 'this;
+
+    type IterChannels<'this>
+        = std::iter::Map<std::slice::Iter<'this, &'b mut [T]>, fn(&'this &'b mut [T]) -> &'this [T]>
+    where
+        Self: 'this;
+
+    fn get_frame(&self, index: usize) -> Option<Self::Frame<'_>> {
+        if index < self.frames {
+            Some(PlanarFrame {
+                channels: self.channels,
+                index,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
+        self.channels.get(index).map(|channel| &**channel)
+    }
+
+    fn iter_frames(&self) -> Self::IterFrames<'_> {
+        PlanarFrameIter {
+            channels: self.channels,
+            frames: self.frames,
+            position: 0,
+        }
+    }
+
+    fn iter_channels(&self) -> Self::IterChannels<'_> {
+        self.channels.iter().map(|channel| &**channel)
+    }
+
+    fn channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    fn samples(&self) -> usize {
+        self.channels.len() * self.frames
+    }
+
+    fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl<'this, 'a, 'b, T: dasp::Sample + 'static> BufferMutTypes<'this> for WrapPlanarMut<'a, 'b, T> {
+    type FrameMut = PlanarFrameMut<'this, 'b, T>;
+    type ChannelMut = &'this mut [T];
+}
+
+impl<'a, 'b, T: dasp::Sample + 'static> BufferMut for WrapPlanarMut<'a, 'b, T> {
+    fn with_frame_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(FrameMut<'this, Self>) -> R,
+    {
+        if index < self.frames {
+            Some(f(PlanarFrameMut {
+                channels: &mut *self.channels,
+                index,
+            }))
+        } else {
+            None
+        }
+    }
+
+    fn with_channel_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(ChannelMut<'this, Self>) -> R,
+    {
+        self.channels.get_mut(index).map(|channel| f(channel))
+    }
+
+    fn set_to_equilibrium(&mut self) {
+        for channel in self.channels.iter_mut() {
+            channel.fill(T::EQUILIBRIUM);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buffers::compatability::slice::{WrapInterleavedMut, WrapPlanarMut},
+        core::{Buffer, BufferMut, axis::BufferAxisMut},
+    };
+
+    #[test]
+    fn wrappers_write_through_to_the_borrowed_samples() {
+        let mut data = [0.0f32; 6];
+        let mut buffer = WrapInterleavedMut::new(&mut data, 2);
+        assert_eq!(buffer.frames(), 3);
+
+        buffer.with_frame_mut(1, |frame| frame.copy_from_slice(&[1.0, 2.0]));
+        buffer.with_channel_mut(1, |mut channel| {
+            channel.map_samples_mut(
+                |sample, _| {
+                    *sample += 0.5;
+                    Some(())
+                },
+                None,
+            );
+        });
+        assert_eq!(buffer.get_frame(2).unwrap(), &[0.0, 0.5]);
+        assert!(buffer.get_frame(3).is_none());
+        assert_eq!(data, [0.0, 0.5, 1.0, 2.5, 0.0, 0.5]);
+
+        let (mut left, mut right) = ([0.0f32; 3], [0.0f32; 3]);
+        let mut channels = [&mut left[..], &mut right[..]];
+        let mut buffer = WrapPlanarMut::new(&mut channels);
+        assert_eq!((buffer.channels(), buffer.frames()), (2, 3));
+
+        buffer.with_frame_mut(2, |mut frame| {
+            frame.map_samples_mut(
+                |sample, channel| {
+                    *sample = channel as f32 + 1.0;
+                    Some(())
+                },
+                None,
+            );
+        });
+        buffer.with_channel_mut(0, |channel| channel[0] = -1.0);
+        assert_eq!(buffer.iter_frames().len(), 3);
+        assert_eq!((left, right), ([-1.0, 0.0, 1.0], [0.0, 0.0, 2.0]));
+    }
+
+    #[test]
+    fn planar_frames_outlive_nothing_but_the_wrapper() {
+        let (mut left, mut right) = ([1.0f32; 2], [2.0f32; 2]);
+        {
+            // the list of channels lives shorter than the channels themselves
+            let mut channels = [&mut left[..], &mut right[..]];
+            let mut buffer = WrapPlanarMut::new(&mut channels);
+            buffer.map_frames_mut(
+                |mut frame, index| {
+                    for sample in frame.iter_samples_mut() {
+                        *sample *= index as f32 + 2.0;
+                    }
+                    Some(())
+                },
+                None,
+            );
+        }
+        assert_eq!((left, right), ([2.0, 3.0], [4.0, 6.0]));
+    }
+}
@@ -1,21 +1,21 @@
 use crate::{
-    buffers::{fixed_frames::FixedFrameBuffer, view::View},
+    buffers::{fixed_frames::FixedFrameBuffer, view::StridedView},
     core::Buffer,
 };
 
-pub struct FrameIter<'a, T: dasp::Sample, const F: usize> {
-    buffer: &'a FixedFrameBuffer<T, F>,
+pub struct FrameIter<'a, T: dasp::Sample, const C: usize, const F: usize> {
+    buffer: &'a FixedFrameBuffer<T, C, F>,
     position: usize,
 }
 
-impl<'a, T: dasp::Sample, const F: usize> FrameIter<'a, T, F> {
-    pub fn new(buffer: &'a FixedFrameBuffer<T, F>, position: usize) -> Self {
+impl<'a, T: dasp::Sample, const C: usize, const F: usize> FrameIter<'a, T, C, F> {
+    pub fn new(buffer: &'a FixedFrameBuffer<T, C, F>, position: usize) -> Self {
         Self { buffer, position }
     }
 }
 
-impl<'a, T: dasp::Sample, const F: usize> Iterator for FrameIter<'a, T, F> {
-    type Item = View<'a, Vec<[T; F]>, usize, (usize, usize)>;
+impl<'a, T: dasp::Sample, const C: usize, const F: usize> Iterator for FrameIter<'a, T, C, F> {
+    type Item = StridedView<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.position < F {
@@ -1,55 +1,52 @@
 use crate::{
     buffers::{
         fixed_frames::iter::FrameIter,
-        view::{Index, IndexMut, InjectiveFn, MutableView, View},
+        view::{Index, IndexMut, StridedView, StridedViewMut},
     },
-    core::{Buffer, BufferMut},
+    core::{Buffer, BufferMut, BufferMutTypes, ChannelMut, FrameMut, TemporalBuffer},
 };
 use time::SampleRate;
 
 pub mod iter;
 
-impl<T, const F: usize> Index<(usize, usize)> for Vec<[T; F]> {
+impl<T, const C: usize, const F: usize> Index<(usize, usize)> for [[T; F]; C] {
     type Output = T;
 
     fn get_indexed(&self, index: (usize, usize)) -> Option<&Self::Output> {
-        if index.0 < self.len() {
-            Some(&self[index.0][index.1])
-        } else {
-            None
-        }
+        self.get(index.0)?.get(index.1)
     }
 }
 
-impl<T, const F: usize> IndexMut<(usize, usize)> for Vec<[T; F]> {
+impl<T, const C: usize, const F: usize> IndexMut<(usize, usize)> for [[T; F]; C] {
     fn get_indexed_mut(&mut self, index: (usize, usize)) -> Option<&mut Self::Output> {
-        if index.0 < self.len() {
-            Some(&mut self[index.0][index.1])
-        } else {
-            None
-        }
+        self.get_mut(index.0)?.get_mut(index.1)
     }
 }
 
-pub struct FixedFrameBuffer<T, const F: usize> {
-    data: Vec<[T; F]>,
+/// A buffer of `C` channels with `F` frames each, stored inline as arrays.
+/// It never allocates, which makes it useful as scratch space inside of
+/// processors. Large buffers should be boxed or kept in a processor's
+/// state rather than on the audio thread's stack.
+pub struct FixedFrameBuffer<T, const C: usize, const F: usize> {
+    data: [[T; F]; C],
     sample_rate: SampleRate,
 }
 
-impl<T: dasp::Sample, const F: usize> FixedFrameBuffer<T, F> {
-    pub fn with_shape(channels: usize, sample_rate: SampleRate) -> Self {
+impl<T: dasp::Sample, const C: usize, const F: usize> FixedFrameBuffer<T, C, F> {
+    pub fn new(sample_rate: SampleRate) -> Self {
+        const { assert!(C > 0, "a buffer needs at least one channel") };
         Self {
-            data: vec![[T::EQUILIBRIUM; F]; channels],
+            data: [[T::EQUILIBRIUM; F]; C],
             sample_rate,
         }
     }
 }
 
-impl<T: dasp::Sample, const F: usize> Buffer for FixedFrameBuffer<T, F> {
+impl<T: dasp::Sample, const C: usize, const F: usize> Buffer for FixedFrameBuffer<T, C, F> {
     type Sample = T;
 
     type Frame<'this>
-        = View<'this, Vec<[T; F]>, usize, (usize, usize)>
+        = StridedView<'this, T>
     where
         Self: 'this;
 
@@ -59,7 +56,7 @@ impl<T: dasp::Sample, const F: usize> Buffer for FixedFrameBuffer<T, F> {
         Self: 'this;
 
     type IterFrames<'this>
-        = FrameIter<'this, T, F>
+        = FrameIter<'this, T, C, F>
     where
         Self: 'this;
 
@@ -70,9 +67,11 @@ impl<T: dasp::Sample, const F: usize> Buffer for FixedFrameBuffer<T, F> {
 
     fn get_frame(&self, index: usize) -> Option<Self::Frame<'_>> {
         if index < F {
-            Some(View::new(
-                &self.data,
-                Box::new(move |channel: usize| (channel, index)),
+            Some(StridedView::new(
+                self.data.as_flattened(),
+                index,
+                F,
+                self.channels(),
             ))
         } else {
             None
@@ -80,11 +79,7 @@ impl<T: dasp::Sample, const F: usize> Buffer for FixedFrameBuffer<T, F> {
     }
 
     fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
-        if index < self.channels() {
-            self.data.get(index)
-        } else {
-            None
-        }
+        self.data.get(index)
     }
 
     fn iter_frames(&self) -> Self::IterFrames<'_> {
@@ -96,42 +91,45 @@ impl<T: dasp::Sample, const F: usize> Buffer for FixedFrameBuffer<T, F> {
     }
 
     fn channels(&self) -> usize {
-        self.data.len()
+        C
     }
 
     fn samples(&self) -> usize {
         self.channels() * F
     }
 
+    fn frames(&self) -> usize {
+        F
+    }
+}
+
+impl<T: dasp::Sample, const C: usize, const F: usize> TemporalBuffer for FixedFrameBuffer<T, C, F> {
     fn sample_rate(&self) -> SampleRate {
         self.sample_rate
     }
 }
 
-impl<T: dasp::Sample, const FRAMES: usize> BufferMut for FixedFrameBuffer<T, FRAMES> {
-    type FrameMut<'this>
-        = MutableView<'this, Vec<[T; FRAMES]>, usize, (usize, usize)>
-    where
-        Self: 'this;
-
-    type ChannelMut<'this>
-        = &'this mut [T; FRAMES]
-    where
-        Self: 'this;
+impl<'this, T: dasp::Sample + 'static, const C: usize, const FRAMES: usize> BufferMutTypes<'this>
+    for FixedFrameBuffer<T, C, FRAMES>
+{
+    type FrameMut = StridedViewMut<'this, T>;
+    type ChannelMut = &'this mut [T; FRAMES];
+}
 
+impl<T: dasp::Sample + 'static, const C: usize, const FRAMES: usize> BufferMut
+    for FixedFrameBuffer<T, C, FRAMES>
+{
     fn with_frame_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
     where
-        F: FnOnce(Self::FrameMut<'this>) -> R,
+        F: FnOnce(FrameMut<'this, Self>) -> R,
     {
         if index < FRAMES {
-            // SAFETY: This is safe because the mapper that is passed is an injective function
-            let view = unsafe {
-                MutableView::from_raw(
-                    &mut self.data as *mut _,
-                    InjectiveFn(Box::new(move |channel: usize| (channel, index))),
-                )
-            };
-            Some(f(view))
+            Some(f(StridedViewMut::new(
+                self.data.as_flattened_mut(),
+                index,
+                FRAMES,
+                C,
+            )))
         } else {
             None
         }
@@ -139,12 +137,9 @@ impl<T: dasp::Sample, const FRAMES: usize> BufferMut for FixedFrameBuffer<T, FRA
 
     fn with_channel_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
     where
-        F: FnOnce(Self::ChannelMut<'this>) -> R,
+        F: FnOnce(ChannelMut<'this, Self>) -> R,
     {
-        match self.data.get_mut(index) {
-            Some(channel) => Some(f(channel)),
-            None => None,
-        }
+        self.data.get_mut(index).map(f)
     }
 
     fn set_to_equilibrium(&mut self) {
@@ -153,3 +148,111 @@ impl<T: dasp::Sample, const FRAMES: usize> BufferMut for FixedFrameBuffer<T, FRA
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use dasp::Sample;
+    use time::SampleRate;
+
+    use crate::{
+        buffers::fixed_frames::FixedFrameBuffer,
+        core::{Buffer, BufferMut, TemporalBuffer, axis::BufferAxisMut},
+    };
+
+    fn stereo<const F: usize>() -> FixedFrameBuffer<f32, 2, F> {
+        FixedFrameBuffer::new(SampleRate(48_000.0))
+    }
+
+    #[test]
+    fn with_shape_is_full() {
+        let buffer = stereo::<256>();
+        assert_eq!(buffer.samples(), 512);
+        assert_eq!(buffer.channels(), 2);
+        assert_eq!(buffer.frames(), 256);
+    }
+
+    #[test]
+    fn samples_are_stored_inline() {
+        assert_eq!(
+            size_of::<FixedFrameBuffer<f32, 2, 4>>(),
+            size_of::<[f32; 8]>() + size_of::<SampleRate>()
+        );
+    }
+
+    #[test]
+    fn get_frame_returns_correct_view() {
+        let mut buffer = stereo::<3>();
+        buffer.data[0][2] = 5.0;
+        buffer.data[1][2] = 6.7;
+
+        let frame = buffer.get_frame(2).unwrap();
+        assert_eq!((frame.get(0), frame.get(1)), (Some(&5.0), Some(&6.7)));
+        assert!(frame.get(2).is_none());
+
+        assert!(buffer.get_frame(3).is_none());
+    }
+
+    #[test]
+    fn get_channel_returns_correct_slice() {
+        let mut buffer = stereo::<2>();
+        buffer.data[1] = [1.0, 2.0];
+
+        assert_eq!(buffer.get_channel(1).unwrap(), &[1.0, 2.0]);
+        assert!(buffer.get_channel(2).is_none());
+    }
+
+    #[test]
+    fn with_frame_mut_changes_values() {
+        let mut buffer = stereo::<3>();
+
+        buffer.with_frame_mut(2, |mut frame| {
+            frame.map_samples_mut(
+                |sample, channel| {
+                    *sample = channel as f32 + 1.0;
+                    Some(())
+                },
+                None,
+            );
+        });
+
+        let frame = buffer.get_frame(2).unwrap();
+        assert_eq!((frame.get(0), frame.get(1)), (Some(&1.0), Some(&2.0)));
+        assert_eq!(buffer.data[0], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn with_channel_mut_changes_values() {
+        let mut buffer = stereo::<2>();
+
+        buffer.with_channel_mut(0, |channel| channel.fill(42.0));
+
+        assert_eq!(buffer.get_channel(0).unwrap(), &[42.0, 42.0]);
+        assert_eq!(buffer.get_channel(1).unwrap(), &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn iter_frames_returns_every_frame() {
+        let buffer = stereo::<3>();
+
+        assert_eq!(buffer.iter_frames().count(), 3);
+        assert_eq!(buffer.iter_channels().count(), 2);
+    }
+
+    #[test]
+    fn set_to_equilibrium_fills_buffer() {
+        let mut buffer = stereo::<3>();
+        buffer.data[1][1] = 0.5;
+
+        buffer.set_to_equilibrium();
+        assert!(buffer.data.iter().flatten().all(|&s| s == f32::EQUILIBRIUM));
+    }
+
+    #[test]
+    fn duration_follows_the_sample_rate() {
+        let buffer = stereo::<480>();
+        assert_eq!(buffer.sample_rate(), SampleRate(48_000.0));
+        assert_eq!(buffer.duration(), Duration::from_millis(10));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::buffers::{interleaved::InterleavedBuffer, view::View};
+use crate::buffers::{interleaved::InterleavedBuffer, view::StridedView};
 use crate::core::Buffer;
 
 pub struct ChannelIter<'a, T: dasp::Sample> {
@@ -13,16 +13,13 @@ impl<'a, T: dasp::Sample> ChannelIter<'a, T> {
 }
 
 impl<'a, T: dasp::Sample> Iterator for ChannelIter<'a, T> {
-    type Item = View<'a, Vec<T>, usize, usize>;
+    type Item = StridedView<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let position = self.position;
         let channels = self.buffer.channels();
         if self.position < channels {
-            let channel = View::new(
-                &self.buffer.data,
-                Box::new(move |sample| sample * channels + position),
-            );
+            let channel = StridedView::channel(&self.buffer.data, channels, position);
             self.position += 1;
             Some(channel)
         } else {
@@ -4,8 +4,11 @@ use time::FrameTime;
 
 use self::iter::ChannelIter;
 use crate::{
-    buffers::view::{Index, IndexMut, InjectiveFn, MutableView, View},
-    core::{Buffer, BufferMut, ResizableBuffer},
+    buffers::{
+        compatability::slice::WrapInterleavedMut,
+        view::{Index, IndexMut, StridedView, StridedViewMut},
+    },
+    core::{Buffer, BufferMut, BufferMutTypes, ChannelMut, FrameMut, ResizableBuffer},
 };
 
 pub mod iter;
@@ -45,6 +48,33 @@ impl<T> InterleavedBuffer<T> {
             None
         }
     }
+
+    /// Takes ownership of interleaved samples, returns `None` if `data`
+    /// doesn't hold a whole number of frames
+    pub fn from_vec(data: Vec<T>, channels: NonZeroUsize) -> Option<Self> {
+        if data.len().is_multiple_of(channels.get()) {
+            Some(Self { data, channels })
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Borrows the samples as a `WrapInterleavedMut`, which is what processors write to
+    pub fn wrap_mut(&mut self) -> WrapInterleavedMut<'_, T> {
+        WrapInterleavedMut::new(&mut self.data, self.channels.get())
+    }
 }
 
 impl<T: dasp::Sample> InterleavedBuffer<T> {
@@ -72,7 +102,7 @@ impl<T: dasp::Sample> Buffer for InterleavedBuffer<T> {
         Self: 'this;
 
     type Channel<'this>
-        = View<'this, Vec<T>, usize, usize>
+        = StridedView<'this, T>
     where
         Self: 'this;
 
@@ -93,11 +123,8 @@ impl<T: dasp::Sample> Buffer for InterleavedBuffer<T> {
 
     fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
         let channels = self.channels();
-        if index < self.channels() {
-            Some(View::new(
-                &self.data,
-                Box::new(move |sample: usize| sample * channels + index),
-            ))
+        if index < channels {
+            Some(StridedView::channel(&self.data, channels, index))
         } else {
             None
         }
@@ -108,7 +135,7 @@ impl<T: dasp::Sample> Buffer for InterleavedBuffer<T> {
     }
 
     fn iter_channels(&self) -> Self::IterChannels<'_> {
-        ChannelIter::new(&self, 0)
+        ChannelIter::new(self, 0)
     }
 
     fn samples(&self) -> usize {
@@ -118,46 +145,35 @@ impl<T: dasp::Sample> Buffer for InterleavedBuffer<T> {
     fn channels(&self) -> usize {
         self.channels.into()
     }
-}
 
-impl<T: dasp::Sample + 'static> BufferMut for InterleavedBuffer<T> {
-    type FrameMut<'this>
-        = &'this mut [Self::Sample]
-    where
-        Self: 'this;
+    fn as_interleaved(&self) -> Option<&[T]> {
+        Some(&self.data)
+    }
+}
 
-    type ChannelMut<'this>
-        = MutableView<'this, Vec<T>, usize, usize>
-    where
-        Self: 'this;
+impl<'this, T: dasp::Sample + 'static> BufferMutTypes<'this> for InterleavedBuffer<T> {
+    type FrameMut = &'this mut [Self::Sample];
+    type ChannelMut = StridedViewMut<'this, T>;
+}
 
+impl<T: dasp::Sample + 'static> BufferMut for InterleavedBuffer<T> {
     fn with_frame_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
     where
-        F: FnOnce(Self::FrameMut<'this>) -> R,
+        F: FnOnce(FrameMut<'this, Self>) -> R,
     {
         let channels = self.channels();
-        match self
-            .data
+        self.data
             .get_mut(index * channels..index * channels + channels)
-        {
-            Some(frame) => Some(f(frame)),
-            None => None,
-        }
+            .map(f)
     }
 
     fn with_channel_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
     where
-        F: FnOnce(Self::ChannelMut<'this>) -> R,
+        F: FnOnce(ChannelMut<'this, Self>) -> R,
     {
         let channels = self.channels();
-        if index < self.channels() {
-            // SAFETY: The function is injective as long as channels != 0
-            Some(f(unsafe {
-                MutableView::from_raw(
-                    &mut self.data,
-                    InjectiveFn(Box::new(move |sample| sample * channels + index)),
-                )
-            }))
+        if index < channels {
+            Some(f(StridedViewMut::channel(&mut self.data, channels, index)))
         } else {
             None
         }
@@ -166,6 +182,10 @@ impl<T: dasp::Sample + 'static> BufferMut for InterleavedBuffer<T> {
     fn set_to_equilibrium(&mut self) {
         self.data.fill(T::EQUILIBRIUM);
     }
+
+    fn as_interleaved_mut(&mut self) -> Option<&mut [T]> {
+        Some(&mut self.data)
+    }
 }
 
 impl<T: dasp::Sample> ResizableBuffer for InterleavedBuffer<T> {
@@ -179,7 +199,6 @@ mod tests {
     use std::num::NonZero;
 
     use dasp::Sample;
-    use time::SampleRate;
 
     use crate::{
         buffers::interleaved::InterleavedBuffer,
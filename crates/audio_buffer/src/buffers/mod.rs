@@ -1,4 +1,5 @@
 pub mod compatability;
-// pub mod fixed_frames;
+pub mod fixed_frames;
 pub mod interleaved;
+pub mod planar;
 pub mod view;
@@ -0,0 +1,81 @@
+use crate::{
+    buffers::{planar::PlanarBuffer, view::StridedView},
+    core::Buffer,
+};
+
+pub struct FrameIter<'a, T: dasp::Sample> {
+    buffer: &'a PlanarBuffer<T>,
+    position: usize,
+}
+
+impl<'a, T: dasp::Sample> FrameIter<'a, T> {
+    pub fn new(buffer: &'a PlanarBuffer<T>, position: usize) -> Self {
+        Self { buffer, position }
+    }
+}
+
+impl<'a, T: dasp::Sample> Iterator for FrameIter<'a, T> {
+    type Item = StridedView<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.position;
+        if position < self.buffer.frames {
+            self.position += 1;
+            Some(self.buffer.get_frame(position).expect("frame is in bounds"))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.buffer.frames - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: dasp::Sample> ExactSizeIterator for FrameIter<'a, T> {
+    fn len(&self) -> usize {
+        self.buffer.frames - self.position
+    }
+}
+
+pub struct ChannelIter<'a, T> {
+    remaining: &'a [T],
+    channels: usize,
+    frames: usize,
+}
+
+impl<'a, T> ChannelIter<'a, T> {
+    pub fn new(data: &'a [T], channels: usize, frames: usize) -> Self {
+        Self {
+            remaining: data,
+            channels,
+            frames,
+        }
+    }
+}
+
+impl<'a, T> Iterator for ChannelIter<'a, T> {
+    type Item = &'a [T];
+
+    // `chunks_exact` can't be used because channels may be empty
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.channels == 0 {
+            return None;
+        }
+        let (channel, remaining) = self.remaining.split_at(self.frames);
+        self.remaining = remaining;
+        self.channels -= 1;
+        Some(channel)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.channels, Some(self.channels))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for ChannelIter<'a, T> {
+    fn len(&self) -> usize {
+        self.channels
+    }
+}
@@ -0,0 +1,394 @@
+use std::num::NonZeroUsize;
+
+use time::FrameTime;
+
+use self::iter::{ChannelIter, FrameIter};
+use crate::{
+    buffers::{
+        interleaved::InterleavedBuffer,
+        view::{StridedView, StridedViewMut},
+    },
+    core::{Buffer, BufferMut, BufferMutTypes, ChannelMut, FrameMut, ResizableBuffer},
+};
+
+pub mod iter;
+
+/// A buffer that stores every channel contiguously, one after the other.
+/// Channels are plain slices, frames are strided views.
+pub struct PlanarBuffer<T> {
+    data: Vec<T>,
+    channels: NonZeroUsize,
+    frames: usize,
+}
+
+impl<T> PlanarBuffer<T> {
+    pub fn get_sample(&self, channel: usize, frame: usize) -> Option<&T> {
+        if frame < self.frames {
+            self.data.get(channel * self.frames + frame)
+        } else {
+            None
+        }
+    }
+
+    /// Takes ownership of channels that are stored one after the other,
+    /// returns `None` if `data` can't be split into `channels` equal parts
+    pub fn from_vec(data: Vec<T>, channels: NonZeroUsize) -> Option<Self> {
+        if data.len().is_multiple_of(channels.get()) {
+            Some(Self {
+                frames: data.len() / channels.get(),
+                data,
+                channels,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+
+    /// All channels, one after the other
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn channel(&self, index: usize) -> Option<&[T]> {
+        if index < self.channels.get() {
+            self.data
+                .get(index * self.frames..(index + 1) * self.frames)
+        } else {
+            None
+        }
+    }
+
+    pub fn channel_mut(&mut self, index: usize) -> Option<&mut [T]> {
+        if index < self.channels.get() {
+            self.data
+                .get_mut(index * self.frames..(index + 1) * self.frames)
+        } else {
+            None
+        }
+    }
+
+    /// Exclusive borrows to all channels at once, e.g. to process them in parallel
+    pub fn channels_mut(&mut self) -> impl ExactSizeIterator<Item = &mut [T]> {
+        let frames = self.frames;
+        let mut remaining = self.data.as_mut_slice();
+        (0..self.channels.get()).map(move |_| {
+            let (channel, rest) = std::mem::take(&mut remaining).split_at_mut(frames);
+            remaining = rest;
+            channel
+        })
+    }
+}
+
+impl<T: dasp::Sample> PlanarBuffer<T> {
+    pub fn new(channels: NonZeroUsize) -> Self {
+        Self {
+            data: Vec::<T>::new(),
+            channels,
+            frames: 0,
+        }
+    }
+
+    pub fn with_shape(channels: NonZeroUsize, frames: FrameTime) -> Self {
+        Self {
+            data: vec![T::EQUILIBRIUM; (frames * channels.get() as u64).0 as usize],
+            channels,
+            frames: frames.0 as usize,
+        }
+    }
+
+    /// Overwrites this buffer with the frames of `interleaved` without allocating.
+    /// Returns the number of frames copied, which is limited by the shorter buffer.
+    ///
+    /// # Panics
+    /// If the buffers have different numbers of channels
+    pub fn deinterleave_from(&mut self, interleaved: &InterleavedBuffer<T>) -> usize {
+        let channels = self.channels.get();
+        assert_eq!(channels, interleaved.channels(), "channel mismatch");
+
+        let frames = self.frames.min(interleaved.frames());
+        for (index, channel) in self.channels_mut().enumerate() {
+            let samples = interleaved.as_slice()[index..].iter().step_by(channels);
+            for (out, &sample) in channel[..frames].iter_mut().zip(samples) {
+                *out = sample;
+            }
+        }
+        frames
+    }
+
+    /// Overwrites `interleaved` with the frames of this buffer without allocating.
+    /// Returns the number of frames copied, which is limited by the shorter buffer.
+    ///
+    /// # Panics
+    /// If the buffers have different numbers of channels
+    pub fn interleave_into(&self, interleaved: &mut InterleavedBuffer<T>) -> usize {
+        let channels = self.channels.get();
+        assert_eq!(channels, interleaved.channels(), "channel mismatch");
+
+        let frames = self.frames.min(interleaved.frames());
+        for (index, channel) in self.iter_channels().enumerate() {
+            let samples = interleaved.as_mut_slice()[index..]
+                .iter_mut()
+                .step_by(channels);
+            for (out, &sample) in samples.zip(&channel[..frames]) {
+                *out = sample;
+            }
+        }
+        frames
+    }
+}
+
+impl<T: dasp::Sample> From<&InterleavedBuffer<T>> for PlanarBuffer<T> {
+    fn from(interleaved: &InterleavedBuffer<T>) -> Self {
+        let channels = NonZeroUsize::new(interleaved.channels()).expect("buffers have channels");
+        let mut planar = Self::with_shape(channels, FrameTime(interleaved.frames() as u64));
+        planar.deinterleave_from(interleaved);
+        planar
+    }
+}
+
+/// Mono buffers have the same layout in both formats and are moved without copying
+impl<T: dasp::Sample> From<InterleavedBuffer<T>> for PlanarBuffer<T> {
+    fn from(interleaved: InterleavedBuffer<T>) -> Self {
+        match NonZeroUsize::new(interleaved.channels()).expect("buffers have channels") {
+            channels if channels.get() == 1 => {
+                Self::from_vec(interleaved.into_inner(), channels).expect("mono always fits")
+            }
+            _ => Self::from(&interleaved),
+        }
+    }
+}
+
+impl<T: dasp::Sample> From<&PlanarBuffer<T>> for InterleavedBuffer<T> {
+    fn from(planar: &PlanarBuffer<T>) -> Self {
+        let mut interleaved =
+            InterleavedBuffer::with_shape(planar.channels, FrameTime(planar.frames as u64));
+        planar.interleave_into(&mut interleaved);
+        interleaved
+    }
+}
+
+impl<T: dasp::Sample> From<PlanarBuffer<T>> for InterleavedBuffer<T> {
+    fn from(planar: PlanarBuffer<T>) -> Self {
+        if planar.channels.get() == 1 {
+            InterleavedBuffer::from_vec(planar.data, planar.channels).expect("mono always fits")
+        } else {
+            Self::from(&planar)
+        }
+    }
+}
+
+impl<T: dasp::Sample> Buffer for PlanarBuffer<T> {
+    type Sample = T;
+
+    type Frame<'this>
+        = StridedView<'this, T>
+    where
+        Self: 'this;
+
+    type Channel<'this>
+        = &'this [Self::Sample]
+    where
+        Self: 'this;
+
+    type IterFrames<'this>
+        = FrameIter<'this, T>
+    where
+        Self: 'this;
+
+    type IterChannels<'this>
+        = ChannelIter<'this, T>
+    where
+        Self: 'this;
+
+    fn get_frame(&self, index: usize) -> Option<Self::Frame<'_>> {
+        if index < self.frames {
+            Some(StridedView::new(
+                &self.data,
+                index,
+                self.frames,
+                self.channels.get(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn get_channel(&self, index: usize) -> Option<Self::Channel<'_>> {
+        self.channel(index)
+    }
+
+    fn iter_frames(&self) -> Self::IterFrames<'_> {
+        FrameIter::new(self, 0)
+    }
+
+    fn iter_channels(&self) -> Self::IterChannels<'_> {
+        ChannelIter::new(&self.data, self.channels.get(), self.frames)
+    }
+
+    fn samples(&self) -> usize {
+        self.data.len()
+    }
+
+    fn channels(&self) -> usize {
+        self.channels.into()
+    }
+
+    fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl<'this, T: dasp::Sample + 'static> BufferMutTypes<'this> for PlanarBuffer<T> {
+    type FrameMut = StridedViewMut<'this, T>;
+    type ChannelMut = &'this mut [Self::Sample];
+}
+
+impl<T: dasp::Sample + 'static> BufferMut for PlanarBuffer<T> {
+    fn with_frame_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(FrameMut<'this, Self>) -> R,
+    {
+        let frames = self.frames;
+        if index < frames {
+            let channels = self.channels.get();
+            Some(f(StridedViewMut::new(
+                &mut self.data,
+                index,
+                frames,
+                channels,
+            )))
+        } else {
+            None
+        }
+    }
+
+    fn with_channel_mut<'this, F, R>(&'this mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(ChannelMut<'this, Self>) -> R,
+    {
+        self.channel_mut(index).map(f)
+    }
+
+    fn set_to_equilibrium(&mut self) {
+        self.data.fill(T::EQUILIBRIUM);
+    }
+}
+
+impl<T: dasp::Sample> ResizableBuffer for PlanarBuffer<T> {
+    // channels are moved in place, so this only allocates when growing past the capacity
+    fn resize(&mut self, frames: usize) {
+        let old = self.frames;
+        let channels = self.channels.get();
+
+        if frames > old {
+            self.data.resize(frames * channels, T::EQUILIBRIUM);
+            // back to front, so no channel overwrites one that hasn't moved yet
+            for channel in (1..channels).rev() {
+                self.data
+                    .copy_within(channel * old..(channel + 1) * old, channel * frames);
+            }
+            for channel in 0..channels {
+                self.data[channel * frames + old..(channel + 1) * frames].fill(T::EQUILIBRIUM);
+            }
+        } else {
+            for channel in 1..channels {
+                self.data
+                    .copy_within(channel * old..channel * old + frames, channel * frames);
+            }
+            self.data.truncate(frames * channels);
+        }
+
+        self.frames = frames;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::{
+        buffers::{interleaved::InterleavedBuffer, planar::PlanarBuffer},
+        core::{Buffer, BufferMut, ResizableBuffer, axis::BufferAxisMut},
+    };
+
+    fn counting(channels: usize, frames: usize) -> PlanarBuffer<f32> {
+        let data = (0..channels * frames).map(|sample| sample as f32).collect();
+        PlanarBuffer::from_vec(data, NonZero::new(channels).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn channels_are_contiguous_and_frames_are_strided() {
+        let mut buffer = counting(2, 3);
+        assert_eq!(buffer.frames(), 3);
+        assert_eq!(buffer.get_channel(1).unwrap(), &[3.0, 4.0, 5.0]);
+        assert!(buffer.get_channel(2).is_none());
+
+        let frame = buffer.get_frame(2).unwrap();
+        assert_eq!((frame.get(0), frame.get(1)), (Some(&2.0), Some(&5.0)));
+        assert!(frame.get(2).is_none());
+        assert!(buffer.get_frame(3).is_none());
+
+        buffer.with_frame_mut(0, |mut frame| {
+            frame.map_samples_mut(
+                |sample, _| {
+                    *sample = -1.0;
+                    Some(())
+                },
+                None,
+            );
+        });
+        buffer.with_channel_mut(1, |channel| channel[2] = 9.0);
+        assert_eq!(buffer.as_slice(), &[-1.0, 1.0, 2.0, -1.0, 4.0, 9.0]);
+        assert_eq!(buffer.iter_channels().len(), 2);
+        assert_eq!(buffer.iter_frames().len(), 3);
+
+        // channels without frames still exist
+        let empty = PlanarBuffer::<f32>::new(NonZero::new(2).unwrap());
+        assert_eq!(empty.iter_channels().count(), 2);
+    }
+
+    #[test]
+    fn resize_keeps_every_channel() {
+        let mut buffer = counting(3, 2);
+
+        buffer.resize(4);
+        assert_eq!(
+            buffer.as_slice(),
+            &[0.0, 1.0, 0.0, 0.0, 2.0, 3.0, 0.0, 0.0, 4.0, 5.0, 0.0, 0.0]
+        );
+
+        buffer.resize(1);
+        assert_eq!(buffer.as_slice(), &[0.0, 2.0, 4.0]);
+        assert_eq!(buffer.samples(), 3);
+    }
+
+    #[test]
+    fn converts_to_and_from_interleaved() {
+        let planar = counting(2, 3);
+
+        let interleaved = InterleavedBuffer::from(&planar);
+        assert_eq!(interleaved.as_slice(), &[0.0, 3.0, 1.0, 4.0, 2.0, 5.0]);
+
+        let mut round_trip = PlanarBuffer::with_shape(NonZero::new(2).unwrap(), time::FrameTime(2));
+        assert_eq!(round_trip.deinterleave_from(&interleaved), 2);
+        assert_eq!(round_trip.as_slice(), &[0.0, 1.0, 3.0, 4.0]);
+        assert_eq!(
+            PlanarBuffer::from(interleaved).as_slice(),
+            planar.as_slice()
+        );
+
+        // mono buffers hand over their allocation
+        let mono = counting(1, 4);
+        let pointer = mono.as_slice().as_ptr();
+        let interleaved = InterleavedBuffer::from(mono);
+        assert_eq!(interleaved.as_slice().as_ptr(), pointer);
+    }
+}
@@ -25,7 +25,7 @@ impl<I, J> InjectiveFn<I, J> {
 // A view over indexable data that transforms indices through a mapping function
 pub struct View<'a, D, I, J>
 where
-    D: Index<J>,
+    D: Index<J> + ?Sized,
 {
     data: &'a D,
     mapper: Box<dyn Fn(I) -> J>,
@@ -34,7 +34,7 @@ where
 
 impl<'a, D, I, J> View<'a, D, I, J>
 where
-    D: Index<J>,
+    D: Index<J> + ?Sized,
 {
     pub fn new(data: &'a D, mapper: Box<dyn Fn(I) -> J>) -> Self {
         Self {
@@ -53,7 +53,7 @@ where
 
 impl<'a, D> View<'a, D, usize, usize>
 where
-    D: Index<usize>,
+    D: Index<usize> + ?Sized,
 {
     pub fn with_stride(data: &'a D, num_channels: usize, channel_index: usize) -> Self {
         Self {
@@ -69,7 +69,7 @@ where
 // A mutable view over indexable data that transforms indices through a mapping function
 pub struct MutableView<'a, D, I, J>
 where
-    D: IndexMut<J>,
+    D: IndexMut<J> + ?Sized,
 {
     data: *mut D,
     mapper: InjectiveFn<I, J>,
@@ -78,7 +78,7 @@ where
 
 impl<'a, D, I, J> MutableView<'a, D, I, J>
 where
-    D: IndexMut<J>,
+    D: IndexMut<J> + ?Sized,
 {
     /// Create a `MutableView` from a raw pointer and a mapping function.
     ///
@@ -123,7 +123,7 @@ where
 
 impl<'a, D, J> BufferAxis<D::Output> for View<'a, D, usize, J>
 where
-    D: Index<J>,
+    D: Index<J> + ?Sized,
 {
     fn get_sample(&self, index: usize) -> Option<&D::Output> {
         self.get(index)
@@ -132,7 +132,7 @@ where
 
 impl<'a, D, J> BufferAxis<D::Output> for MutableView<'a, D, usize, J>
 where
-    D: IndexMut<J>,
+    D: IndexMut<J> + ?Sized,
 {
     fn get_sample(&self, index: usize) -> Option<&D::Output> {
         self.get(index)
@@ -141,26 +141,38 @@ where
 
 impl<'a, D, J> BufferAxisMut<'a, D::Output> for MutableView<'a, D, usize, J>
 where
-    D: IndexMut<J>,
+    D: IndexMut<J> + ?Sized,
 {
     fn get_sample_mut(&mut self, index: usize) -> Option<&mut D::Output> {
         self.get_mut(index)
     }
+
+    fn iter_samples_mut<'s>(&'s mut self) -> impl Iterator<Item = &'s mut D::Output>
+    where
+        D::Output: 's,
+    {
+        MutableViewIterMut {
+            view: self,
+            index: 0,
+        }
+    }
 }
 
-pub struct MutableViewIterMut<'view, D, J>
+// The view and the data it borrows have separate lifetimes, tying
+// them together is what made this impossible to express before
+pub struct MutableViewIterMut<'view, 'a, D, J>
 where
-    D: IndexMut<J>,
+    D: IndexMut<J> + ?Sized,
 {
-    view: &'view mut MutableView<'view, D, usize, J>,
+    view: &'view mut MutableView<'a, D, usize, J>,
     index: usize,
 }
 
-impl<'a, D, J> Iterator for MutableViewIterMut<'a, D, J>
+impl<'view, 'a, D, J> Iterator for MutableViewIterMut<'view, 'a, D, J>
 where
-    D: IndexMut<J>,
+    D: IndexMut<J> + ?Sized,
 {
-    type Item = &'a mut D::Output;
+    type Item = &'view mut D::Output;
 
     fn next(&mut self) -> Option<Self::Item> {
         let index = self.index;
@@ -175,8 +187,221 @@ where
         // 3. Therefore sample is always a unique reference
         //
         // Lifetime
-        // 1. The Iterator holds a mutable reference with lifetime 'a to the MutableView
+        // 1. The Iterator holds a mutable reference with lifetime 'view to the MutableView
         // 2. The Iterator consumes itsself so the returned references can't dangle
         Some(unsafe { &mut *(sample as *mut D::Output) })
     }
 }
+
+// Slicing to exactly the visited elements lets `step_by` know its length
+// up front, which is considerably faster than bounding it with `take`
+fn span(start: usize, stride: usize, len: usize) -> std::ops::Range<usize> {
+    match len {
+        0 => 0..0,
+        len => start..start + (len - 1) * stride + 1,
+    }
+}
+
+/// Every `stride`th element of a slice, starting at `start`. Unlike `View`
+/// this neither allocates nor calls through a function pointer.
+#[derive(Debug, Clone, Copy)]
+pub struct StridedView<'a, T> {
+    data: &'a [T],
+    start: usize,
+    stride: usize,
+    len: usize,
+}
+
+impl<'a, T> StridedView<'a, T> {
+    /// # Panics
+    /// If `stride` is zero or the last element is out of bounds
+    pub fn new(data: &'a [T], start: usize, stride: usize, len: usize) -> Self {
+        assert!(stride > 0, "stride must not be zero");
+        assert!(
+            len == 0 || start + (len - 1) * stride < data.len(),
+            "view is out of bounds"
+        );
+        Self {
+            data,
+            start: if len == 0 { 0 } else { start },
+            stride,
+            len,
+        }
+    }
+
+    /// The channel of interleaved samples
+    pub fn channel(data: &'a [T], channels: usize, channel: usize) -> Self {
+        Self::new(data, channel, channels, data.len() / channels)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        if index < self.len {
+            self.data.get(self.start + index * self.stride)
+        } else {
+            None
+        }
+    }
+
+    /// The viewed elements as a slice if they are adjacent
+    pub fn as_slice(&self) -> Option<&'a [T]> {
+        (self.stride == 1 || self.len <= 1)
+            .then(|| &self.data[span(self.start, self.stride, self.len)])
+    }
+
+    pub fn iter(&self) -> std::iter::StepBy<std::slice::Iter<'a, T>> {
+        self.data[span(self.start, self.stride, self.len)]
+            .iter()
+            .step_by(self.stride)
+    }
+}
+
+impl<'a, T> IntoIterator for StridedView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = std::iter::StepBy<std::slice::Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> BufferAxis<T> for StridedView<'a, T> {
+    fn get_sample(&self, index: usize) -> Option<&T> {
+        self.get(index)
+    }
+}
+
+/// The mutable counterpart of `StridedView`
+#[derive(Debug)]
+pub struct StridedViewMut<'a, T> {
+    data: &'a mut [T],
+    start: usize,
+    stride: usize,
+    len: usize,
+}
+
+impl<'a, T> StridedViewMut<'a, T> {
+    /// # Panics
+    /// If `stride` is zero or the last element is out of bounds
+    pub fn new(data: &'a mut [T], start: usize, stride: usize, len: usize) -> Self {
+        assert!(stride > 0, "stride must not be zero");
+        assert!(
+            len == 0 || start + (len - 1) * stride < data.len(),
+            "view is out of bounds"
+        );
+        Self {
+            data,
+            start: if len == 0 { 0 } else { start },
+            stride,
+            len,
+        }
+    }
+
+    /// The channel of interleaved samples
+    pub fn channel(data: &'a mut [T], channels: usize, channel: usize) -> Self {
+        let len = data.len() / channels;
+        Self::new(data, channel, channels, len)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            self.data.get(self.start + index * self.stride)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            self.data.get_mut(self.start + index * self.stride)
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> std::iter::StepBy<std::slice::Iter<'_, T>> {
+        self.data[span(self.start, self.stride, self.len)]
+            .iter()
+            .step_by(self.stride)
+    }
+
+    pub fn iter_mut(&mut self) -> std::iter::StepBy<std::slice::IterMut<'_, T>> {
+        self.data[span(self.start, self.stride, self.len)]
+            .iter_mut()
+            .step_by(self.stride)
+    }
+}
+
+impl<'a, T> IntoIterator for StridedViewMut<'a, T> {
+    type Item = &'a mut T;
+    type IntoIter = std::iter::StepBy<std::slice::IterMut<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data[span(self.start, self.stride, self.len)]
+            .iter_mut()
+            .step_by(self.stride)
+    }
+}
+
+impl<'a, T> BufferAxis<T> for StridedViewMut<'a, T> {
+    fn get_sample(&self, index: usize) -> Option<&T> {
+        self.get(index)
+    }
+}
+
+impl<'a, T> BufferAxisMut<'a, T> for StridedViewMut<'a, T> {
+    fn get_sample_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.get_mut(index)
+    }
+
+    fn iter_samples_mut<'s>(&'s mut self) -> impl Iterator<Item = &'s mut T>
+    where
+        T: 's,
+    {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buffers::view::{StridedView, StridedViewMut},
+        core::axis::BufferAxisMut,
+    };
+
+    #[test]
+    fn strided_views_visit_every_stride() {
+        let mut data = [0, 1, 2, 3, 4, 5, 6, 7];
+
+        let view = StridedView::channel(&data, 3, 1);
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.iter().copied().collect::<Vec<_>>(), [1, 4]);
+        assert_eq!((view.get(1), view.get(2)), (Some(&4), None));
+
+        let mut view = StridedViewMut::new(&mut data, 2, 2, 3);
+        for sample in view.iter_samples_mut() {
+            *sample *= 10;
+        }
+        *view.get_mut(0).unwrap() += 1;
+        assert!(view.get_mut(3).is_none());
+        assert_eq!(data, [0, 1, 21, 3, 40, 5, 60, 7]);
+
+        let empty = StridedView::new(&data[..0], 5, 2, 0);
+        assert_eq!(empty.iter().count(), 0);
+    }
+}
@@ -38,6 +38,9 @@ where
 
 pub trait BufferAxisMut<'a, T>: BufferAxis<T> {
     fn get_sample_mut(&mut self, index: usize) -> Option<&mut T>;
+    fn iter_samples_mut<'s>(&'s mut self) -> impl Iterator<Item = &'s mut T>
+    where
+        T: 's;
     fn map_samples_mut<F, R>(&mut self, mut f: F, offset: Option<usize>)
     where
         F: for<'sample> FnMut(&'sample mut T, usize) -> Option<R>,
@@ -70,4 +73,11 @@ where
     fn get_sample_mut(&mut self, index: usize) -> Option<&mut T> {
         self.as_mut().get_mut(index)
     }
+
+    fn iter_samples_mut<'s>(&'s mut self) -> impl Iterator<Item = &'s mut T>
+    where
+        T: 's,
+    {
+        self.as_mut().iter_mut()
+    }
 }
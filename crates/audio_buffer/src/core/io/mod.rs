@@ -5,11 +5,12 @@ use crate::core::{
     axis::{BufferAxis, BufferAxisMut},
     io::error::IoError,
 };
+use crate::kernels;
 
 pub mod error;
 pub mod writer;
 
-pub fn mix_buffers<T: Sample, I: Buffer<Sample = T>, O: BufferMut<Sample = T>>(
+pub fn mix_buffers<T: Sample + 'static, I: Buffer<Sample = T>, O: BufferMut<Sample = T>>(
     input: &I,
     output: &mut O,
     output_offset: Option<usize>,
@@ -21,29 +22,39 @@ pub fn mix_buffers<T: Sample, I: Buffer<Sample = T>, O: BufferMut<Sample = T>>(
         ));
     }
 
+    let channels = output.channels();
+    if let (Some(input), Some(output)) = (input.as_interleaved(), output.as_interleaved_mut()) {
+        let start = output_offset.unwrap_or(0);
+        let frames = (input.len().min(output.len()) / channels).saturating_sub(start);
+        let samples = start * channels..(start + frames) * channels;
+        kernels::mix(&mut output[samples.clone()], &input[samples]);
+        return Ok(frames);
+    }
+
     let mut written = 0;
 
     output.map_frames_mut(
         |mut out_frame, frame_index| -> Option<()> {
             match input.get_frame(frame_index) {
                 Some(in_frame) => {
-                    let result = Some(out_frame.map_samples_mut(|out_sample, sample_index| {
-                    match in_frame.get_sample(sample_index) {
-                        Some(in_sample) => {
-                            *out_sample =
-                                out_sample.add_amp(dasp::Sample::to_signed_sample(*in_sample));
-                            Some(())
-                        }
-                        None => {
-                            panic!(
-                                "channel mismatch between input and output buffers must not occur"
-                            )
-                        }
-                    }
-                }, None));
+                    out_frame.map_samples_mut(
+                        |out_sample, sample_index| match in_frame.get_sample(sample_index) {
+                            Some(in_sample) => {
+                                *out_sample =
+                                    out_sample.add_amp(dasp::Sample::to_signed_sample(*in_sample));
+                                Some(())
+                            }
+                            None => {
+                                panic!(
+                                    "channel mismatch between input and output buffers must not occur"
+                                )
+                            }
+                        },
+                        None,
+                    );
 
                     written += 1;
-                    result
+                    Some(())
                 }
                 None => None,
             }
@@ -53,3 +64,64 @@ pub fn mix_buffers<T: Sample, I: Buffer<Sample = T>, O: BufferMut<Sample = T>>(
 
     Ok(written)
 }
+
+/// Mixes at most `frames` frames of `input`, starting at frame `input_offset`, into
+/// `output` starting at frame `output_offset`. Returns the number of frames mixed,
+/// which is less than `frames` if either buffer ends first.
+pub fn mix_buffers_region<T: Sample + 'static, I: Buffer<Sample = T>, O: BufferMut<Sample = T>>(
+    input: &I,
+    input_offset: usize,
+    output: &mut O,
+    output_offset: usize,
+    frames: usize,
+) -> Result<usize, IoError> {
+    if input.channels() != output.channels() {
+        return Err(IoError::ChannelMismatch(
+            output.channels(),
+            input.channels(),
+        ));
+    }
+
+    let channels = output.channels();
+    if let (Some(input), Some(output)) = (input.as_interleaved(), output.as_interleaved_mut()) {
+        let frames = frames
+            .min((input.len() / channels).saturating_sub(input_offset))
+            .min((output.len() / channels).saturating_sub(output_offset));
+        kernels::mix(
+            &mut output[output_offset * channels..(output_offset + frames) * channels],
+            &input[input_offset * channels..(input_offset + frames) * channels],
+        );
+        return Ok(frames);
+    }
+
+    let mut written = 0;
+
+    output.map_frames_mut(
+        |mut out_frame, frame_index| -> Option<()> {
+            if written >= frames {
+                return None;
+            }
+
+            let in_frame = input.get_frame(input_offset + frame_index - output_offset)?;
+            out_frame.map_samples_mut(
+                |out_sample, sample_index| match in_frame.get_sample(sample_index) {
+                    Some(in_sample) => {
+                        *out_sample =
+                            out_sample.add_amp(dasp::Sample::to_signed_sample(*in_sample));
+                        Some(())
+                    }
+                    None => {
+                        panic!("channel mismatch between input and output buffers must not occur")
+                    }
+                },
+                None,
+            );
+
+            written += 1;
+            Some(())
+        },
+        Some(output_offset),
+    );
+
+    Ok(written)
+}
@@ -6,6 +6,7 @@ use crate::core::{
     axis::{BufferAxis, BufferAxisMut},
     io::error::IoError,
 };
+use crate::kernels;
 
 pub struct Writer<'a, T: dasp::Sample + 'static, B: BufferMut<Sample = T>> {
     buffer: &'a mut B,
@@ -35,26 +36,35 @@ impl<'a, T: dasp::Sample + 'static, B: BufferMut<Sample = T>> Writer<'a, T, B> {
             ));
         }
 
+        if let Some((output, input, frames)) = self.interleaved(input) {
+            kernels::copy(output, input);
+            self.position += frames;
+            return Ok(frames);
+        }
+
         let mut written = 0;
 
         self.buffer.map_frames_mut(
             |mut out_frame, frame_index| -> Option<()> {
                 match input.get_frame(frame_index - self.position) {
                     Some(in_frame) => {
-                        let result = Some(out_frame.map_samples_mut(|out_sample, sample_index| {
-                            match in_frame.get_sample(sample_index) {
+                        out_frame.map_samples_mut(
+                            |out_sample, sample_index| match in_frame.get_sample(sample_index) {
                                 Some(in_sample) => {
                                     *out_sample = *in_sample;
                                     Some(())
                                 }
                                 None => {
-                                    unreachable!("channel mismatch between input and output buffers must not occur")
+                                    unreachable!(
+                                        "channel mismatch between input and output buffers must not occur"
+                                    )
                                 }
-                        }
-                    }, None));
+                            },
+                            None,
+                        );
 
                         written += 1;
-                        result
+                        Some(())
                     }
                     None => None,
                 }
@@ -66,6 +76,24 @@ impl<'a, T: dasp::Sample + 'static, B: BufferMut<Sample = T>> Writer<'a, T, B> {
         Ok(written)
     }
 
+    // The overlapping samples of this buffer from the current position and `input`,
+    // if both are stored interleaved
+    fn interleaved<'s, I: Buffer<Sample = T>>(
+        &'s mut self,
+        input: &'s I,
+    ) -> Option<(&'s mut [T], &'s [T], usize)> {
+        let channels = input.channels();
+        let input = input.as_interleaved()?;
+        let output = self.buffer.as_interleaved_mut()?;
+        let start = self.position * channels;
+        let samples = input.len().min(output.len().saturating_sub(start));
+        Some((
+            &mut output[start..start + samples],
+            &input[..samples],
+            samples / channels,
+        ))
+    }
+
     pub fn mix_block_remaining<I: Buffer<Sample = T>>(
         &mut self,
         input: &I,
@@ -76,29 +104,36 @@ impl<'a, T: dasp::Sample + 'static, B: BufferMut<Sample = T>> Writer<'a, T, B> {
                 input.channels(),
             ));
         }
+        if let Some((output, input, frames)) = self.interleaved(input) {
+            kernels::mix(output, input);
+            self.position += frames;
+            return Ok(frames);
+        }
+
         let mut written = 0;
 
         self.buffer.map_frames_mut(
             |mut out_frame, frame_index| -> Option<()> {
                 match input.get_frame(frame_index - self.position) {
                     Some(in_frame) => {
-                        let result = Some(out_frame.map_samples_mut(|out_sample, sample_index| {
-                    match in_frame.get_sample(sample_index) {
-                        Some(in_sample) => {
-                            *out_sample =
-                                out_sample.add_amp(dasp::Sample::to_signed_sample(*in_sample));
-                            Some(())
-                        }
-                        None => {
-                            panic!(
-                                "channel mismatch between input and output buffers must not occur"
-                            )
-                        }
-                    }
-                }, None));
+                        out_frame.map_samples_mut(
+                            |out_sample, sample_index| match in_frame.get_sample(sample_index) {
+                                Some(in_sample) => {
+                                    *out_sample =
+                                        out_sample.add_amp(dasp::Sample::to_signed_sample(*in_sample));
+                                    Some(())
+                                }
+                                None => {
+                                    panic!(
+                                        "channel mismatch between input and output buffers must not occur"
+                                    )
+                                }
+                            },
+                            None,
+                        );
 
                         written += 1;
-                        result
+                        Some(())
                     }
                     None => None,
                 }
@@ -36,16 +36,30 @@ pub trait Buffer {
     fn frames(&self) -> usize {
         self.samples() / self.channels()
     }
+
+    /// The samples as one interleaved slice, if they are stored that way.
+    /// Lets mixing and copying skip the per frame access.
+    fn as_interleaved(&self) -> Option<&[Self::Sample]> {
+        None
+    }
 }
 
-pub trait BufferMut: Buffer {
-    type FrameMut<'this>: BufferAxisMut<'this, Self::Sample>
-    where
-        Self: 'this;
-    type ChannelMut<'this>: BufferAxisMut<'this, Self::Sample>
-    where
-        Self: 'this;
+/// The mutable frame and channel of a `BufferMut` that is borrowed for `'this`.
+///
+/// These would be generic associated types of `BufferMut`, but its closures are
+/// higher-ranked over `'this` and a `Self: 'this` bound on a GAT can then only be
+/// met by `'static` buffers. The defaulted parameter implies that bound inside of
+/// every impl instead, so buffers that borrow their samples can name the lifetimes
+/// of those borrows in their frames.
+pub trait BufferMutTypes<'this, ImpliedBound = &'this Self>: Buffer {
+    type FrameMut: BufferAxisMut<'this, Self::Sample>;
+    type ChannelMut: BufferAxisMut<'this, Self::Sample>;
+}
+
+pub type FrameMut<'this, B> = <B as BufferMutTypes<'this>>::FrameMut;
+pub type ChannelMut<'this, B> = <B as BufferMutTypes<'this>>::ChannelMut;
 
+pub trait BufferMut: Buffer + for<'this> BufferMutTypes<'this> {
     /// Takes a closure that gets an exclusive borrow to a frame passed to it.
     /// Returning `None` indicates that the index was out of bounds.
     // SAFETY: this is safe because
@@ -55,32 +69,33 @@ pub trait BufferMut: Buffer {
     // This does not compile!
     // ```rust
     //    let mut buffer = FixedFrameBuffer {
-    //      data: vec![[0.; 256]],
+    //      data: [[0.; 256]],
     //      sample_rate: 44_100,
     //    };
     //    let mut view = buffer.with_frame_mut(0, |view| view).unwrap();
     // ```
     fn with_frame_mut<'s, F, R>(&'s mut self, index: usize, f: F) -> Option<R>
     where
-        F: for<'this> FnOnce(Self::FrameMut<'this>) -> R,
+        F: for<'this> FnOnce(FrameMut<'this, Self>) -> R,
         R: 's;
 
     fn with_channel_mut<'s, F, R>(&'s mut self, index: usize, f: F) -> Option<R>
     where
-        F: for<'this> FnOnce(Self::ChannelMut<'this>) -> R,
+        F: for<'this> FnOnce(ChannelMut<'this, Self>) -> R,
         R: 's;
 
     /// Apply a function to all frames in the buffer mutably. Returning
     /// `None` from this function instantly breaks out of the mapping loop.
     fn map_frames_mut<F, R>(&mut self, mut f: F, offset: Option<usize>)
     where
-        F: for<'frame> FnMut(Self::FrameMut<'frame>, usize) -> Option<R>,
+        F: for<'frame> FnMut(FrameMut<'frame, Self>, usize) -> Option<R>,
     {
         let num_frames = self.frames();
         for index in offset.unwrap_or(0)..num_frames {
-            if let None = self
+            if self
                 .with_frame_mut(index, |frame| f(frame, index))
                 .expect("index is never out of bounds")
+                .is_none()
             {
                 break;
             };
@@ -89,13 +104,14 @@ pub trait BufferMut: Buffer {
 
     fn map_channels_mut<F, R>(&mut self, mut f: F, offset: Option<usize>)
     where
-        F: for<'channel> FnMut(Self::ChannelMut<'channel>, usize) -> Option<R>,
+        F: for<'channel> FnMut(ChannelMut<'channel, Self>, usize) -> Option<R>,
     {
         let num_channels = self.channels();
         for index in offset.unwrap_or(0)..num_channels {
-            if let None = self
+            if self
                 .with_channel_mut(index, |frame| f(frame, index))
                 .expect("index is never out of bounds")
+                .is_none()
             {
                 break;
             };
@@ -103,6 +119,11 @@ pub trait BufferMut: Buffer {
     }
 
     fn set_to_equilibrium(&mut self);
+
+    /// The mutable counterpart of `Buffer::as_interleaved`
+    fn as_interleaved_mut(&mut self) -> Option<&mut [Self::Sample]> {
+        None
+    }
 }
 
 pub trait TemporalBuffer: Buffer {
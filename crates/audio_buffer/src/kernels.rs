@@ -0,0 +1,220 @@
+//! Kernels for the hot paths of mixing contiguous samples. `f32` and `f64` are
+//! processed in fixed size lanes that the compiler turns into SIMD instructions,
+//! every other sample type goes through the generic `dasp::Sample` arithmetic.
+//!
+//! The kernels taking an input panic if it isn't as long as the output.
+
+use std::{
+    any::TypeId,
+    ops::{Add, Mul},
+};
+
+use dasp::Sample;
+
+const LANES: usize = 8;
+
+/// Adds `input` to `output`
+pub fn mix<T: Sample + 'static>(output: &mut [T], input: &[T]) {
+    assert_eq!(output.len(), input.len(), "input and output lengths differ");
+
+    if let Some((output, input)) = as_float::<T, f32>(output, input) {
+        lanes::mix(output, input);
+    } else if let Some((output, input)) = as_float::<T, f64>(output, input) {
+        lanes::mix(output, input);
+    } else {
+        for (out, &sample) in output.iter_mut().zip(input) {
+            *out = out.add_amp(sample.to_signed_sample());
+        }
+    }
+}
+
+/// Adds `input` scaled by `gain` to `output`
+pub fn mix_with_gain<T: Sample + 'static>(output: &mut [T], input: &[T], gain: f32) {
+    mix_with_gain_ramp(output, input, gain, gain);
+}
+
+/// Adds `input` to `output`, scaled by a gain that moves linearly from `start`
+/// towards `end` over the length of the output
+pub fn mix_with_gain_ramp<T: Sample + 'static>(
+    output: &mut [T],
+    input: &[T],
+    start: f32,
+    end: f32,
+) {
+    assert_eq!(output.len(), input.len(), "input and output lengths differ");
+    let step = (end - start) / output.len().max(1) as f32;
+
+    if let Some((output, input)) = as_float::<T, f32>(output, input) {
+        lanes::mix_with_gain_ramp(output, input, start, step);
+    } else if let Some((output, input)) = as_float::<T, f64>(output, input) {
+        lanes::mix_with_gain_ramp(output, input, start as f64, step as f64);
+    } else {
+        for (index, (out, &sample)) in output.iter_mut().zip(input).enumerate() {
+            let gain = start + step * index as f32;
+            let sample = sample.to_float_sample().mul_amp(gain.to_sample());
+            *out = out.add_amp(sample.to_sample::<T>().to_signed_sample());
+        }
+    }
+}
+
+/// Scales `output` by `gain`
+pub fn apply_gain<T: Sample + 'static>(output: &mut [T], gain: f32) {
+    apply_gain_ramp(output, gain, gain);
+}
+
+/// Scales `output` by a gain that moves linearly from `start` towards `end`
+pub fn apply_gain_ramp<T: Sample + 'static>(output: &mut [T], start: f32, end: f32) {
+    let step = (end - start) / output.len().max(1) as f32;
+
+    if let Some(output) = cast_mut::<T, f32>(output) {
+        lanes::apply_gain_ramp(output, start, step);
+    } else if let Some(output) = cast_mut::<T, f64>(output) {
+        lanes::apply_gain_ramp(output, start as f64, step as f64);
+    } else {
+        for (index, sample) in output.iter_mut().enumerate() {
+            let gain = start + step * index as f32;
+            *sample = sample.mul_amp(gain.to_sample());
+        }
+    }
+}
+
+/// Copies `input` to `output`. The standard library already vectorizes this
+/// for every sample type, it is here so all hot paths go through this module.
+pub fn copy<T: Copy>(output: &mut [T], input: &[T]) {
+    output.copy_from_slice(input);
+}
+
+/// Sets every sample of `output` to `value`, see `copy`
+pub fn fill<T: Copy>(output: &mut [T], value: T) {
+    output.fill(value);
+}
+
+// Reinterprets the samples as `F` if that is what `T` is
+fn cast_mut<T: 'static, F: 'static>(samples: &mut [T]) -> Option<&mut [F]> {
+    // SAFETY: `T` and `F` are the same type
+    (TypeId::of::<T>() == TypeId::of::<F>())
+        .then(|| unsafe { &mut *(samples as *mut [T] as *mut [F]) })
+}
+
+fn as_float<'a, T: 'static, F: 'static>(
+    output: &'a mut [T],
+    input: &'a [T],
+) -> Option<(&'a mut [F], &'a [F])> {
+    let output = cast_mut(output)?;
+    // SAFETY: `cast_mut` only succeeds if `T` and `F` are the same type
+    let input = unsafe { &*(input as *const [T] as *const [F]) };
+    Some((output, input))
+}
+
+trait Float: Copy + Add<Output = Self> + Mul<Output = Self> {
+    fn from_index(index: usize) -> Self;
+}
+
+impl Float for f32 {
+    fn from_index(index: usize) -> Self {
+        index as f32
+    }
+}
+
+impl Float for f64 {
+    fn from_index(index: usize) -> Self {
+        index as f64
+    }
+}
+
+// Each kernel walks whole lanes of `LANES` samples, whose fixed length lets the
+// compiler vectorize the inner loop, and finishes the remainder one at a time.
+// Ramps keep one gain per lane and advance all of them at once, so that no
+// lane depends on the previous one.
+mod lanes {
+    use super::{Float, LANES};
+
+    pub(super) fn mix<F: Float>(output: &mut [F], input: &[F]) {
+        let (output_lanes, output_rest) = output.as_chunks_mut::<LANES>();
+        let (input_lanes, input_rest) = input.as_chunks::<LANES>();
+
+        for (out, samples) in output_lanes.iter_mut().zip(input_lanes) {
+            for lane in 0..LANES {
+                out[lane] = out[lane] + samples[lane];
+            }
+        }
+        for (out, &sample) in output_rest.iter_mut().zip(input_rest) {
+            *out = *out + sample;
+        }
+    }
+
+    pub(super) fn mix_with_gain_ramp<F: Float>(output: &mut [F], input: &[F], start: F, step: F) {
+        let (output_lanes, output_rest) = output.as_chunks_mut::<LANES>();
+        let (input_lanes, input_rest) = input.as_chunks::<LANES>();
+        let (mut gains, advance) = ramp(start, step);
+
+        for (out, samples) in output_lanes.iter_mut().zip(input_lanes) {
+            for lane in 0..LANES {
+                out[lane] = out[lane] + samples[lane] * gains[lane];
+                gains[lane] = gains[lane] + advance;
+            }
+        }
+        for ((out, &sample), gain) in output_rest.iter_mut().zip(input_rest).zip(gains) {
+            *out = *out + sample * gain;
+        }
+    }
+
+    pub(super) fn apply_gain_ramp<F: Float>(output: &mut [F], start: F, step: F) {
+        let (output_lanes, output_rest) = output.as_chunks_mut::<LANES>();
+        let (mut gains, advance) = ramp(start, step);
+
+        for out in output_lanes {
+            for (sample, gain) in out.iter_mut().zip(&mut gains) {
+                *sample = *sample * *gain;
+                *gain = *gain + advance;
+            }
+        }
+        for (sample, gain) in output_rest.iter_mut().zip(gains) {
+            *sample = *sample * gain;
+        }
+    }
+
+    // The gains of the first chunk and how much they grow from one chunk to the next
+    fn ramp<F: Float>(start: F, step: F) -> ([F; LANES], F) {
+        let gains = std::array::from_fn(|lane| start + step * F::from_index(lane));
+        (gains, step * F::from_index(LANES))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dasp::Sample;
+
+    use crate::kernels::{apply_gain, apply_gain_ramp, mix, mix_with_gain, mix_with_gain_ramp};
+
+    #[test]
+    fn float_kernels_match_the_generic_path() {
+        // long enough to have whole lanes and a remainder
+        let input: Vec<f32> = (0..19).map(|i| i as f32 / 38.0).collect();
+        let mut output = vec![0.25f32; input.len()];
+        let generic_input: Vec<i16> = input.iter().map(|s| s.to_sample()).collect();
+        let mut generic = vec![0.25f32.to_sample::<i16>(); input.len()];
+
+        mix_with_gain_ramp(&mut output, &input, 1.0, 0.0);
+        mix_with_gain_ramp(&mut generic, &generic_input, 1.0, 0.0);
+
+        for (index, (&float, &int)) in output.iter().zip(&generic).enumerate() {
+            let gain = 1.0 - index as f32 / 19.0;
+            assert!((float - (0.25 + input[index] * gain)).abs() < 1e-6);
+            assert!((int.to_sample::<f32>() - float).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn gains_scale_and_sum() {
+        let mut output = [1.0f64; 10];
+        mix(&mut output, &[0.5; 10]);
+        mix_with_gain(&mut output, &[1.0; 10], 0.5);
+        assert!(output.iter().all(|&s| s == 2.0));
+
+        apply_gain(&mut output, 0.5);
+        apply_gain_ramp(&mut output, 0.0, 1.0);
+        assert_eq!(output[0], 0.0);
+        assert!((output[5] - 0.5).abs() < 1e-6);
+    }
+}
@@ -3,6 +3,7 @@ pub use symphonia;
 
 pub mod buffers;
 pub mod core;
+pub mod kernels;
 
 pub trait SharedSample: dasp::Sample + Send + Sync + 'static {}
 impl<T> SharedSample for T where T: dasp::Sample + Send + Sync + 'static {}
@@ -23,7 +23,7 @@ pub fn load<T: ConvertibleSample + dasp::Sample + 'static>(
 
     let mut decoder = symphonia::default::get_codecs()
         .make(&track.codec_params, &DecoderOptions::default())
-        .map_err(|e| LoadError::CouldNotCreateDecoder(e))?;
+        .map_err(LoadError::CouldNotCreateDecoder)?;
 
     let track_id = track.id;
     let mut sample_buffer = None;
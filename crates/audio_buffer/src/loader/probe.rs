@@ -25,10 +25,10 @@ pub fn probe_file<P: AsRef<Path>>(
     let file = File::open(path)?;
     let mut hint = Hint::new();
 
-    if let Some(extension) = path.extension() {
-        if let Some(extension_str) = extension.to_str() {
-            hint.with_extension(extension_str);
-        }
+    if let Some(extension) = path.extension()
+        && let Some(extension_str) = extension.to_str()
+    {
+        hint.with_extension(extension_str);
     }
 
     probe_audio_source(Box::new(file), Some(hint), probe)
@@ -48,14 +48,14 @@ pub fn probe_audio_source(
 
     let probed = probe
         .format(&hint, mss, &format_opts, &metadata_opts)
-        .map_err(|e| LoadError::UnkownFormat(e))?;
+        .map_err(LoadError::UnkownFormat)?;
 
     let track = probed
         .format
         .default_track()
         .ok_or_else(|| LoadError::NoTrackFound)?;
 
-    let sample_rate = track.codec_params.sample_rate.unwrap_or_else(|| {
+    let sample_rate = track.codec_params.sample_rate.unwrap_or({
         // log::warn!("Could not find sample rate of PCM resource. Assuming a sample rate of 44100");
         44100
     });
@@ -3,17 +3,46 @@ use std::{
     num::NonZero,
 };
 
-use audio_buffer::{buffers::interleaved::InterleavedBuffer, core::Buffer};
+use audio_buffer::{
+    buffers::{interleaved::InterleavedBuffer, planar::PlanarBuffer},
+    core::BufferMut,
+};
 use time::FrameTime;
 
-pub struct BufferArena<T> {
+/// A buffer the arena can allocate
+pub trait PooledBuffer: BufferMut {
+    fn with_shape(channels: NonZero<usize>, buffer_size: FrameTime) -> Self;
+}
+
+impl<T: audio_buffer::dasp::Sample + 'static> PooledBuffer for InterleavedBuffer<T> {
+    fn with_shape(channels: NonZero<usize>, buffer_size: FrameTime) -> Self {
+        InterleavedBuffer::with_shape(channels, buffer_size)
+    }
+}
+
+impl<T: audio_buffer::dasp::Sample + 'static> PooledBuffer for PlanarBuffer<T> {
+    fn with_shape(channels: NonZero<usize>, buffer_size: FrameTime) -> Self {
+        PlanarBuffer::with_shape(channels, buffer_size)
+    }
+}
+
+pub struct BufferArena<B> {
     // (channels, buffer_size)
-    free: HashMap<(usize, FrameTime), VecDeque<InterleavedBuffer<T>>>,
+    free: HashMap<(usize, FrameTime), VecDeque<B>>,
 }
 
-impl<T> BufferArena<T>
+impl<B> Default for BufferArena<B>
 where
-    T: audio_buffer::dasp::Sample,
+    B: PooledBuffer,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> BufferArena<B>
+where
+    B: PooledBuffer,
 {
     pub fn new() -> Self {
         Self {
@@ -23,16 +52,12 @@ where
 
     pub fn allocate_buffer(&mut self, channels: usize, buffer_size: FrameTime) {
         match self.free.get_mut(&(channels, buffer_size)) {
-            Some(queue) => queue.push_front(InterleavedBuffer::with_shape(
-                NonZero::new(channels).unwrap(),
-                buffer_size,
-            )),
+            Some(queue) => {
+                queue.push_front(B::with_shape(NonZero::new(channels).unwrap(), buffer_size))
+            }
             None => {
                 let mut queue = VecDeque::new();
-                queue.push_front(InterleavedBuffer::with_shape(
-                    NonZero::new(channels).unwrap(),
-                    buffer_size,
-                ));
+                queue.push_front(B::with_shape(NonZero::new(channels).unwrap(), buffer_size));
                 self.free.insert((channels, buffer_size), queue);
             }
         }
@@ -40,7 +65,7 @@ where
 
     pub fn ensure_capacity(&mut self, channels: usize, buffer_size: FrameTime, amount: usize) {
         let required = match self.free.get(&(channels, buffer_size)) {
-            Some(queue) => amount - queue.len(),
+            Some(queue) => amount.saturating_sub(queue.len()),
             None => amount,
         };
 
@@ -49,21 +74,14 @@ where
         }
     }
 
-    pub fn take(
-        &mut self,
-        channels: usize,
-        buffer_size: FrameTime,
-    ) -> Option<InterleavedBuffer<T>> {
+    pub fn take(&mut self, channels: usize, buffer_size: FrameTime) -> Option<B> {
         match self.free.get_mut(&(channels, buffer_size)) {
-            Some(queue) => match queue.pop_front() {
-                Some(buffer) => Some(buffer),
-                None => None,
-            },
+            Some(queue) => queue.pop_front(),
             None => None,
         }
     }
 
-    pub fn release(&mut self, buffer: InterleavedBuffer<T>) {
+    pub fn release(&mut self, buffer: B) {
         let num_channels = buffer.channels();
         let size = buffer.frames();
         match self.free.get_mut(&(num_channels, size.into())) {
@@ -1,7 +1,7 @@
 use daggy::{NodeIndex, WouldCycle};
 use thiserror::Error;
 
-use crate::Connection;
+use crate::{Connection, Tap};
 
 #[derive(Error, Debug)]
 pub enum GraphError {
@@ -13,6 +13,8 @@ pub enum GraphError {
     WouldInvalidPinMatrix,
     #[error("")]
     WouldDanglingNodeInConnection,
+    #[error("The source doesn't have the output {0:?}")]
+    WouldInvalidTap(Tap),
 }
 
 #[derive(Error, Debug)]
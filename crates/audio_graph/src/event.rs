@@ -0,0 +1,53 @@
+/// A MIDI channel message. Channels, keys, velocities and values
+/// use their 7-bit MIDI ranges, channels count from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    /// Releases every sounding note, e.g. when playback stops or jumps
+    AllNotesOff,
+}
+
+impl MidiMessage {
+    // events at the same offset are ordered so a note can be retriggered
+    fn order(&self) -> u8 {
+        match self {
+            MidiMessage::AllNotesOff => 0,
+            MidiMessage::NoteOff { .. } => 1,
+            MidiMessage::ControlChange { .. } => 2,
+            MidiMessage::NoteOn { .. } => 3,
+        }
+    }
+}
+
+/// A message that takes effect `offset` frames into the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiEvent {
+    pub offset: usize,
+    pub message: MidiMessage,
+}
+
+impl MidiEvent {
+    pub fn new(offset: usize, message: MidiMessage) -> Self {
+        Self { offset, message }
+    }
+}
+
+/// Sorts events by their offset. At the same offset note offs come
+/// before control changes, which come before note ons. Doesn't allocate.
+pub fn sort_events(events: &mut [MidiEvent]) {
+    events.sort_unstable_by_key(|event| (event.offset, event.message.order()));
+}
@@ -0,0 +1,116 @@
+use std::num::NonZeroUsize;
+
+use audio_buffer::{
+    buffers::interleaved::InterleavedBuffer,
+    core::{Buffer, BufferMut, ResizableBuffer},
+    dasp,
+};
+use time::FrameTime;
+
+/// Delays the output of a node on its way to a child, so it lines
+/// up with other parents of the child that have more latency.
+pub(crate) struct DelayLine<T> {
+    frames: usize,
+    // interleaved samples waiting to be output
+    history: Vec<T>,
+    position: usize,
+    output: InterleavedBuffer<T>,
+}
+
+impl<T> DelayLine<T>
+where
+    T: dasp::Sample + 'static,
+{
+    pub(crate) fn new(channels: NonZeroUsize, frames: usize, block_size: FrameTime) -> Self {
+        Self {
+            frames,
+            history: vec![T::EQUILIBRIUM; frames * channels.get()],
+            position: 0,
+            output: InterleavedBuffer::with_shape(channels, block_size),
+        }
+    }
+
+    pub(crate) fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns `input` delayed by the length of the line
+    pub(crate) fn process(&mut self, input: &InterleavedBuffer<T>) -> &InterleavedBuffer<T> {
+        if self.output.frames() != input.frames() {
+            self.output.resize(input.frames());
+        }
+
+        let channels = input.channels();
+        let frames = self.frames;
+        let history = &mut self.history;
+        let position = &mut self.position;
+
+        self.output.map_frames_mut(
+            |frame, index| {
+                let input = input.get_frame(index)?;
+                let delayed = &mut history[*position * channels..(*position + 1) * channels];
+                for ((out, &sample), delayed) in frame.iter_mut().zip(input).zip(delayed) {
+                    *out = std::mem::replace(delayed, sample);
+                }
+                *position = (*position + 1) % frames;
+                Some(())
+            },
+            None,
+        );
+
+        &self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, num::NonZeroUsize};
+
+    use audio_buffer::{
+        buffers::interleaved::InterleavedBuffer,
+        core::{Buffer, BufferMut},
+    };
+    use time::{FrameTime, SampleRate};
+
+    use crate::{
+        AudioGraph,
+        pin_matrix::PinMatrix,
+        processor::{AudioProcessor, PassThrough, limiter::Limiter},
+    };
+
+    #[test]
+    fn parallel_paths_are_aligned() {
+        let sample_rate = SampleRate::new(48_000.0);
+        let (mut graph, input) = AudioGraph::<f32, Box<dyn AudioProcessor<f32>>>::new(
+            Box::new(PassThrough::new(1, 1)),
+            sample_rate,
+            FrameTime(512),
+        );
+        let limiter = graph.add_node(Box::new(Limiter::new(sample_rate, 1).with_ceiling(0.0)));
+        let output = graph.add_node(Box::new(PassThrough::new(1, 1)));
+        graph.set_output_index(output).unwrap();
+        graph
+            .add_connection(input, limiter, PinMatrix::diagonal(1, 1))
+            .unwrap();
+        graph
+            .add_connection(limiter, output, PinMatrix::diagonal(1, 1))
+            .unwrap();
+        graph
+            .add_connection(input, output, PinMatrix::diagonal(1, 1))
+            .unwrap();
+
+        let latency = graph.latency();
+        assert!(latency > 0);
+
+        let channels = NonZeroUsize::new(1).unwrap();
+        let mut impulse = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(512));
+        impulse.with_frame_mut(0, |frame| frame[0] = 0.25);
+        let mut result = InterleavedBuffer::with_shape(channels, FrameTime(512));
+        graph.process_block(&HashMap::from([(input, &impulse)]), &mut result.wrap_mut());
+
+        for (index, frame) in result.iter_frames().enumerate() {
+            let expected = if index == latency { 0.5 } else { 0.0 };
+            assert_eq!(frame[0], expected, "frame {index}");
+        }
+    }
+}
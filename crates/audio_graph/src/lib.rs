@@ -1,17 +1,23 @@
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 
+use audio_buffer::buffers::compatability::slice::WrapInterleavedMut;
 use audio_buffer::buffers::interleaved::InterleavedBuffer;
+use audio_buffer::buffers::planar::PlanarBuffer;
+use audio_buffer::buffers::view::StridedViewMut;
 use audio_buffer::core::Buffer;
 use audio_buffer::core::BufferMut;
-use audio_buffer::core::axis::BufferAxisMut;
+use audio_buffer::core::ResizableBuffer;
+use audio_buffer::core::io::mix_buffers;
 use audio_buffer::dasp;
-use daggy::petgraph::visit::IntoNeighbors;
+use audio_buffer::kernels;
 use daggy::{Dag, EdgeIndex, NodeIndex, Walker, petgraph};
 use time::FrameTime;
 use time::SampleRate;
 
 use crate::buffer_pool::BufferArena;
 use crate::error::GraphError;
+use crate::latency::DelayLine;
 use crate::pin_matrix::PinMatrix;
 use crate::processor::AudioProcessor;
 use crate::processor::ProcessorConfiguration;
@@ -20,11 +26,80 @@ pub use daggy;
 
 pub mod buffer_pool;
 pub mod error;
+pub mod event;
+mod latency;
+pub mod parameter;
 pub mod pin_matrix;
 pub mod processor;
+pub mod smoothing;
+
+/// Which output of the source node a connection reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tap {
+    /// The buffer the node processed into
+    #[default]
+    Main,
+    /// An additional output the node exposes through `AudioProcessor::auxiliary_output`,
+    /// e.g. the signal of a track before its fader
+    Auxiliary(usize),
+}
 
+/// An edge of the graph. The source's channels are routed through the matrix
+/// and scaled by the gain, which is ramped over a block when it changes.
+#[derive(Debug, Clone)]
 pub struct Connection {
     matrix: PinMatrix,
+    tap: Tap,
+    gain: f32,
+    // the gain at the end of the last processed block
+    applied_gain: f32,
+}
+
+impl Connection {
+    pub fn new(matrix: PinMatrix) -> Self {
+        Self {
+            matrix,
+            tap: Tap::Main,
+            gain: 1.0,
+            applied_gain: 1.0,
+        }
+    }
+
+    pub fn with_tap(mut self, tap: Tap) -> Self {
+        self.tap = tap;
+        self
+    }
+
+    /// Linear gain, starts without a ramp
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self.applied_gain = gain;
+        self
+    }
+
+    pub fn matrix(&self) -> &PinMatrix {
+        &self.matrix
+    }
+
+    pub fn tap(&self) -> Tap {
+        self.tap
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    // returns the gain at the start and the end of the next block
+    fn advance_gain(&mut self) -> (f32, f32) {
+        let start = std::mem::replace(&mut self.applied_gain, self.gain);
+        (start, self.gain)
+    }
+}
+
+impl From<PinMatrix> for Connection {
+    fn from(matrix: PinMatrix) -> Self {
+        Self::new(matrix)
+    }
 }
 
 // INVARIANT: "PinMatrix Validity"
@@ -41,16 +116,59 @@ where
 {
     dag: Dag<N, Connection>,
     execution_order: Vec<NodeIndex>,
-    buffer_arena: BufferArena<T>,
+    buffer_arena: BufferArena<InterleavedBuffer<T>>,
+    // inputs of the nodes that take planar input, see `AudioProcessor::planar_input`
+    planar_arena: BufferArena<PlanarBuffer<T>>,
 
     // stores cached buffer -> last consumer
     // this means that during processing a cached buffer
     // can be released once the last consumer has been
     // processed
     buffer_lifetimes: HashMap<NodeIndex, NodeIndex>,
+
+    // the latency each node's output lags behind the graph's input,
+    // edges from parents with less latency than others are delayed
+    latencies: HashMap<NodeIndex, usize>,
+    delay_lines: HashMap<EdgeIndex, DelayLine<T>>,
     block_size: FrameTime,
     sample_rate: SampleRate,
     output: NodeIndex,
+    // copies of the outputs of other nodes that are needed after the block,
+    // e.g. mixes that are played on other outputs of a device
+    secondary_outputs: HashMap<NodeIndex, InterleavedBuffer<T>>,
+}
+
+// the input a node is processed with
+enum NodeInput<T> {
+    // passed to `process_block` for the node
+    External,
+    // the mix of the node's parents
+    Interleaved(InterleavedBuffer<T>),
+    // the mix of the node's parents or its external input, deinterleaved
+    Planar(PlanarBuffer<T>),
+}
+
+// Adds the samples of a parent's channel to a mixed channel,
+// ramping the gain from the first to the second one
+fn mix_channel<'a, 'p, T>(
+    mixed: impl ExactSizeIterator<Item = &'a mut T>,
+    parent: impl IntoIterator<Item = &'p T>,
+    (start_gain, end_gain): (f32, f32),
+) where
+    T: dasp::Sample + 'a + 'p,
+{
+    let frames = mixed.len().max(1) as f32;
+    let unity = start_gain == 1.0 && end_gain == 1.0;
+
+    for (sample_index, (out_sample, &in_sample)) in mixed.zip(parent).enumerate() {
+        let in_sample = if unity {
+            in_sample
+        } else {
+            let gain = start_gain + (end_gain - start_gain) * sample_index as f32 / frames;
+            processor::from_f32(processor::to_f32(in_sample) * gain)
+        };
+        *out_sample = out_sample.add_amp(dasp::Sample::to_signed_sample(in_sample));
+    }
 }
 
 impl<T, N> AudioGraph<T, N>
@@ -63,10 +181,14 @@ where
             dag: Dag::new(),
             execution_order: vec![],
             buffer_arena: BufferArena::new(),
-            sample_rate: sample_rate,
+            planar_arena: BufferArena::new(),
+            sample_rate,
             block_size,
             output: 0.into(),
             buffer_lifetimes: HashMap::new(),
+            latencies: HashMap::new(),
+            delay_lines: HashMap::new(),
+            secondary_outputs: HashMap::new(),
         };
 
         let node_idx = graph.add_node(node);
@@ -93,6 +215,18 @@ where
         src: NodeIndex,
         dst: NodeIndex,
         pin_matrix: PinMatrix,
+    ) -> Result<EdgeIndex, GraphError> {
+        self.connect(src, dst, Connection::new(pin_matrix))
+    }
+
+    // Invalid States:
+    // - same as `add_connection`
+    // - the tap reads an auxiliary output the source doesn't have
+    pub fn connect(
+        &mut self,
+        src: NodeIndex,
+        dst: NodeIndex,
+        connection: Connection,
     ) -> Result<EdgeIndex, GraphError> {
         let src_node = self
             .dag
@@ -106,15 +240,18 @@ where
 
         let src_config = src_node.config();
         let dst_config = dst_node.config();
-        if !(pin_matrix.input_channels() == src_config.num_output_channels
-            && pin_matrix.output_channels() == dst_config.num_input_channels)
+        if !(connection.matrix.input_channels() == src_config.num_output_channels
+            && connection.matrix.output_channels() == dst_config.num_input_channels)
         {
             return Err(GraphError::WouldInvalidPinMatrix);
         }
+        if let Tap::Auxiliary(index) = connection.tap
+            && index >= src_node.auxiliary_outputs()
+        {
+            return Err(GraphError::WouldInvalidTap(connection.tap));
+        }
 
-        let edge_index = self
-            .dag
-            .add_edge(src, dst, Connection { matrix: pin_matrix })?;
+        let edge_index = self.dag.add_edge(src, dst, connection)?;
 
         self.recompute_execution_order();
         Ok(edge_index)
@@ -146,6 +283,26 @@ where
         }
     }
 
+    /// Changes the gain of a connection, ramping to it over the next block.
+    /// Returns the old gain.
+    pub fn set_connection_gain(&mut self, edge_index: EdgeIndex, gain: f32) -> Option<f32> {
+        let connection = self.dag.edge_weight_mut(edge_index)?;
+        Some(std::mem::replace(&mut connection.gain, gain))
+    }
+
+    /// Returns the old tap or `None` if the edge dangles or the source doesn't have the output
+    pub fn set_connection_tap(&mut self, edge_index: EdgeIndex, tap: Tap) -> Option<Tap> {
+        let (start, _) = self.dag.edge_endpoints(edge_index)?;
+        if let Tap::Auxiliary(index) = tap
+            && index >= self.dag.node_weight(start)?.auxiliary_outputs()
+        {
+            return None;
+        }
+
+        let connection = self.dag.edge_weight_mut(edge_index)?;
+        Some(std::mem::replace(&mut connection.tap, tap))
+    }
+
     // Invalid States:
     // - the execution order is outdated
     //   after removing a connection
@@ -159,14 +316,37 @@ where
     // - index could be self.input
     // - index could be self.output
     // - index could be part of a connection
+    // - self.output could be moved by the removal
+    // - the execution order is outdated
+    //   after removing a node
     pub fn remove_node(&mut self, index: NodeIndex) -> Result<Option<N>, GraphError> {
         if index == self.output {
             return Err(GraphError::WouldInvalidNode(self.output));
-        } else if self.dag.neighbors(index).peekable().peek().is_some() {
+        } else if self
+            .dag
+            .graph()
+            .neighbors_undirected(index)
+            .next()
+            .is_some()
+        {
             return Err(GraphError::WouldDanglingNodeInConnection);
         }
 
-        Ok(self.dag.remove_node(index))
+        // removing a node moves the last node into the freed index
+        let last = NodeIndex::new(self.dag.node_count() - 1);
+        let node = self.dag.remove_node(index);
+        if node.is_some() && self.output == last {
+            self.output = index;
+        }
+        if node.is_some() {
+            self.secondary_outputs.remove(&index);
+            if let Some(buffer) = self.secondary_outputs.remove(&last) {
+                self.secondary_outputs.insert(index, buffer);
+            }
+        }
+
+        self.recompute_execution_order();
+        Ok(node)
     }
 
     // Invalid States:
@@ -187,8 +367,33 @@ where
         self.block_size = block_size;
 
         self.update_buffer_pool();
+        self.delay_lines.clear();
+        self.update_latencies();
+        for buffer in self.secondary_outputs.values_mut() {
+            buffer.resize(block_size.0 as usize);
+        }
         old
     }
+
+    /// Keeps a copy of the output of `index` after every block, which is
+    /// returned by `secondary_output`. Nodes are processed even if they
+    /// don't lead to the output, as long as they have a secondary output.
+    pub fn add_secondary_output(&mut self, index: NodeIndex) -> Result<(), GraphError> {
+        let config = self
+            .get_node_config(index)
+            .ok_or(GraphError::WouldInvalidNode(index))?;
+        let channels = NonZeroUsize::new(config.num_output_channels)
+            .ok_or(GraphError::WouldInvalidNode(index))?;
+
+        self.secondary_outputs
+            .entry(index)
+            .or_insert_with(|| InterleavedBuffer::with_shape(channels, self.block_size));
+        Ok(())
+    }
+
+    pub fn remove_secondary_output(&mut self, index: NodeIndex) -> bool {
+        self.secondary_outputs.remove(&index).is_some()
+    }
 }
 
 impl<T, N> AudioGraph<T, N>
@@ -204,73 +409,118 @@ where
     pub fn process_block(
         &mut self,
         inputs: &HashMap<NodeIndex, &InterleavedBuffer<T>>,
-        output: &mut InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
     ) {
         let mut node_outputs: HashMap<NodeIndex, InterleavedBuffer<T>> = HashMap::new();
 
-        for &node_idx in &self.execution_order {
-            let node_config = self
-                .dag
-                .node_weight(node_idx)
-                .expect("precondition a")
-                .config();
-
-            if let Some(external_input) = inputs.get(&node_idx) {
-                self.dag
-                    .node_weight_mut(node_idx)
-                    .expect("precondition a")
-                    .process_unchecked(
-                        &external_input,
-                        if node_idx == self.output {
-                            output
-                        } else {
-                            let node_output = self
-                                .buffer_arena
-                                .take(node_config.num_output_channels, self.block_size)
-                                .expect("precondition b");
-                            node_outputs.insert(node_idx, node_output);
-                            node_outputs.get_mut(&node_idx).unwrap()
-                        },
-                    );
+        // processors may have changed their latency since the last block
+        self.update_latencies();
+
+        let mut past_output = false;
+        for position in 0..self.execution_order.len() {
+            let node_idx = self.execution_order[position];
+
+            // the output isn't cached, so nodes that depend on it can't be processed
+            if past_output
+                && !inputs.contains_key(&node_idx)
+                && self
+                    .dag
+                    .parents(node_idx)
+                    .iter(&self.dag)
+                    .any(|(_, parent)| !node_outputs.contains_key(&parent))
+            {
+                continue;
+            }
+            let node = self.dag.node_weight(node_idx).expect("precondition a");
+            let node_config = node.config();
+            let planar = node.planar_input();
+
+            let mut mixed = match (inputs.get(&node_idx), planar) {
+                (Some(_), false) => NodeInput::External,
+                (Some(input), true) => {
+                    let mut mixed = self
+                        .planar_arena
+                        .take(node_config.num_input_channels, self.block_size)
+                        .expect("precondition b");
+                    mixed.deinterleave_from(input);
+                    NodeInput::Planar(mixed)
+                }
+                (None, false) => NodeInput::Interleaved(
+                    self.buffer_arena
+                        .take(node_config.num_input_channels, self.block_size)
+                        .expect("precondition b"),
+                ),
+                (None, true) => NodeInput::Planar(
+                    self.planar_arena
+                        .take(node_config.num_input_channels, self.block_size)
+                        .expect("precondition b"),
+                ),
+            };
+            if !inputs.contains_key(&node_idx) {
+                self.mix_parents_from_cache(node_idx, &mut node_outputs, &mut mixed);
+            }
+
+            // the output node renders straight into `output`
+            let mut node_output = if node_idx == self.output {
+                None
             } else {
-                let mut mixed = self
-                    .buffer_arena
-                    .take(node_config.num_input_channels, self.block_size)
-                    .expect("precondition b");
+                Some(
+                    self.buffer_arena
+                        .take(node_config.num_output_channels, self.block_size)
+                        .expect("precondition b"),
+                )
+            };
+            let mut target = match &mut node_output {
+                Some(node_output) => node_output.wrap_mut(),
+                None => output.reborrow(),
+            };
+            let node = self.dag.node_weight_mut(node_idx).expect("precondition a");
+            match &mixed {
+                NodeInput::External => node.process_unchecked(inputs[&node_idx], &mut target),
+                NodeInput::Interleaved(mixed) => node.process_unchecked(mixed, &mut target),
+                NodeInput::Planar(mixed) => node.process_planar(mixed, &mut target),
+            }
 
-                self.mix_parents_from_cache(node_idx, &mut node_outputs, &mut mixed);
+            match mixed {
+                NodeInput::External => {}
+                NodeInput::Interleaved(mut mixed) => {
+                    mixed.set_to_equilibrium();
+                    self.buffer_arena.release(mixed);
+                }
+                NodeInput::Planar(mut mixed) => {
+                    mixed.set_to_equilibrium();
+                    self.planar_arena.release(mixed);
+                }
+            }
 
-                self.dag
-                    .node_weight_mut(node_idx)
-                    .expect("precondition a")
-                    .process_unchecked(
-                        &mixed,
-                        if node_idx == self.output {
-                            output
-                        } else {
-                            let node_output = self
-                                .buffer_arena
-                                .take(node_config.num_output_channels, self.block_size)
-                                .expect("precondition b");
-                            node_outputs.insert(node_idx, node_output);
-                            node_outputs.get_mut(&node_idx).unwrap()
-                        },
-                    );
+            if let Some(secondary) = self.secondary_outputs.get_mut(&node_idx) {
+                secondary.set_to_equilibrium();
+                match &node_output {
+                    Some(node_output) => mix_buffers(node_output, secondary, None),
+                    None => mix_buffers(&*output, secondary, None),
+                }
+                .expect("secondary outputs have the shape of the node's output");
+            }
 
-                mixed.set_to_equilibrium();
-                self.buffer_arena.release(mixed);
-            };
+            if let Some(node_output) = node_output {
+                node_outputs.insert(node_idx, node_output);
+            }
 
             if node_idx == self.output {
-                break;
+                // nodes after the output only matter for the secondary outputs
+                if self.secondary_outputs.is_empty() {
+                    break;
+                }
+                past_output = true;
+                continue;
             }
 
             for (&cached, &last_consumer) in &self.buffer_lifetimes {
-                if last_consumer == node_idx {
-                    if let Some(mut buffer) = node_outputs.remove(&cached) {
-                        buffer.set_to_equilibrium();
-                        self.buffer_arena.release(buffer);
-                    }
+                if last_consumer == node_idx
+                    && let Some(mut buffer) = node_outputs.remove(&cached)
+                {
+                    buffer.set_to_equilibrium();
+                    self.buffer_arena.release(buffer);
                 }
             }
         }
@@ -285,46 +535,88 @@ where
     // a) parent_outputs_cache must contain a buffer for every parent
     // b) the size of the output buffer and all buffers in parent_outputs_cache
     //    must be the same
+    // c) output isn't `NodeInput::External`
     fn mix_parents_from_cache(
-        &self,
+        &mut self,
         index: NodeIndex,
         parent_outputs_cache: &mut HashMap<NodeIndex, InterleavedBuffer<T>>,
-        output: &mut InterleavedBuffer<T>,
+        output: &mut NodeInput<T>,
     ) {
-        for (edge, parent) in self.dag.parents(index).iter(&self.dag) {
-            let parent_out = parent_outputs_cache
-                .get(&parent)
-                .expect("must be cached due to precondition a");
-
+        let mut parents = self.dag.parents(index);
+        while let Some((edge, parent)) = parents.walk_next(&self.dag) {
             let connection = self
                 .dag
-                .edge_weight(edge)
+                .edge_weight_mut(edge)
                 .expect("was just returned by self.dag.parents call");
+            let (start_gain, end_gain) = connection.advance_gain();
+            let tap = connection.tap;
+
+            let parent_out = match tap {
+                Tap::Main => parent_outputs_cache
+                    .get(&parent)
+                    .expect("must be cached due to precondition a"),
+                Tap::Auxiliary(index) => self.dag[parent]
+                    .auxiliary_output(index)
+                    .expect("taps are validated when they are set"),
+            };
+            let parent_out = match self.delay_lines.get_mut(&edge) {
+                Some(delay_line) => delay_line.process(parent_out),
+                None => parent_out,
+            };
+
+            let connection = &self.dag[edge];
+            let gains = (start_gain, end_gain);
+
+            // the channels line up, so the interleaved samples are mixed as a whole.
+            // The ramp then moves per sample rather than per frame, which is less
+            // than a frame's worth of gain apart.
+            if let NodeInput::Interleaved(output) = output
+                && connection.matrix.is_identity()
+            {
+                let (mixed, parent) = (output.as_mut_slice(), parent_out.as_slice());
+                debug_assert_eq!(parent.len(), mixed.len(), "precondition b");
+
+                if gains == (1.0, 1.0) {
+                    kernels::mix(mixed, parent);
+                } else {
+                    kernels::mix_with_gain_ramp(mixed, parent, start_gain, end_gain);
+                }
+                continue;
+            }
 
             for (parent_channel_idx, mixed_channel_idx) in connection.matrix.channel_connections() {
                 let parent_channel = parent_out
                     .get_channel(parent_channel_idx)
                     .expect("must be valid due to PinMatrix Validity");
 
-                output.map_channels_mut(
-                    |mut mixed_channel, _| {
-                        mixed_channel.map_samples_mut(
-                            |out_sample, sample_index| match parent_channel.get(sample_index) {
-                                Some(in_sample) => {
-                                    *out_sample = out_sample
-                                        .add_amp(dasp::Sample::to_signed_sample(*in_sample));
-                                    Some(())
-                                }
-                                None => {
-                                    unreachable!("precondition b")
-                                }
-                            },
-                            None,
+                match output {
+                    NodeInput::Interleaved(output) => {
+                        let channels = output.channels();
+                        let mut mixed_channel = StridedViewMut::channel(
+                            output.as_mut_slice(),
+                            channels,
+                            mixed_channel_idx,
+                        );
+                        debug_assert_eq!(
+                            parent_channel.len(),
+                            mixed_channel.len(),
+                            "precondition b"
+                        );
+                        mix_channel(mixed_channel.iter_mut(), parent_channel, gains);
+                    }
+                    NodeInput::Planar(output) => {
+                        let mixed_channel = output
+                            .channel_mut(mixed_channel_idx)
+                            .expect("must be valid due to PinMatrix Validity");
+                        debug_assert_eq!(
+                            parent_channel.len(),
+                            mixed_channel.len(),
+                            "precondition b"
                         );
-                        None::<usize>
-                    },
-                    Some(mixed_channel_idx),
-                );
+                        mix_channel(mixed_channel.iter_mut(), parent_channel, gains);
+                    }
+                    NodeInput::External => unreachable!("precondition c"),
+                }
             }
         }
     }
@@ -335,23 +627,32 @@ where
 
         fn increment(map: &mut HashMap<usize, usize>, channels: usize) {
             match map.get_mut(&channels) {
-                Some(number) => *number = *number + 1usize,
+                Some(number) => *number += 1usize,
                 None => {
                     map.insert(channels, 1);
                 }
             }
         }
 
+        let mut planar_required: HashMap<usize, usize> = HashMap::new();
         for node in self.dag.node_weights_mut() {
             let config = node.config();
             increment(&mut buffers_required, config.num_output_channels);
-            increment(&mut buffers_required, config.num_input_channels);
+            if node.planar_input() {
+                increment(&mut planar_required, config.num_input_channels);
+            } else {
+                increment(&mut buffers_required, config.num_input_channels);
+            }
         }
 
         for (channels, amount) in buffers_required {
             self.buffer_arena
                 .ensure_capacity(channels, self.block_size, amount);
         }
+        for (channels, amount) in planar_required {
+            self.planar_arena
+                .ensure_capacity(channels, self.block_size, amount);
+        }
     }
 
     fn recompute_execution_order(&mut self) {
@@ -361,6 +662,58 @@ where
             .collect();
 
         self.compute_buffer_lifetimes();
+        // edge indices move when edges are removed
+        self.delay_lines.clear();
+        self.update_latencies();
+    }
+
+    fn parent_latency(&self, index: NodeIndex) -> usize {
+        self.dag
+            .parents(index)
+            .iter(&self.dag)
+            .filter_map(|(_, parent)| self.latencies.get(&parent).copied())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Recomputes the latency of every node and makes sure every edge from a
+    // parent with less latency than the slowest parent is delayed by the difference.
+    // Only allocates when a compensation changes.
+    fn update_latencies(&mut self) {
+        self.latencies.clear();
+        for &node_idx in &self.execution_order {
+            let latency = self.parent_latency(node_idx)
+                + self
+                    .dag
+                    .node_weight(node_idx)
+                    .expect("execution order is up to date")
+                    .latency();
+            self.latencies.insert(node_idx, latency);
+        }
+
+        for &node_idx in &self.execution_order {
+            let latency = self.parent_latency(node_idx);
+            for (edge, parent) in self.dag.parents(node_idx).iter(&self.dag) {
+                let compensation = latency - self.latencies[&parent];
+                if compensation == 0 {
+                    self.delay_lines.remove(&edge);
+                } else if self
+                    .delay_lines
+                    .get(&edge)
+                    .is_none_or(|delay_line| delay_line.frames() != compensation)
+                {
+                    let channels = self.dag[parent].config().num_output_channels;
+                    self.delay_lines.insert(
+                        edge,
+                        DelayLine::new(
+                            NonZeroUsize::new(channels).expect("nodes output at least one channel"),
+                            compensation,
+                            self.block_size,
+                        ),
+                    );
+                }
+            }
+        }
     }
 
     fn compute_buffer_lifetimes(&mut self) {
@@ -375,8 +728,9 @@ where
     }
 
     pub fn add_node(&mut self, weight: N) -> NodeIndex {
+        let index = self.dag.add_node(weight);
         self.update_buffer_pool();
-        self.dag.add_node(weight)
+        index
     }
 
     pub fn get_output(&self) -> &N {
@@ -406,7 +760,22 @@ where
         self.dag.node_weight_mut(index)
     }
 
+    /// The output of `index` in the last block, if it has a secondary output
+    pub fn secondary_output(&self, index: NodeIndex) -> Option<&InterleavedBuffer<T>> {
+        self.secondary_outputs.get(&index)
+    }
+
     pub fn sample_rate(&self) -> SampleRate {
         self.sample_rate
     }
+
+    pub fn block_size(&self) -> FrameTime {
+        self.block_size
+    }
+
+    /// How many frames the output lags behind the input, i.e. the latency
+    /// of the slowest path to the output
+    pub fn latency(&self) -> usize {
+        self.latencies.get(&self.output).copied().unwrap_or(0)
+    }
 }
@@ -0,0 +1,336 @@
+use std::ops::RangeInclusive;
+
+use crate::smoothing::SmoothedValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ParameterId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParameterUnit {
+    #[default]
+    Generic,
+    Decibels,
+    Hertz,
+    Seconds,
+    Milliseconds,
+    Percent,
+    Ratio,
+    Beats,
+    Semitones,
+}
+
+/// Describes a single parameter of an `AudioProcessor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterDescriptor {
+    pub id: ParameterId,
+    pub name: String,
+    pub range: RangeInclusive<f32>,
+    pub unit: ParameterUnit,
+    pub default: f32,
+    /// Whether changes ramp to the new value or take effect immediately,
+    /// e.g. for switches or filter types.
+    pub smoothed: bool,
+}
+
+impl ParameterDescriptor {
+    pub fn new(
+        id: ParameterId,
+        name: impl Into<String>,
+        range: RangeInclusive<f32>,
+        default: f32,
+    ) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            range,
+            unit: ParameterUnit::Generic,
+            default,
+            smoothed: true,
+        }
+    }
+
+    pub fn with_unit(mut self, unit: ParameterUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Changes to stepped parameters are never smoothed
+    pub fn stepped(mut self) -> Self {
+        self.smoothed = false;
+        self
+    }
+
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(*self.range.start(), *self.range.end())
+    }
+}
+
+/// Moves a parameter linearly to `value` within `frames` frames, starting
+/// `offset` frames into the block. A ramp without frames is a jump.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParameterRamp {
+    pub offset: usize,
+    pub frames: usize,
+    pub value: f32,
+}
+
+/// How many ramps a parameter holds per block. The storage is allocated up
+/// front, so that scheduling ramps on the audio thread doesn't allocate.
+pub const MAX_RAMPS: usize = 64;
+
+struct ParameterState {
+    value: SmoothedValue,
+    // ramps scheduled for the current block, sorted by offset
+    ramps: Vec<ParameterRamp>,
+    next_ramp: usize,
+    frame: usize,
+}
+
+/// Stores the values of a processor's parameters. Processors can embed
+/// this to get smoothed and sample-accurate parameter changes for free.
+pub struct ParameterValues {
+    descriptors: Vec<ParameterDescriptor>,
+    states: Vec<ParameterState>,
+    // the index of each parameter sorted by id, for ids that aren't their index
+    indices: Vec<(ParameterId, usize)>,
+}
+
+impl ParameterValues {
+    pub fn new(descriptors: Vec<ParameterDescriptor>, ramp_frames: usize) -> Self {
+        let states = descriptors
+            .iter()
+            .map(|descriptor| {
+                let ramp = if descriptor.smoothed { ramp_frames } else { 0 };
+                ParameterState {
+                    value: SmoothedValue::new(descriptor.default, ramp),
+                    ramps: Vec::with_capacity(MAX_RAMPS),
+                    next_ramp: 0,
+                    frame: 0,
+                }
+            })
+            .collect();
+
+        let mut indices: Vec<_> = descriptors
+            .iter()
+            .enumerate()
+            .map(|(index, descriptor)| (descriptor.id, index))
+            .collect();
+        indices.sort_unstable();
+
+        Self {
+            descriptors,
+            states,
+            indices,
+        }
+    }
+
+    pub fn descriptors(&self) -> &[ParameterDescriptor] {
+        &self.descriptors
+    }
+
+    // Called per frame by `next_value`. Parameters are usually numbered
+    // in the order they are described, which is checked first.
+    fn position(&self, id: ParameterId) -> Option<usize> {
+        let index = id.0 as usize;
+        if self
+            .descriptors
+            .get(index)
+            .is_some_and(|descriptor| descriptor.id == id)
+        {
+            return Some(index);
+        }
+
+        self.indices
+            .binary_search_by_key(&id, |&(id, _)| id)
+            .ok()
+            .map(|found| self.indices[found].1)
+    }
+
+    /// Returns the value the parameter was last set to
+    pub fn get(&self, id: ParameterId) -> Option<f32> {
+        self.position(id)
+            .map(|index| self.states[index].value.target())
+    }
+
+    /// Sets a parameter, clamping the value to its range.
+    /// Returns `false` if the processor doesn't have such a parameter.
+    pub fn set(&mut self, id: ParameterId, value: f32) -> bool {
+        match self.position(id) {
+            Some(index) => {
+                let value = self.descriptors[index].clamp(value);
+                let state = &mut self.states[index];
+                state.ramps.clear();
+                state.value.set_target(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets a parameter without ramping, e.g. to configure a processor before it is used.
+    /// Returns `false` if the processor doesn't have such a parameter.
+    pub fn reset(&mut self, id: ParameterId, value: f32) -> bool {
+        match self.position(id) {
+            Some(index) => {
+                let value = self.descriptors[index].clamp(value);
+                let state = &mut self.states[index];
+                state.ramps.clear();
+                state.value.set_immediate(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the ramps of a parameter for the next block. Of more than
+    /// `MAX_RAMPS` ramps the first ones and the last one are kept, so the
+    /// parameter still ends up at the right value.
+    /// Returns `false` if the processor doesn't have such a parameter.
+    pub fn automate(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        let Some(index) = self.position(id) else {
+            return false;
+        };
+
+        let (kept, last) = if ramps.len() > MAX_RAMPS {
+            ramps.split_at(MAX_RAMPS - 1)
+        } else {
+            (ramps, &[][..])
+        };
+        let last = last.last();
+
+        let descriptor = &self.descriptors[index];
+        let state = &mut self.states[index];
+        state.ramps.clear();
+        state
+            .ramps
+            .extend(kept.iter().chain(last).map(|ramp| ParameterRamp {
+                value: descriptor.clamp(ramp.value),
+                ..*ramp
+            }));
+        state.next_ramp = 0;
+        state.frame = 0;
+        true
+    }
+
+    /// Returns the current value of a parameter without advancing it
+    ///
+    /// # Panics
+    /// Panics if there is no parameter with the given id
+    pub fn current(&self, id: ParameterId) -> f32 {
+        let index = self.position(id).expect("unknown parameter");
+        self.states[index].value.current()
+    }
+
+    /// Advances a parameter by a single frame and returns its value.
+    /// This has to be called once per frame for scheduled ramps to line up.
+    ///
+    /// # Panics
+    /// Panics if there is no parameter with the given id
+    pub fn next_value(&mut self, id: ParameterId) -> f32 {
+        let index = self.position(id).expect("unknown parameter");
+        let state = &mut self.states[index];
+
+        while let Some(ramp) = state.ramps.get(state.next_ramp)
+            && ramp.offset <= state.frame
+        {
+            state.value.ramp_to(ramp.value, ramp.frames);
+            state.next_ramp += 1;
+        }
+        state.frame += 1;
+
+        // a jump takes effect on the frame it is scheduled for
+        if state.value.is_smoothing() {
+            state.value.next_value()
+        } else {
+            state.value.current()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameter::{
+        MAX_RAMPS, ParameterDescriptor, ParameterId, ParameterRamp, ParameterValues,
+    };
+
+    #[test]
+    fn values_are_clamped_and_smoothed() {
+        let gain = ParameterId(0);
+        let mode = ParameterId(1);
+        let mut values = ParameterValues::new(
+            vec![
+                ParameterDescriptor::new(gain, "Gain", 0.0..=2.0, 1.0),
+                ParameterDescriptor::new(mode, "Mode", 0.0..=3.0, 0.0).stepped(),
+            ],
+            4,
+        );
+
+        assert!(values.set(gain, 5.0));
+        assert_eq!(values.get(gain), Some(2.0));
+        assert_eq!(values.next_value(gain), 1.25);
+
+        assert!(values.set(mode, 2.0));
+        assert_eq!(values.current(mode), 2.0);
+
+        assert!(!values.set(ParameterId(2), 0.0));
+    }
+
+    #[test]
+    fn automation_ramps_are_sample_accurate() {
+        let gain = ParameterId(0);
+        let mut values = ParameterValues::new(
+            vec![ParameterDescriptor::new(gain, "Gain", 0.0..=1.0, 0.0)],
+            64,
+        );
+
+        values.automate(
+            gain,
+            &[
+                ParameterRamp {
+                    offset: 2,
+                    frames: 0,
+                    value: 1.0,
+                },
+                ParameterRamp {
+                    offset: 4,
+                    frames: 2,
+                    value: 0.0,
+                },
+            ],
+        );
+
+        let block: Vec<f32> = (0..7).map(|_| values.next_value(gain)).collect();
+        assert_eq!(block, vec![0.0, 0.0, 1.0, 1.0, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn excess_ramps_keep_the_last_value() {
+        // ids that don't match the order of the descriptors
+        let gain = ParameterId(7);
+        let pan = ParameterId(3);
+        let mut values = ParameterValues::new(
+            vec![
+                ParameterDescriptor::new(gain, "Gain", 0.0..=1.0, 0.0),
+                ParameterDescriptor::new(pan, "Pan", -1.0..=1.0, 0.0),
+            ],
+            0,
+        );
+
+        let ramps: Vec<_> = (0..MAX_RAMPS * 2)
+            .map(|offset| ParameterRamp {
+                offset,
+                frames: 0,
+                value: offset as f32 / (MAX_RAMPS * 2 - 1) as f32,
+            })
+            .collect();
+        assert!(values.automate(gain, &ramps));
+        assert!(values.set(pan, -0.5));
+
+        let block: Vec<f32> = (0..MAX_RAMPS * 2)
+            .map(|_| values.next_value(gain))
+            .collect();
+        assert_eq!(block[1], ramps[1].value);
+        assert_eq!(block[MAX_RAMPS * 2 - 1], 1.0);
+        assert_eq!(values.get(pan), Some(-0.5));
+    }
+}
@@ -47,6 +47,16 @@ impl PinMatrix {
         self.data[output_channel * self.cols + input_channel] = val;
     }
 
+    /// Whether every channel is connected to the same channel and nothing else
+    pub fn is_identity(&self) -> bool {
+        self.rows == self.cols
+            && self
+                .data
+                .iter()
+                .enumerate()
+                .all(|(index, &pin)| pin == (index % self.cols == index / self.cols))
+    }
+
     pub fn input_channels(&self) -> usize {
         self.cols
     }
@@ -0,0 +1,159 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{
+        AudioProcessor, ProcessorConfiguration,
+        modulation::{Lfo, ModulatedDelay, channel_offset},
+        process_frames, ramp_frames,
+    },
+};
+
+pub const RATE: ParameterId = ParameterId(0);
+pub const DEPTH: ParameterId = ParameterId(1);
+pub const DELAY: ParameterId = ParameterId(2);
+pub const FEEDBACK: ParameterId = ParameterId(3);
+pub const MIX: ParameterId = ParameterId(4);
+
+/// Mixes a signal with a copy whose delay is modulated by an LFO.
+///
+/// With a delay of around 15 ms this thickens the signal, with a delay of
+/// a few milliseconds and feedback it becomes a flanger, see `Chorus::flanger`.
+/// Every channel is modulated with its own phase.
+pub struct Chorus {
+    channels: usize,
+    frames_per_ms: f32,
+    values: ParameterValues,
+    lfo: Lfo,
+    delay: ModulatedDelay,
+    scratch: Vec<f32>,
+}
+
+impl Chorus {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self::with_ranges(sample_rate, channels, (40.0, 10.0), [0.8, 3.0, 15.0, 0.0])
+    }
+
+    pub fn flanger(sample_rate: SampleRate, channels: usize) -> Self {
+        Self::with_ranges(sample_rate, channels, (10.0, 5.0), [0.25, 1.5, 2.0, 0.5])
+    }
+
+    // the longest delay and depth in milliseconds and the defaults
+    // of the rate, depth, delay and feedback
+    fn with_ranges(
+        sample_rate: SampleRate,
+        channels: usize,
+        (delay, depth): (f32, f32),
+        defaults: [f32; 4],
+    ) -> Self {
+        let frames_per_ms = sample_rate.as_f64() as f32 / 1000.0;
+
+        Self {
+            channels,
+            frames_per_ms,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(RATE, "Rate", 0.01..=10.0, defaults[0])
+                        .with_unit(ParameterUnit::Hertz),
+                    ParameterDescriptor::new(DEPTH, "Depth", 0.0..=depth, defaults[1])
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(DELAY, "Delay", 0.1..=delay, defaults[2])
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(FEEDBACK, "Feedback", -0.95..=0.95, defaults[3]),
+                    ParameterDescriptor::new(MIX, "Mix", 0.0..=1.0, 0.5),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            lfo: Lfo::new(sample_rate),
+            delay: ModulatedDelay::new(channels, ((delay + depth) * frames_per_ms) as usize + 1),
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.values.reset(RATE, rate);
+        self
+    }
+
+    /// Depth of the modulation in milliseconds
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.values.reset(DEPTH, depth);
+        self
+    }
+
+    /// Delay the modulation is centered around in milliseconds
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.values.reset(DELAY, delay);
+        self
+    }
+
+    pub fn with_feedback(mut self, feedback: f32) -> Self {
+        self.values.reset(FEEDBACK, feedback);
+        self
+    }
+
+    pub fn with_mix(mut self, mix: f32) -> Self {
+        self.values.reset(MIX, mix);
+        self
+    }
+}
+
+impl<T> AudioProcessor<T> for Chorus
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let channels = self.channels;
+
+        process_frames(input, output, &mut scratch, |_, frame| {
+            let rate = self.values.next_value(RATE);
+            let depth = self.values.next_value(DEPTH) * self.frames_per_ms;
+            let delay = self.values.next_value(DELAY) * self.frames_per_ms;
+            let feedback = self.values.next_value(FEEDBACK);
+            let mix = self.values.next_value(MIX);
+
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let modulation = self.lfo.value(channel_offset(channel, channels));
+                let delayed = self.delay.read(channel, delay + depth * modulation);
+                self.delay.write(channel, *sample + delayed * feedback);
+                *sample = *sample * (1.0 - mix) + delayed * mix;
+            }
+            self.delay.advance();
+            self.lfo.advance(rate);
+        });
+
+        self.scratch = scratch;
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
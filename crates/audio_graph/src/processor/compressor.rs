@@ -0,0 +1,262 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{
+        AudioProcessor, ProcessorConfiguration,
+        gain::{decibels_to_gain, gain_to_decibels},
+        key_peak, process_frames, ramp_frames, time_coefficient,
+    },
+};
+
+pub const THRESHOLD: ParameterId = ParameterId(0);
+pub const RATIO: ParameterId = ParameterId(1);
+pub const ATTACK: ParameterId = ParameterId(2);
+pub const RELEASE: ParameterId = ParameterId(3);
+pub const KNEE: ParameterId = ParameterId(4);
+pub const MAKEUP: ParameterId = ParameterId(5);
+
+/// A feed-forward compressor with a soft knee.
+///
+/// With a sidechain the level is detected on the extra input channels
+/// following the main ones, which are the only channels that are output.
+pub struct Compressor {
+    channels: usize,
+    sidechain_channels: usize,
+    sample_rate: SampleRate,
+    values: ParameterValues,
+    // gain reduction in decibels, always <= 0
+    reduction: f32,
+    scratch: Vec<f32>,
+}
+
+impl Compressor {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            channels,
+            sidechain_channels: 0,
+            sample_rate,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(THRESHOLD, "Threshold", -60.0..=0.0, -18.0)
+                        .with_unit(ParameterUnit::Decibels),
+                    ParameterDescriptor::new(RATIO, "Ratio", 1.0..=20.0, 4.0)
+                        .with_unit(ParameterUnit::Ratio),
+                    ParameterDescriptor::new(ATTACK, "Attack", 0.1..=200.0, 10.0)
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(RELEASE, "Release", 5.0..=2000.0, 100.0)
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(KNEE, "Knee", 0.0..=24.0, 6.0)
+                        .with_unit(ParameterUnit::Decibels),
+                    ParameterDescriptor::new(MAKEUP, "Makeup", 0.0..=24.0, 0.0)
+                        .with_unit(ParameterUnit::Decibels),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            reduction: 0.0,
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    /// Adds input channels after the main ones that drive the detector
+    pub fn with_sidechain(mut self, channels: usize) -> Self {
+        self.sidechain_channels = channels;
+        self.scratch.resize(self.channels + channels, 0.0);
+        self
+    }
+
+    pub fn with_threshold(mut self, decibels: f32) -> Self {
+        self.values.reset(THRESHOLD, decibels);
+        self
+    }
+
+    pub fn with_ratio(mut self, ratio: f32) -> Self {
+        self.values.reset(RATIO, ratio);
+        self
+    }
+
+    /// Attack and release in milliseconds
+    pub fn with_times(mut self, attack: f32, release: f32) -> Self {
+        self.values.reset(ATTACK, attack);
+        self.values.reset(RELEASE, release);
+        self
+    }
+
+    pub fn with_knee(mut self, decibels: f32) -> Self {
+        self.values.reset(KNEE, decibels);
+        self
+    }
+
+    pub fn with_makeup(mut self, decibels: f32) -> Self {
+        self.values.reset(MAKEUP, decibels);
+        self
+    }
+
+    /// The current gain reduction in decibels, e.g. for metering
+    pub fn gain_reduction(&self) -> f32 {
+        self.reduction
+    }
+}
+
+/// Returns the output level of the static curve for an input level, all in decibels
+pub fn compression_curve(level: f32, threshold: f32, ratio: f32, knee: f32) -> f32 {
+    let over = level - threshold;
+    if 2.0 * over <= -knee {
+        level
+    } else if 2.0 * over.abs() < knee {
+        level + (1.0 / ratio - 1.0) * (over + knee / 2.0).powi(2) / (2.0 * knee)
+    } else {
+        threshold + over / ratio
+    }
+}
+
+impl<T> AudioProcessor<T> for Compressor
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let channels = self.channels;
+        let sample_rate = self.sample_rate;
+        let values = &mut self.values;
+        let reduction = &mut self.reduction;
+
+        process_frames(input, output, &mut self.scratch, |_, frame| {
+            let threshold = values.next_value(THRESHOLD);
+            let ratio = values.next_value(RATIO);
+            let attack = time_coefficient(values.next_value(ATTACK) / 1000.0, sample_rate);
+            let release = time_coefficient(values.next_value(RELEASE) / 1000.0, sample_rate);
+            let knee = values.next_value(KNEE);
+            let makeup = values.next_value(MAKEUP);
+
+            let level = gain_to_decibels(key_peak(frame, channels));
+            let target = compression_curve(level, threshold, ratio, knee) - level;
+            let coefficient = if target < *reduction { attack } else { release };
+            *reduction = target + coefficient * (*reduction - target);
+
+            let gain = decibels_to_gain(*reduction + makeup);
+            for sample in &mut frame[..channels] {
+                *sample *= gain;
+            }
+        });
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels + self.sidechain_channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use audio_buffer::buffers::interleaved::InterleavedBuffer;
+    use time::{FrameTime, SampleRate};
+
+    use crate::processor::{
+        AudioProcessor,
+        compressor::{Compressor, compression_curve},
+    };
+
+    fn run(compressor: &mut Compressor, input: &[f32], channels: usize) -> InterleavedBuffer<f32> {
+        let frames = FrameTime((input.len() / channels) as u64);
+        let input =
+            InterleavedBuffer::from_vec(input.to_vec(), NonZeroUsize::new(channels).unwrap())
+                .unwrap();
+        let mut output = InterleavedBuffer::with_shape(NonZeroUsize::new(1).unwrap(), frames);
+        compressor.process(&input, &mut output.wrap_mut()).unwrap();
+        output
+    }
+
+    fn compressor() -> Compressor {
+        Compressor::new(SampleRate::new(48_000.0), 1)
+            .with_threshold(-20.0)
+            .with_ratio(4.0)
+            .with_knee(0.0)
+            .with_times(10.0, 100.0)
+    }
+
+    #[test]
+    fn curve_is_continuous_around_the_knee() {
+        // below the knee nothing changes, above it the ratio applies
+        assert_eq!(compression_curve(-40.0, -20.0, 4.0, 6.0), -40.0);
+        assert_eq!(compression_curve(0.0, -20.0, 4.0, 6.0), -15.0);
+
+        for knee in [0.0, 6.0, 12.0] {
+            let below = compression_curve(-20.0 - knee / 2.0 - 1e-3, -20.0, 4.0, knee);
+            let above = compression_curve(-20.0 + knee / 2.0 + 1e-3, -20.0, 4.0, knee);
+            let start = compression_curve(-20.0 - knee / 2.0, -20.0, 4.0, knee);
+            let end = compression_curve(-20.0 + knee / 2.0, -20.0, 4.0, knee);
+            assert!((below - start).abs() < 1e-2);
+            assert!((above - end).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn reduction_follows_attack_and_release() {
+        let mut compressor = compressor();
+
+        // 0 dB is 20 dB over the threshold and reduced to -15 dB,
+        // which is reached by 63% after one attack time
+        run(&mut compressor, &[1.0; 480], 1);
+        assert!((compressor.gain_reduction() + 15.0 * (1.0 - (-1f32).exp())).abs() < 0.1);
+        let output = run(&mut compressor, &[1.0; 4800], 1);
+        assert!((compressor.gain_reduction() + 15.0).abs() < 1e-3);
+        assert!((*output.get_sample(0, 4799).unwrap() - 10f32.powf(-15.0 / 20.0)).abs() < 1e-4);
+
+        // below the threshold the reduction releases to 37% after one release time
+        run(&mut compressor, &[0.01; 4800], 1);
+        assert!((compressor.gain_reduction() + 15.0 * (-1f32).exp()).abs() < 0.1);
+    }
+
+    #[test]
+    fn sidechain_keys_the_compressor() {
+        let mut compressor = compressor().with_sidechain(1);
+        assert_eq!(
+            AudioProcessor::<f32>::config(&compressor).num_input_channels,
+            2
+        );
+        assert_eq!(
+            AudioProcessor::<f32>::config(&compressor).num_output_channels,
+            1
+        );
+
+        // a quiet main signal is reduced while the key is loud
+        let keyed: Vec<f32> = [0.01, 1.0].repeat(4800);
+        let output = run(&mut compressor, &keyed, 2);
+        let reduced = 0.01 * 10f32.powf(-15.0 / 20.0);
+        assert!((*output.get_sample(0, 4799).unwrap() - reduced).abs() < 1e-5);
+
+        // a loud main signal passes once the key goes silent
+        let unkeyed: Vec<f32> = [1.0, 0.0].repeat(48_000);
+        let output = run(&mut compressor, &unkeyed, 2);
+        assert!(compressor.gain_reduction() > -1e-3);
+        assert!((*output.get_sample(0, 47_999).unwrap() - 1.0).abs() < 1e-3);
+    }
+}
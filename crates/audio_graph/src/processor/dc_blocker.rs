@@ -0,0 +1,190 @@
+use std::f32::consts::TAU;
+
+use audio_buffer::{
+    buffers::{
+        compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer,
+        planar::PlanarBuffer, view::StridedViewMut,
+    },
+    core::Buffer,
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::processor::{AudioProcessor, ProcessorConfiguration, from_f32, process_frames, to_f32};
+
+/// Cutoff of the high pass that removes the offset
+const CUTOFF: f32 = 10.0;
+
+/// Removes constant offsets from every channel with a one pole high pass.
+/// Takes planar input inside of a graph, as it filters one channel at a time.
+pub struct DcBlocker {
+    coefficient: f32,
+    // previous input and output per channel
+    state: Vec<(f32, f32)>,
+    scratch: Vec<f32>,
+}
+
+impl DcBlocker {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            coefficient: 1.0 - TAU * CUTOFF / sample_rate.as_f64() as f32,
+            state: vec![(0.0, 0.0); channels],
+            scratch: vec![0.0; channels],
+        }
+    }
+}
+
+fn high_pass(coefficient: f32, (x1, y1): &mut (f32, f32), x: f32) -> f32 {
+    let y = x - *x1 + coefficient * *y1;
+    *x1 = x;
+    *y1 = y;
+    y
+}
+
+impl<T> AudioProcessor<T> for DcBlocker
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let coefficient = self.coefficient;
+        let state = &mut self.state;
+        process_frames(input, output, &mut self.scratch, |_, frame| {
+            for (sample, state) in frame.iter_mut().zip(state.iter_mut()) {
+                *sample = high_pass(coefficient, state, *sample);
+            }
+        });
+    }
+
+    fn planar_input(&self) -> bool {
+        true
+    }
+
+    fn process_planar(&mut self, input: &PlanarBuffer<T>, output: &mut WrapInterleavedMut<'_, T>) {
+        let channels = output.channels();
+        for (index, state) in self.state.iter_mut().enumerate() {
+            let input = input
+                .channel(index)
+                .expect("the input has a channel per state");
+            let mut output = StridedViewMut::channel(output.as_mut_slice(), channels, index);
+            for (out, &sample) in output.iter_mut().zip(input) {
+                *out = from_f32(high_pass(self.coefficient, state, to_f32(sample)));
+            }
+        }
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.state.len(),
+            num_output_channels: self.state.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, f32::consts::TAU, num::NonZeroUsize};
+
+    use audio_buffer::{buffers::interleaved::InterleavedBuffer, core::BufferMut};
+    use time::{FrameTime, SampleRate};
+
+    use crate::{
+        AudioGraph,
+        pin_matrix::PinMatrix,
+        processor::{AudioProcessor, PassThrough, dc_blocker::DcBlocker},
+    };
+
+    #[test]
+    fn removes_offsets_and_keeps_the_signal() {
+        let sample_rate = SampleRate::new(48_000.0);
+
+        for channels in [1, 4] {
+            let mut blocker = DcBlocker::new(sample_rate, channels);
+
+            // every channel gets a different offset on top of a 1 kHz sine
+            let shape = NonZeroUsize::new(channels).unwrap();
+            let mut input = InterleavedBuffer::<f32>::with_shape(shape, FrameTime(48_000));
+            input.map_frames_mut(
+                |frame, index| {
+                    let sine = (TAU * 1000.0 * index as f32 / 48_000.0).sin();
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        *sample = 0.5 + channel as f32 * 0.25 + sine;
+                    }
+                    Some(())
+                },
+                None,
+            );
+            let mut output = InterleavedBuffer::with_shape(shape, FrameTime(48_000));
+            blocker.process(&input, &mut output.wrap_mut()).unwrap();
+
+            // the last 48 frames are a whole period of the sine
+            for channel in 0..channels {
+                let tail =
+                    (47_952..48_000).map(|frame| *output.get_sample(channel, frame).unwrap());
+                let (sum, peak) = tail.fold((0.0, 0f32), |(sum, peak), sample| {
+                    (sum + sample, peak.max(sample.abs()))
+                });
+                assert!(
+                    (sum / 48.0).abs() < 1e-3,
+                    "offset left on channel {channel}"
+                );
+                assert!((peak - 1.0).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn planar_input_inside_a_graph_matches_interleaved_processing() {
+        let sample_rate = SampleRate::new(48_000.0);
+        let shape = NonZeroUsize::new(2).unwrap();
+        let mut input = InterleavedBuffer::<f32>::with_shape(shape, FrameTime(64));
+        input.map_frames_mut(
+            |frame, index| {
+                frame[0] = 0.5 + (index as f32 * 0.1).sin();
+                frame[1] = -0.25;
+                Some(())
+            },
+            None,
+        );
+        let mut swapped = InterleavedBuffer::with_shape(shape, FrameTime(64));
+        swapped.map_frames_mut(
+            |frame, index| {
+                frame[0] = *input.get_sample(1, index).unwrap();
+                frame[1] = *input.get_sample(0, index).unwrap();
+                Some(())
+            },
+            None,
+        );
+
+        let mut expected = InterleavedBuffer::with_shape(shape, FrameTime(64));
+        DcBlocker::new(sample_rate, 2)
+            .process(&swapped, &mut expected.wrap_mut())
+            .unwrap();
+
+        // the parent is mixed into the planar input through a matrix that swaps the channels
+        let blocker: Box<dyn AudioProcessor<f32>> = Box::new(DcBlocker::new(sample_rate, 2));
+        let (mut graph, output) = AudioGraph::new(blocker, sample_rate, FrameTime(64));
+        let source = graph.add_node(Box::new(PassThrough::new(2, 2)));
+        let mut matrix = PinMatrix::new(2, 2);
+        matrix.set(0, 1, true);
+        matrix.set(1, 0, true);
+        graph.add_connection(source, output, matrix).unwrap();
+
+        let mut rendered = InterleavedBuffer::with_shape(shape, FrameTime(64));
+        graph.process_block(&HashMap::from([(source, &input)]), &mut rendered.wrap_mut());
+        assert_eq!(rendered.as_slice(), expected.as_slice());
+
+        // an external input is deinterleaved
+        let blocker: Box<dyn AudioProcessor<f32>> = Box::new(DcBlocker::new(sample_rate, 2));
+        let (mut graph, output) = AudioGraph::new(blocker, sample_rate, FrameTime(64));
+        let mut rendered = InterleavedBuffer::with_shape(shape, FrameTime(64));
+        graph.process_block(
+            &HashMap::from([(output, &swapped)]),
+            &mut rendered.wrap_mut(),
+        );
+        assert_eq!(rendered.as_slice(), expected.as_slice());
+    }
+}
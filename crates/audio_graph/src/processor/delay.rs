@@ -0,0 +1,189 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::{MusicalTime, SampleRate};
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{AudioProcessor, ProcessorConfiguration, process_frames, ramp_frames},
+};
+
+pub const TIME: ParameterId = ParameterId(0);
+pub const FEEDBACK: ParameterId = ParameterId(1);
+pub const MIX: ParameterId = ParameterId(2);
+pub const TEMPO: ParameterId = ParameterId(3);
+
+/// Longest delay time in beats
+pub const MAX_BEATS: f32 = 4.0;
+/// Slowest tempo the delay line is allocated for
+pub const MIN_BPM: f32 = 30.0;
+
+/// An echo whose delay time is set in beats and follows the tempo.
+///
+/// The delay line is allocated up front for `MAX_BEATS` at `MIN_BPM`,
+/// so neither time nor tempo changes allocate while processing.
+pub struct Delay {
+    channels: usize,
+    sample_rate: SampleRate,
+    values: ParameterValues,
+    // interleaved ring buffer
+    line: Vec<f32>,
+    write: usize,
+    // the delay time in frames for the cached time and tempo
+    cached: (f32, f32, usize),
+    scratch: Vec<f32>,
+}
+
+impl Delay {
+    pub fn new(sample_rate: SampleRate, channels: usize, bpm: f64) -> Self {
+        let capacity = MusicalTime::from_beats_f64_lossy(f64::from(MAX_BEATS))
+            .to_nearest_frame_round_lossy(f64::from(MIN_BPM), sample_rate)
+            .0 as usize
+            + 1;
+
+        let mut delay = Self {
+            channels,
+            sample_rate,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(TIME, "Time", 1.0 / 64.0..=MAX_BEATS, 0.5)
+                        .with_unit(ParameterUnit::Beats),
+                    ParameterDescriptor::new(FEEDBACK, "Feedback", 0.0..=0.95, 0.4),
+                    ParameterDescriptor::new(MIX, "Mix", 0.0..=1.0, 0.5),
+                    ParameterDescriptor::new(TEMPO, "Tempo", MIN_BPM..=300.0, 120.0).stepped(),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            line: vec![0.0; capacity * channels],
+            write: 0,
+            cached: (0.0, 0.0, 0),
+            scratch: vec![0.0; channels],
+        };
+        delay.values.reset(TEMPO, bpm as f32);
+        delay
+    }
+
+    pub fn with_time(mut self, time: MusicalTime) -> Self {
+        self.values.reset(TIME, time.as_beats_f64_lossy() as f32);
+        self
+    }
+
+    pub fn with_feedback(mut self, feedback: f32) -> Self {
+        self.values.reset(FEEDBACK, feedback);
+        self
+    }
+
+    pub fn with_mix(mut self, mix: f32) -> Self {
+        self.values.reset(MIX, mix);
+        self
+    }
+
+    /// Follows a tempo change, equivalent to setting the `TEMPO` parameter
+    pub fn set_bpm(&mut self, bpm: f64) {
+        self.values.set(TEMPO, bpm as f32);
+    }
+
+    fn delay_frames(&mut self, beats: f32, bpm: f32) -> usize {
+        if (self.cached.0, self.cached.1) != (beats, bpm) {
+            let frames = MusicalTime::from_beats_f64_lossy(f64::from(beats))
+                .to_nearest_frame_round_lossy(f64::from(bpm), self.sample_rate)
+                .0 as usize;
+            let capacity = self.line.len() / self.channels.max(1);
+            self.cached = (beats, bpm, frames.clamp(1, capacity - 1));
+        }
+        self.cached.2
+    }
+}
+
+impl<T> AudioProcessor<T> for Delay
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let channels = self.channels;
+        let capacity = self.line.len() / channels.max(1);
+
+        process_frames(input, output, &mut scratch, |_, frame| {
+            let beats = self.values.next_value(TIME);
+            let bpm = self.values.next_value(TEMPO);
+            let feedback = self.values.next_value(FEEDBACK);
+            let mix = self.values.next_value(MIX);
+
+            let delay = self.delay_frames(beats, bpm);
+            let read = (self.write + capacity - delay) % capacity;
+
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let delayed = self.line[read * channels + channel];
+                self.line[self.write * channels + channel] = *sample + delayed * feedback;
+                *sample = *sample * (1.0 - mix) + delayed * mix;
+            }
+            self.write = (self.write + 1) % capacity;
+        });
+
+        self.scratch = scratch;
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use audio_buffer::{
+        buffers::interleaved::InterleavedBuffer,
+        core::{Buffer, BufferMut},
+    };
+    use time::{FrameTime, MusicalTime, SampleRate};
+
+    use crate::processor::{AudioProcessor, delay::Delay};
+
+    #[test]
+    fn echoes_after_the_delay_time() {
+        // at 60 bpm and 100 Hz a beat lasts 100 frames
+        let mut delay = Delay::new(SampleRate::new(100.0), 2, 60.0)
+            .with_time(MusicalTime::from_half_beats(0, 1))
+            .with_feedback(0.5)
+            .with_mix(1.0);
+
+        let channels = NonZeroUsize::new(2).unwrap();
+        let mut input = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(200));
+        input.with_frame_mut(0, |frame| frame.fill(1.0));
+        let mut output = InterleavedBuffer::with_shape(channels, FrameTime(200));
+
+        delay.process(&input, &mut output.wrap_mut()).unwrap();
+
+        let left = |frame: usize| output.get_frame(frame).unwrap()[0];
+        assert_eq!(left(0), 0.0);
+        assert_eq!(left(50), 1.0);
+        assert_eq!(left(100), 0.5);
+        assert_eq!(left(101), 0.0);
+    }
+}
@@ -0,0 +1,194 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterValues},
+    processor::{
+        AudioProcessor, ProcessorConfiguration,
+        filter::{self, FREQUENCY, FilterState, FilterType, GAIN, Q, TYPE},
+        process_frames, ramp_frames,
+    },
+};
+
+/// Every band has the parameters of a `Biquad`
+pub const PARAMETERS_PER_BAND: u32 = 4;
+
+/// Returns the id of a band's parameter, e.g. `band_parameter(2, filter::GAIN)`
+pub fn band_parameter(band: usize, parameter: ParameterId) -> ParameterId {
+    ParameterId(band as u32 * PARAMETERS_PER_BAND + parameter.0)
+}
+
+/// A parametric equalizer made of biquad bands that are applied in series.
+///
+/// The first band starts out as a low shelf, the last as a high shelf and the ones
+/// in between as peaks, spread logarithmically between 100 Hz and 8 kHz at 0 dB.
+pub struct ParametricEq {
+    channels: usize,
+    values: ParameterValues,
+    bands: Vec<FilterState>,
+    scratch: Vec<f32>,
+}
+
+impl ParametricEq {
+    pub fn new(sample_rate: SampleRate, channels: usize, bands: usize) -> Self {
+        let descriptors = (0..bands)
+            .flat_map(|band| {
+                let filter_type = match band {
+                    0 if bands > 1 => FilterType::LowShelf,
+                    _ if band + 1 == bands && bands > 1 => FilterType::HighShelf,
+                    _ => FilterType::Peak,
+                };
+                let position = band as f32 / (bands - 1).max(1) as f32;
+                let frequency = 100.0 * 80f32.powf(position);
+
+                filter::descriptors(band as u32 * PARAMETERS_PER_BAND, filter_type, frequency)
+                    .into_iter()
+                    .map(move |mut descriptor| {
+                        descriptor.name = format!("Band {} {}", band + 1, descriptor.name);
+                        descriptor
+                    })
+            })
+            .collect();
+
+        Self {
+            channels,
+            values: ParameterValues::new(descriptors, ramp_frames(sample_rate)),
+            bands: (0..bands)
+                .map(|_| FilterState::new(sample_rate, channels))
+                .collect(),
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    pub fn with_band(
+        mut self,
+        band: usize,
+        filter_type: FilterType,
+        frequency: f32,
+        q: f32,
+        gain: f32,
+    ) -> Self {
+        self.values
+            .reset(band_parameter(band, TYPE), filter_type.to_value());
+        self.values
+            .reset(band_parameter(band, FREQUENCY), frequency);
+        self.values.reset(band_parameter(band, Q), q);
+        self.values.reset(band_parameter(band, GAIN), gain);
+        self
+    }
+
+    pub fn bands(&self) -> usize {
+        self.bands.len()
+    }
+}
+
+impl<T> AudioProcessor<T> for ParametricEq
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let values = &mut self.values;
+        let bands = &mut self.bands;
+        process_frames(input, output, &mut self.scratch, |_, frame| {
+            for (band, filter) in bands.iter_mut().enumerate() {
+                filter.update(
+                    FilterType::from_value(values.next_value(band_parameter(band, TYPE))),
+                    values.next_value(band_parameter(band, FREQUENCY)),
+                    values.next_value(band_parameter(band, Q)),
+                    values.next_value(band_parameter(band, GAIN)),
+                );
+                filter.process(frame);
+            }
+        });
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::TAU, num::NonZeroUsize};
+
+    use audio_buffer::{buffers::interleaved::InterleavedBuffer, core::BufferMut};
+    use time::{FrameTime, SampleRate};
+
+    use crate::processor::{AudioProcessor, eq::ParametricEq, filter::FilterType};
+
+    // the peak level of the last 480 frames of a sine at `frequency` on every channel
+    fn peaks(eq: &mut ParametricEq, channels: usize, frequency: f32) -> Vec<f32> {
+        let shape = NonZeroUsize::new(channels).unwrap();
+        let mut input = InterleavedBuffer::<f32>::with_shape(shape, FrameTime(9600));
+        input.map_frames_mut(
+            |frame, index| {
+                frame.fill((TAU * frequency * index as f32 / 48_000.0).sin());
+                Some(())
+            },
+            None,
+        );
+        let mut output = InterleavedBuffer::with_shape(shape, FrameTime(9600));
+        eq.process(&input, &mut output.wrap_mut()).unwrap();
+
+        (0..channels)
+            .map(|channel| {
+                (9120..9600).fold(0f32, |peak, frame| {
+                    peak.max(output.get_sample(channel, frame).unwrap().abs())
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flat_bands_pass_the_signal_unchanged() {
+        let mut eq = ParametricEq::new(SampleRate::new(48_000.0), 1, 4);
+        assert_eq!(eq.bands(), 4);
+
+        for frequency in [50.0, 1000.0, 12_000.0] {
+            let peak = peaks(&mut eq, 1, frequency)[0];
+            assert!((peak - 1.0).abs() < 0.01, "{frequency} Hz at {peak}");
+        }
+    }
+
+    #[test]
+    fn bands_apply_in_series_on_every_channel() {
+        let mut eq = ParametricEq::new(SampleRate::new(48_000.0), 4, 3)
+            .with_band(1, FilterType::Peak, 1000.0, 1.0, 12.0)
+            .with_band(2, FilterType::LowPass, 4000.0, 0.707, 0.0);
+
+        // +12 dB at the peak, the low pass doesn't touch it
+        for peak in peaks(&mut eq, 4, 1000.0) {
+            assert!((peak - 3.98).abs() < 0.1, "{peak}");
+        }
+        // far above both, only the low pass matters
+        for peak in peaks(&mut eq, 4, 16_000.0) {
+            assert!(peak < 0.1, "{peak}");
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::f64::consts::PI;
+
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{AudioProcessor, ProcessorConfiguration, process_frames, ramp_frames},
+};
+
+pub const TYPE: ParameterId = ParameterId(0);
+pub const FREQUENCY: ParameterId = ParameterId(1);
+pub const Q: ParameterId = ParameterId(2);
+pub const GAIN: ParameterId = ParameterId(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    LowPass,
+    HighPass,
+    /// Constant 0 dB peak gain
+    BandPass,
+    LowShelf,
+    HighShelf,
+    Peak,
+}
+
+impl FilterType {
+    const ALL: [FilterType; 6] = [
+        FilterType::LowPass,
+        FilterType::HighPass,
+        FilterType::BandPass,
+        FilterType::LowShelf,
+        FilterType::HighShelf,
+        FilterType::Peak,
+    ];
+
+    pub(crate) fn from_value(value: f32) -> Self {
+        Self::ALL[(value.max(0.0) as usize).min(Self::ALL.len() - 1)]
+    }
+
+    pub(crate) fn to_value(self) -> f32 {
+        Self::ALL.iter().position(|&t| t == self).unwrap_or(0) as f32
+    }
+}
+
+/// Normalized coefficients of a biquad, computed with the formulas
+/// of the Audio EQ Cookbook by Robert Bristow-Johnson.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiquadCoefficients {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl BiquadCoefficients {
+    pub const IDENTITY: Self = Self {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: 0.0,
+    };
+
+    /// `gain` in decibels only affects shelves and peaks
+    pub fn new(
+        filter_type: FilterType,
+        sample_rate: SampleRate,
+        frequency: f32,
+        q: f32,
+        gain: f32,
+    ) -> Self {
+        let sample_rate = sample_rate.as_f64();
+        let frequency = f64::from(frequency).clamp(1.0, sample_rate * 0.49);
+        let q = f64::from(q).max(0.01);
+        let a = 10f64.powf(f64::from(gain) / 40.0);
+
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let shelf = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match filter_type {
+            FilterType::LowPass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            FilterType::HighPass => (
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            FilterType::BandPass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            FilterType::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                (a + 1.0) + (a - 1.0) * cos + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - shelf,
+            ),
+            FilterType::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                (a + 1.0) - (a - 1.0) * cos + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - shelf,
+            ),
+            FilterType::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+        };
+
+        Self {
+            b0: (b0 / a0) as f32,
+            b1: (b1 / a0) as f32,
+            b2: (b2 / a0) as f32,
+            a1: (a1 / a0) as f32,
+            a2: (a2 / a0) as f32,
+        }
+    }
+}
+
+/// The coefficients and per-channel state of a single filter, shared by `Biquad`
+/// and the bands of `ParametricEq`. Coefficients are only recomputed when the
+/// settings change.
+pub(crate) struct FilterState {
+    sample_rate: SampleRate,
+    settings: Option<(FilterType, f32, f32, f32)>,
+    coefficients: BiquadCoefficients,
+    // transposed direct form II
+    delays: Vec<[f32; 2]>,
+}
+
+impl FilterState {
+    pub(crate) fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            sample_rate,
+            settings: None,
+            coefficients: BiquadCoefficients::IDENTITY,
+            delays: vec![[0.0; 2]; channels],
+        }
+    }
+
+    pub(crate) fn update(&mut self, filter_type: FilterType, frequency: f32, q: f32, gain: f32) {
+        let settings = Some((filter_type, frequency, q, gain));
+        if self.settings != settings {
+            self.settings = settings;
+            self.coefficients =
+                BiquadCoefficients::new(filter_type, self.sample_rate, frequency, q, gain);
+        }
+    }
+
+    pub(crate) fn process(&mut self, frame: &mut [f32]) {
+        let c = self.coefficients;
+        for (sample, [z1, z2]) in frame.iter_mut().zip(&mut self.delays) {
+            let x = *sample;
+            let y = c.b0 * x + *z1;
+            *z1 = c.b1 * x - c.a1 * y + *z2;
+            *z2 = c.b2 * x - c.a2 * y;
+            *sample = y;
+        }
+    }
+}
+
+/// A single second order filter applied to every channel.
+pub struct Biquad {
+    channels: usize,
+    values: ParameterValues,
+    filter: FilterState,
+    scratch: Vec<f32>,
+}
+
+impl Biquad {
+    pub fn new(sample_rate: SampleRate, channels: usize, filter_type: FilterType) -> Self {
+        Self {
+            channels,
+            values: ParameterValues::new(
+                descriptors(0, filter_type, 1000.0),
+                ramp_frames(sample_rate),
+            ),
+            filter: FilterState::new(sample_rate, channels),
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.values.reset(FREQUENCY, frequency);
+        self
+    }
+
+    pub fn with_q(mut self, q: f32) -> Self {
+        self.values.reset(Q, q);
+        self
+    }
+
+    /// Gain in decibels for shelves and peaks
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.values.reset(GAIN, gain);
+        self
+    }
+}
+
+/// The descriptors of a filter with ids starting at `first`
+pub(crate) fn descriptors(
+    first: u32,
+    filter_type: FilterType,
+    frequency: f32,
+) -> Vec<ParameterDescriptor> {
+    vec![
+        ParameterDescriptor::new(
+            ParameterId(first + TYPE.0),
+            "Type",
+            0.0..=5.0,
+            filter_type.to_value(),
+        )
+        .stepped(),
+        ParameterDescriptor::new(
+            ParameterId(first + FREQUENCY.0),
+            "Frequency",
+            20.0..=20_000.0,
+            frequency,
+        )
+        .with_unit(ParameterUnit::Hertz),
+        ParameterDescriptor::new(ParameterId(first + Q.0), "Q", 0.1..=18.0, 0.707),
+        ParameterDescriptor::new(ParameterId(first + GAIN.0), "Gain", -24.0..=24.0, 0.0)
+            .with_unit(ParameterUnit::Decibels),
+    ]
+}
+
+impl<T> AudioProcessor<T> for Biquad
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let values = &mut self.values;
+        let filter = &mut self.filter;
+        process_frames(input, output, &mut self.scratch, |_, frame| {
+            filter.update(
+                FilterType::from_value(values.next_value(TYPE)),
+                values.next_value(FREQUENCY),
+                values.next_value(Q),
+                values.next_value(GAIN),
+            );
+            filter.process(frame);
+        });
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::SampleRate;
+
+    use crate::processor::filter::{FilterState, FilterType};
+
+    #[test]
+    fn lowpass_passes_dc_and_blocks_nyquist() {
+        let mut filter = FilterState::new(SampleRate::new(48_000.0), 2);
+        filter.update(FilterType::LowPass, 1000.0, 0.707, 0.0);
+
+        let mut last = [0.0; 2];
+        for frame in 0..4800 {
+            // dc on the left, an alternating signal at nyquist on the right
+            let nyquist = if frame % 2 == 0 { 1.0 } else { -1.0 };
+            last = [1.0, nyquist];
+            filter.process(&mut last);
+        }
+
+        assert!((last[0] - 1.0).abs() < 1e-3);
+        assert!(last[1].abs() < 1e-3);
+    }
+}
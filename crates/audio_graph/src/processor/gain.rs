@@ -0,0 +1,142 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{AudioProcessor, ProcessorConfiguration, process_frames, ramp_frames},
+};
+
+pub const GAIN: ParameterId = ParameterId(0);
+
+/// Gains at or below this level are treated as silence
+pub const MIN_DECIBELS: f32 = -96.0;
+
+pub fn decibels_to_gain(decibels: f32) -> f32 {
+    if decibels <= MIN_DECIBELS {
+        0.0
+    } else {
+        10f32.powf(decibels / 20.0)
+    }
+}
+
+pub fn gain_to_decibels(gain: f32) -> f32 {
+    if gain <= 0.0 {
+        MIN_DECIBELS
+    } else {
+        (20.0 * gain.log10()).max(MIN_DECIBELS)
+    }
+}
+
+/// Applies a gain in decibels to every channel, e.g. to trim the level of an input.
+pub struct Gain {
+    channels: usize,
+    values: ParameterValues,
+    scratch: Vec<f32>,
+}
+
+impl Gain {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            channels,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(GAIN, "Gain", MIN_DECIBELS..=24.0, 0.0)
+                        .with_unit(ParameterUnit::Decibels),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    pub fn with_gain(mut self, decibels: f32) -> Self {
+        self.values.reset(GAIN, decibels);
+        self
+    }
+}
+
+impl<T> AudioProcessor<T> for Gain
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let values = &mut self.values;
+        process_frames(input, output, &mut self.scratch, |_, frame| {
+            let gain = decibels_to_gain(values.next_value(GAIN));
+            for sample in frame {
+                *sample *= gain;
+            }
+        });
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use audio_buffer::buffers::interleaved::InterleavedBuffer;
+    use time::{FrameTime, SampleRate};
+
+    use crate::processor::{
+        AudioProcessor,
+        gain::{Gain, MIN_DECIBELS, decibels_to_gain, gain_to_decibels},
+    };
+
+    #[test]
+    fn applies_the_gain_to_any_channel_count() {
+        for channels in [1, 2, 4] {
+            let mut gain = Gain::new(SampleRate::new(48_000.0), channels).with_gain(-6.0);
+
+            let samples: Vec<f32> = (0..64 * channels).map(|i| i as f32 / 100.0).collect();
+            let channels = NonZeroUsize::new(channels).unwrap();
+            let input = InterleavedBuffer::from_vec(samples.clone(), channels).unwrap();
+            let mut output = InterleavedBuffer::with_shape(channels, FrameTime(64));
+            gain.process(&input, &mut output.wrap_mut()).unwrap();
+
+            let factor = decibels_to_gain(-6.0);
+            for (out, sample) in output.as_slice().iter().zip(&samples) {
+                assert_eq!(*out, sample * factor);
+            }
+        }
+    }
+
+    #[test]
+    fn converts_between_decibels_and_gain() {
+        assert_eq!(decibels_to_gain(0.0), 1.0);
+        assert!((decibels_to_gain(20.0) - 10.0).abs() < 1e-5);
+        assert_eq!(decibels_to_gain(MIN_DECIBELS), 0.0);
+
+        assert!((gain_to_decibels(0.5) + 6.0206).abs() < 1e-3);
+        assert_eq!(gain_to_decibels(0.0), MIN_DECIBELS);
+    }
+}
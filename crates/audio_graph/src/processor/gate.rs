@@ -0,0 +1,213 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{
+        AudioProcessor, ProcessorConfiguration,
+        gain::{decibels_to_gain, gain_to_decibels},
+        key_peak, process_frames, ramp_frames, time_coefficient,
+    },
+};
+
+pub const THRESHOLD: ParameterId = ParameterId(0);
+pub const RANGE: ParameterId = ParameterId(1);
+pub const ATTACK: ParameterId = ParameterId(2);
+pub const HOLD: ParameterId = ParameterId(3);
+pub const RELEASE: ParameterId = ParameterId(4);
+
+/// Attenuates the signal by `RANGE` while its level stays below the threshold.
+///
+/// With a sidechain the level is detected on the extra input channels
+/// following the main ones, which are the only channels that are output.
+pub struct Gate {
+    channels: usize,
+    sidechain_channels: usize,
+    sample_rate: SampleRate,
+    values: ParameterValues,
+    // frames left until the gate starts closing
+    hold: usize,
+    gain: f32,
+    scratch: Vec<f32>,
+}
+
+impl Gate {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            channels,
+            sidechain_channels: 0,
+            sample_rate,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(THRESHOLD, "Threshold", -80.0..=0.0, -40.0)
+                        .with_unit(ParameterUnit::Decibels),
+                    ParameterDescriptor::new(RANGE, "Range", -96.0..=0.0, -96.0)
+                        .with_unit(ParameterUnit::Decibels),
+                    ParameterDescriptor::new(ATTACK, "Attack", 0.01..=100.0, 0.5)
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(HOLD, "Hold", 0.0..=1000.0, 20.0)
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(RELEASE, "Release", 5.0..=2000.0, 100.0)
+                        .with_unit(ParameterUnit::Milliseconds),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            hold: 0,
+            gain: 0.0,
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    /// Adds input channels after the main ones that open and close the gate
+    pub fn with_sidechain(mut self, channels: usize) -> Self {
+        self.sidechain_channels = channels;
+        self.scratch.resize(self.channels + channels, 0.0);
+        self
+    }
+
+    pub fn with_threshold(mut self, decibels: f32) -> Self {
+        self.values.reset(THRESHOLD, decibels);
+        self
+    }
+
+    pub fn with_range(mut self, decibels: f32) -> Self {
+        self.values.reset(RANGE, decibels);
+        self
+    }
+
+    /// Attack, hold and release in milliseconds
+    pub fn with_times(mut self, attack: f32, hold: f32, release: f32) -> Self {
+        self.values.reset(ATTACK, attack);
+        self.values.reset(HOLD, hold);
+        self.values.reset(RELEASE, release);
+        self
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.hold > 0
+    }
+}
+
+impl<T> AudioProcessor<T> for Gate
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let channels = self.channels;
+        let sample_rate = self.sample_rate;
+        let values = &mut self.values;
+        let hold = &mut self.hold;
+        let gain = &mut self.gain;
+
+        process_frames(input, output, &mut self.scratch, |_, frame| {
+            let threshold = values.next_value(THRESHOLD);
+            let closed = decibels_to_gain(values.next_value(RANGE));
+            let attack = time_coefficient(values.next_value(ATTACK) / 1000.0, sample_rate);
+            let hold_frames = values.next_value(HOLD) / 1000.0 * sample_rate.as_f64() as f32;
+            let release = time_coefficient(values.next_value(RELEASE) / 1000.0, sample_rate);
+
+            if gain_to_decibels(key_peak(frame, channels)) >= threshold {
+                *hold = hold_frames as usize + 1;
+            } else {
+                *hold = hold.saturating_sub(1);
+            }
+
+            let (target, coefficient) = if *hold > 0 {
+                (1.0, attack)
+            } else {
+                (closed, release)
+            };
+            *gain = target + coefficient * (*gain - target);
+
+            for sample in &mut frame[..channels] {
+                *sample *= *gain;
+            }
+        });
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels + self.sidechain_channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use audio_buffer::buffers::interleaved::InterleavedBuffer;
+    use time::{FrameTime, SampleRate};
+
+    use crate::processor::{AudioProcessor, gate::Gate};
+
+    fn run(gate: &mut Gate, input: &[f32], channels: usize) -> InterleavedBuffer<f32> {
+        let frames = FrameTime((input.len() / channels) as u64);
+        let input =
+            InterleavedBuffer::from_vec(input.to_vec(), NonZeroUsize::new(channels).unwrap())
+                .unwrap();
+        let mut output = InterleavedBuffer::with_shape(NonZeroUsize::new(1).unwrap(), frames);
+        gate.process(&input, &mut output.wrap_mut()).unwrap();
+        output
+    }
+
+    #[test]
+    fn closes_below_the_threshold() {
+        let mut gate = Gate::new(SampleRate::new(48_000.0), 1).with_threshold(-40.0);
+
+        // -6 dB opens the gate and passes through once the attack settled
+        let output = run(&mut gate, &[0.5; 4800], 1);
+        assert!(gate.is_open());
+        assert!((*output.get_sample(0, 4799).unwrap() - 0.5).abs() < 1e-3);
+
+        // -60 dB is attenuated by the full range after hold and release
+        let output = run(&mut gate, &[0.001; 48_000], 1);
+        assert!(!gate.is_open());
+        assert!(output.get_sample(0, 47_999).unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn sidechain_keys_the_gate() {
+        let mut gate = Gate::new(SampleRate::new(48_000.0), 1)
+            .with_sidechain(1)
+            .with_threshold(-40.0);
+        assert_eq!(AudioProcessor::<f32>::config(&gate).num_input_channels, 2);
+        assert_eq!(AudioProcessor::<f32>::config(&gate).num_output_channels, 1);
+
+        // a quiet main signal passes while the key is loud
+        let keyed: Vec<f32> = [0.001, 1.0].repeat(4800);
+        let output = run(&mut gate, &keyed, 2);
+        assert!(gate.is_open());
+        assert!((*output.get_sample(0, 4799).unwrap() - 0.001).abs() < 1e-6);
+
+        // a loud main signal is gated once the key goes silent
+        let unkeyed: Vec<f32> = [1.0, 0.0].repeat(48_000);
+        let output = run(&mut gate, &unkeyed, 2);
+        assert!(!gate.is_open());
+        assert!(output.get_sample(0, 47_999).unwrap().abs() < 1e-4);
+    }
+}
@@ -0,0 +1,89 @@
+//! Renders the time based effects offline and compares the result against the
+//! golden files in `golden/`. After an intended change to an effect the files
+//! are rewritten by running the tests with `UPDATE_GOLDEN=1`.
+
+use std::{num::NonZeroUsize, path::PathBuf};
+
+use audio_buffer::{
+    buffers::interleaved::InterleavedBuffer,
+    core::{Buffer, BufferMut},
+};
+use time::{FrameTime, SampleRate};
+
+use crate::processor::{AudioProcessor, chorus::Chorus, phaser::Phaser, reverb::Reverb};
+
+const SAMPLE_RATE: f64 = 8000.0;
+const BLOCK_SIZE: usize = 256;
+const BLOCKS: usize = 8;
+// differences between platforms' floating point functions are tolerated
+const TOLERANCE: f32 = 1e-4;
+
+/// Renders an impulse followed by a sine burst through `processor` block by block
+fn render(mut processor: impl AudioProcessor<f32>) -> Vec<f32> {
+    let channels = NonZeroUsize::new(2).unwrap();
+    let mut input = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(BLOCK_SIZE as u64));
+    let mut output = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(BLOCK_SIZE as u64));
+    let mut rendered = Vec::new();
+
+    for block in 0..BLOCKS {
+        input.map_frames_mut(
+            |frame, index| {
+                let time = block * BLOCK_SIZE + index;
+                let sample = match time {
+                    0 => 1.0,
+                    512..1024 => 0.5 * (time as f32 * 0.1).sin(),
+                    _ => 0.0,
+                };
+                frame.fill(sample);
+                Some(())
+            },
+            None,
+        );
+        processor.process(&input, &mut output.wrap_mut()).unwrap();
+        rendered.extend(output.iter_frames().flatten());
+    }
+
+    rendered
+}
+
+fn assert_golden(name: &str, rendered: &[f32]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(format!("{name}.f32"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let bytes: Vec<u8> = rendered.iter().flat_map(|s| s.to_le_bytes()).collect();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        return;
+    }
+
+    let bytes = std::fs::read(&path)
+        .unwrap_or_else(|_| panic!("{path:?} is missing, run with UPDATE_GOLDEN=1"));
+    let golden: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+
+    assert_eq!(golden.len(), rendered.len());
+    for (index, (golden, rendered)) in golden.iter().zip(rendered).enumerate() {
+        assert!(
+            (golden - rendered).abs() <= TOLERANCE,
+            "{name} differs at sample {index}: {golden} != {rendered}"
+        );
+    }
+}
+
+#[test]
+fn effects_match_golden_files() {
+    let sample_rate = SampleRate::new(SAMPLE_RATE);
+
+    let reverb = || Reverb::new(sample_rate).with_pre_delay(10.0).with_mix(0.5);
+    // a fresh instance renders the same output again
+    assert_eq!(render(reverb()), render(reverb()));
+
+    assert_golden("reverb", &render(reverb()));
+    assert_golden("chorus", &render(Chorus::new(sample_rate, 2)));
+    assert_golden("flanger", &render(Chorus::flanger(sample_rate, 2)));
+    assert_golden("phaser", &render(Phaser::new(sample_rate, 2)));
+}
@@ -0,0 +1,329 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{
+        AudioProcessor, ProcessorConfiguration, gain::decibels_to_gain, process_frames,
+        ramp_frames, time_coefficient,
+    },
+};
+
+pub const CEILING: ParameterId = ParameterId(0);
+pub const RELEASE: ParameterId = ParameterId(1);
+
+/// How far the limiter looks ahead to catch peaks
+pub const LOOKAHEAD_SECONDS: f64 = 0.005;
+
+// length of the interpolation filters used to find peaks between samples
+const TAPS: usize = 8;
+// the detector looks at the sample that is this many frames old
+const DETECTOR_DELAY: usize = TAPS / 2;
+// the detector oversamples by 4 to estimate true peaks
+const PHASES: usize = 3;
+
+/// A brickwall limiter that keeps the true peak level below the ceiling.
+///
+/// Peaks between samples are estimated by oversampling the detector four times.
+/// The gain starts falling before a peak arrives, so the output is delayed by
+/// the lookahead, which is reported through `latency`. With a sidechain the
+/// level is detected on the extra input channels following the main ones.
+pub struct Limiter {
+    channels: usize,
+    sidechain_channels: usize,
+    sample_rate: SampleRate,
+    values: ParameterValues,
+    lookahead: usize,
+    phases: [[f32; TAPS]; PHASES],
+    // the last `TAPS` samples of every key channel
+    history: Vec<[f32; TAPS]>,
+    // interleaved main channels, delayed until their gain is known
+    delay: Vec<f32>,
+    delay_position: usize,
+    // (frame, gain) pairs with increasing gains, their front is the minimum
+    minimum: VecDeque<(usize, f32)>,
+    envelope: f32,
+    // the last `lookahead + 1` envelope values and their sum
+    average: Vec<f32>,
+    average_sum: f64,
+    frame: usize,
+    scratch: Vec<f32>,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        let lookahead = ((sample_rate.as_f64() * LOOKAHEAD_SECONDS) as usize).max(1);
+
+        // windowed sinc filters that interpolate a quarter, half and three
+        // quarters of a frame after the sample the detector looks at
+        let mut phases = [[0.0; TAPS]; PHASES];
+        for (phase, coefficients) in phases.iter_mut().enumerate() {
+            let fraction = (phase + 1) as f32 / (PHASES + 1) as f32;
+            for (tap, coefficient) in coefficients.iter_mut().enumerate() {
+                let x = fraction - (tap as f32 - (TAPS - 1 - DETECTOR_DELAY) as f32);
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let window = 0.5 * (1.0 + (PI * x / (TAPS / 2) as f32).cos());
+                *coefficient = sinc * window;
+            }
+            let sum: f32 = coefficients.iter().sum();
+            coefficients.iter_mut().for_each(|c| *c /= sum);
+        }
+
+        let mut limiter = Self {
+            channels,
+            sidechain_channels: 0,
+            sample_rate,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(CEILING, "Ceiling", -24.0..=0.0, -1.0)
+                        .with_unit(ParameterUnit::Decibels),
+                    ParameterDescriptor::new(RELEASE, "Release", 1.0..=1000.0, 50.0)
+                        .with_unit(ParameterUnit::Milliseconds),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            lookahead,
+            phases,
+            history: Vec::new(),
+            delay: vec![0.0; (lookahead + DETECTOR_DELAY) * channels],
+            delay_position: 0,
+            minimum: VecDeque::with_capacity(lookahead + 2),
+            envelope: 1.0,
+            average: vec![1.0; lookahead + 1],
+            average_sum: (lookahead + 1) as f64,
+            frame: 0,
+            scratch: Vec::new(),
+        };
+        limiter.resize_key(0);
+        limiter
+    }
+
+    /// Adds input channels after the main ones that drive the detector
+    pub fn with_sidechain(mut self, channels: usize) -> Self {
+        self.resize_key(channels);
+        self
+    }
+
+    pub fn with_ceiling(mut self, decibels: f32) -> Self {
+        self.values.reset(CEILING, decibels);
+        self
+    }
+
+    /// Release in milliseconds
+    pub fn with_release(mut self, release: f32) -> Self {
+        self.values.reset(RELEASE, release);
+        self
+    }
+
+    /// The current gain, e.g. for metering
+    pub fn gain(&self) -> f32 {
+        (self.average_sum / self.average.len() as f64) as f32
+    }
+
+    fn resize_key(&mut self, sidechain_channels: usize) {
+        self.sidechain_channels = sidechain_channels;
+        let key = if sidechain_channels > 0 {
+            sidechain_channels
+        } else {
+            self.channels
+        };
+        self.history = vec![[0.0; TAPS]; key];
+        self.scratch = vec![0.0; self.channels + sidechain_channels];
+    }
+
+    /// Returns the true peak of the key samples around the detector position
+    fn detect(&mut self, key: &[f32]) -> f32 {
+        let mut peak = 0f32;
+        for (history, &sample) in self.history.iter_mut().zip(key) {
+            history.copy_within(1.., 0);
+            history[TAPS - 1] = sample;
+
+            peak = peak.max(history[TAPS - 1 - DETECTOR_DELAY].abs());
+            for coefficients in &self.phases {
+                let interpolated: f32 = history.iter().zip(coefficients).map(|(x, c)| x * c).sum();
+                peak = peak.max(interpolated.abs());
+            }
+        }
+        peak
+    }
+
+    /// Advances the gain envelope by a frame, `required` being the gain
+    /// that keeps the newest detected peak below the ceiling
+    fn next_gain(&mut self, required: f32, release: f32) -> f32 {
+        while self
+            .minimum
+            .back()
+            .is_some_and(|&(_, gain)| gain >= required)
+        {
+            self.minimum.pop_back();
+        }
+        self.minimum.push_back((self.frame, required));
+        while self
+            .minimum
+            .front()
+            .is_some_and(|&(frame, _)| frame + self.lookahead < self.frame)
+        {
+            self.minimum.pop_front();
+        }
+        let minimum = self.minimum.front().map_or(1.0, |&(_, gain)| gain);
+
+        self.envelope = if minimum < self.envelope {
+            minimum
+        } else {
+            minimum + release * (self.envelope - minimum)
+        };
+
+        // averaging over the lookahead smooths the attack without letting peaks through
+        let slot = self.frame % self.average.len();
+        self.average_sum += f64::from(self.envelope) - f64::from(self.average[slot]);
+        self.average[slot] = self.envelope;
+        self.frame += 1;
+
+        (self.average_sum / self.average.len() as f64) as f32
+    }
+}
+
+impl<T> AudioProcessor<T> for Limiter
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let channels = self.channels;
+        let delay_frames = self.lookahead + DETECTOR_DELAY;
+
+        process_frames(input, output, &mut scratch, |_, frame| {
+            let ceiling = decibels_to_gain(self.values.next_value(CEILING));
+            let release =
+                time_coefficient(self.values.next_value(RELEASE) / 1000.0, self.sample_rate);
+
+            let key = if self.sidechain_channels > 0 {
+                &frame[channels..]
+            } else {
+                &frame[..channels]
+            };
+            let peak = self.detect(key);
+            let required = if peak > ceiling { ceiling / peak } else { 1.0 };
+            let gain = self.next_gain(required, release);
+
+            let position = self.delay_position * channels;
+            for (sample, delayed) in frame[..channels]
+                .iter_mut()
+                .zip(&mut self.delay[position..position + channels])
+            {
+                let current = std::mem::replace(delayed, *sample);
+                *sample = (current * gain).clamp(-ceiling, ceiling);
+            }
+            self.delay_position = (self.delay_position + 1) % delay_frames;
+        });
+
+        self.scratch = scratch;
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels + self.sidechain_channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+
+    fn latency(&self) -> usize {
+        self.lookahead + DETECTOR_DELAY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use audio_buffer::{
+        buffers::interleaved::InterleavedBuffer,
+        core::{Buffer, BufferMut},
+    };
+    use time::{FrameTime, SampleRate};
+
+    use crate::processor::{AudioProcessor, gain::decibels_to_gain, limiter::Limiter};
+
+    #[test]
+    fn output_stays_below_the_ceiling() {
+        let mut limiter = Limiter::new(SampleRate::new(48_000.0), 1).with_ceiling(-1.0);
+        let latency = AudioProcessor::<f32>::latency(&limiter);
+        assert_eq!(latency, 240 + 4);
+
+        let channels = NonZeroUsize::new(1).unwrap();
+        let mut input = InterleavedBuffer::<f32>::with_shape(channels, FrameTime(4800));
+        input.map_frames_mut(
+            |frame, index| {
+                frame[0] = 2.0 * (index as f32 * 0.3).sin();
+                Some(())
+            },
+            None,
+        );
+        let mut output = InterleavedBuffer::with_shape(channels, FrameTime(4800));
+        limiter.process(&input, &mut output.wrap_mut()).unwrap();
+
+        let ceiling = decibels_to_gain(-1.0);
+        let peak = output
+            .iter_frames()
+            .skip(latency)
+            .fold(0f32, |peak, frame| peak.max(frame[0].abs()));
+        assert!(peak <= ceiling + 1e-6);
+        assert!(peak > ceiling * 0.8);
+    }
+
+    #[test]
+    fn gain_is_down_before_peaks_leave_the_delay() {
+        let mut limiter = Limiter::new(SampleRate::new(48_000.0), 1).with_ceiling(-1.0);
+        let latency = AudioProcessor::<f32>::latency(&limiter);
+        let ceiling = decibels_to_gain(-1.0);
+
+        // the output clamps to the ceiling, so the gain is checked frame by frame
+        // against the sample leaving the delay line before it would be clamped
+        let channels = NonZeroUsize::new(1).unwrap();
+        let signal: Vec<f32> = (0..4800)
+            .map(|index| 2.0 * (index as f32 * 0.3).sin())
+            .collect();
+        let mut output = InterleavedBuffer::with_shape(channels, FrameTime(1));
+        for (index, &sample) in signal.iter().enumerate() {
+            let input = InterleavedBuffer::from_vec(vec![sample], channels).unwrap();
+            limiter.process(&input, &mut output.wrap_mut()).unwrap();
+
+            if let Some(delayed) = index.checked_sub(latency).map(|index| signal[index]) {
+                assert!(
+                    delayed.abs() * limiter.gain() <= ceiling + 1e-6,
+                    "frame {index} leaves the delay with {delayed} at gain {}",
+                    limiter.gain()
+                );
+            }
+        }
+    }
+}
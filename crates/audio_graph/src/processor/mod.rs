@@ -0,0 +1,324 @@
+use audio_buffer::{
+    buffers::{
+        compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer,
+        planar::PlanarBuffer,
+    },
+    core::{Buffer, BufferMut, io::mix_buffers},
+    dasp::{self, Sample},
+};
+
+pub mod chorus;
+pub mod compressor;
+pub mod dc_blocker;
+pub mod delay;
+pub mod eq;
+pub mod filter;
+pub mod gain;
+pub mod gate;
+#[cfg(test)]
+mod golden;
+pub mod limiter;
+mod modulation;
+pub mod pan;
+pub mod phaser;
+pub mod reverb;
+pub mod sampler;
+
+use crate::{
+    error::ProcessingError,
+    event::MidiEvent,
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp},
+};
+
+/// A node of an `AudioGraph`.
+///
+/// Breaking change in 0.2: processors write into a `WrapInterleavedMut` instead of an
+/// `&mut InterleavedBuffer`, so the graph can render its output node straight into a
+/// borrowed slice such as the buffer of the output device. Implementations outside of
+/// this crate have to change the type of `output`. It implements `Buffer` and
+/// `BufferMut` like the buffer did, and callers can pass an `InterleavedBuffer`
+/// through `InterleavedBuffer::wrap_mut`.
+pub trait AudioProcessor<T: dasp::Sample>: Send {
+    fn process(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) -> Result<(), ProcessingError> {
+        let config = self.config();
+        if config.num_input_channels != input.channels()
+            || config.num_output_channels != output.channels()
+        {
+            Err(ProcessingError::InvalidBuffers)
+        } else {
+            self.process_unchecked(input, output);
+            Ok(())
+        }
+    }
+
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    );
+
+    /// Processors that work on whole channels at a time, e.g. filters, return `true`
+    /// to get their input as contiguous channels through `process_planar` when they
+    /// run inside an `AudioGraph`. The graph then mixes their parents straight into
+    /// a `PlanarBuffer` instead of interleaving them first.
+    fn planar_input(&self) -> bool {
+        false
+    }
+
+    /// Like `process_unchecked` with the input deinterleaved. The graph only
+    /// calls this if `planar_input` returns `true`, the default leaves the
+    /// output silent.
+    fn process_planar(
+        &mut self,
+        _input: &PlanarBuffer<T>,
+        _output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+    }
+
+    fn config(&self) -> ProcessorConfiguration;
+
+    /// Describes the parameters that can be changed at runtime
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        &[]
+    }
+
+    fn get_parameter(&self, _id: ParameterId) -> Option<f32> {
+        None
+    }
+
+    /// Sets a parameter, taking effect at the start of the next block.
+    /// Returns `false` if the processor doesn't have such a parameter.
+    fn set_parameter(&mut self, _id: ParameterId, _value: f32) -> bool {
+        false
+    }
+
+    /// Schedules the ramps automation computed for the next block. Processors
+    /// that don't follow them sample-accurately jump to the final value instead.
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        match ramps.last() {
+            Some(ramp) => self.set_parameter(id, ramp.value),
+            None => self.get_parameter(id).is_some(),
+        }
+    }
+
+    /// Hands the events of the next block to the processor, sorted by offset.
+    /// Processors that don't consume events ignore them.
+    fn schedule_events(&mut self, _events: &[MidiEvent]) {}
+
+    /// How many frames the output lags behind the input, e.g. because of
+    /// lookahead. The graph delays parallel paths by the difference.
+    fn latency(&self) -> usize {
+        0
+    }
+
+    /// Number of additional outputs connections can tap, see `Tap::Auxiliary`
+    fn auxiliary_outputs(&self) -> usize {
+        0
+    }
+
+    /// An additional output of the last processed block. It has
+    /// as many channels and frames as the main output.
+    fn auxiliary_output(&self, _index: usize) -> Option<&InterleavedBuffer<T>> {
+        None
+    }
+}
+
+impl<T, S> AudioProcessor<S> for Box<T>
+where
+    T: AudioProcessor<S> + ?Sized,
+    S: dasp::Sample,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<S>,
+        output: &mut WrapInterleavedMut<'_, S>,
+    ) {
+        (**self).process_unchecked(input, output);
+    }
+
+    fn planar_input(&self) -> bool {
+        (**self).planar_input()
+    }
+
+    fn process_planar(&mut self, input: &PlanarBuffer<S>, output: &mut WrapInterleavedMut<'_, S>) {
+        (**self).process_planar(input, output);
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        (**self).config()
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        (**self).parameters()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        (**self).get_parameter(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        (**self).set_parameter(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        (**self).automate_parameter(id, ramps)
+    }
+
+    fn schedule_events(&mut self, events: &[MidiEvent]) {
+        (**self).schedule_events(events);
+    }
+
+    fn latency(&self) -> usize {
+        (**self).latency()
+    }
+
+    fn auxiliary_outputs(&self) -> usize {
+        (**self).auxiliary_outputs()
+    }
+
+    fn auxiliary_output(&self, index: usize) -> Option<&InterleavedBuffer<S>> {
+        (**self).auxiliary_output(index)
+    }
+}
+
+pub struct AudioNode<T>
+where
+    T: audio_buffer::dasp::Sample,
+{
+    processor: Box<dyn AudioProcessor<T>>,
+}
+
+impl<T> AudioNode<T>
+where
+    T: audio_buffer::dasp::Sample,
+{
+    pub fn new(processor: Box<dyn AudioProcessor<T>>) -> Self {
+        Self { processor }
+    }
+
+    pub fn get_processor(&self) -> &(dyn AudioProcessor<T> + 'static) {
+        self.processor.as_ref()
+    }
+
+    pub fn get_processor_mut(&mut self) -> &mut Box<dyn AudioProcessor<T> + 'static> {
+        &mut self.processor
+    }
+}
+
+pub struct PassThrough {
+    num_input_channels: usize,
+    num_output_channels: usize,
+}
+
+impl PassThrough {
+    pub fn new(input_channels: usize, output_channels: usize) -> Self {
+        Self {
+            num_input_channels: input_channels,
+            num_output_channels: output_channels,
+        }
+    }
+}
+
+impl<T> AudioProcessor<T> for PassThrough
+where
+    T: audio_buffer::dasp::Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        mix_buffers(input, output, None).expect("this is the unchecked method");
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.num_input_channels,
+            num_output_channels: self.num_output_channels,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessorConfiguration {
+    // If these will ever be reconfigurable they will
+    // probably have to be stored here
+    // pub sample_rate: SampleRate,
+    // pub block_size: usize,
+    pub num_input_channels: usize,
+    pub num_output_channels: usize,
+}
+
+/// Length of the ramp used when a parameter of a built-in processor changes
+pub const RAMP_SECONDS: f64 = 0.02;
+
+/// `RAMP_SECONDS` in frames at `sample_rate`
+pub fn ramp_frames(sample_rate: time::SampleRate) -> usize {
+    (sample_rate.as_f64() * RAMP_SECONDS) as usize
+}
+
+pub(crate) fn to_f32<T: Sample>(sample: T) -> f32 {
+    sample.to_float_sample().to_sample()
+}
+
+pub(crate) fn from_f32<T: Sample>(value: f32) -> T {
+    T::Float::from_sample(value).to_sample()
+}
+
+/// Copies `input` into `output` and calls `f` with the index and the samples
+/// of every input frame converted to `f32`, writing as many of them back as
+/// the output has channels. Extra input channels, e.g. a sidechain, are
+/// only read. `scratch` must hold a sample for every input and output channel.
+pub(crate) fn process_frames<T, F>(
+    input: &InterleavedBuffer<T>,
+    output: &mut WrapInterleavedMut<'_, T>,
+    scratch: &mut [f32],
+    mut f: F,
+) where
+    T: Sample + 'static,
+    F: FnMut(usize, &mut [f32]),
+{
+    output.map_frames_mut(
+        |frame, index| {
+            let samples = &mut scratch[..input.channels().max(frame.len())];
+            samples.fill(0.0);
+            if let Some(input) = input.get_frame(index) {
+                for (sample, &x) in samples.iter_mut().zip(input) {
+                    *sample = to_f32(x);
+                }
+            }
+
+            f(index, samples);
+
+            for (out, &sample) in frame.iter_mut().zip(samples.iter()) {
+                *out = from_f32(sample);
+            }
+            Some(())
+        },
+        None,
+    );
+}
+
+/// Converts a time constant into the coefficient of a one pole smoother
+pub(crate) fn time_coefficient(seconds: f32, sample_rate: time::SampleRate) -> f32 {
+    if seconds <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (seconds * sample_rate.as_f64() as f32)).exp()
+    }
+}
+
+/// The peak level of a frame's key signal, which is the sidechain if the
+/// frame has more than `channels` samples and the main signal otherwise
+pub(crate) fn key_peak(frame: &[f32], channels: usize) -> f32 {
+    let key = if frame.len() > channels {
+        &frame[channels..]
+    } else {
+        frame
+    };
+    key.iter().fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
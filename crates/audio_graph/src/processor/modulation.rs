@@ -0,0 +1,87 @@
+use std::f64::consts::TAU;
+
+use time::SampleRate;
+
+/// A sine oscillator that modulates every channel with its own phase offset.
+/// It always starts at phase zero, so renders are reproducible.
+pub(crate) struct Lfo {
+    sample_rate: f64,
+    phase: f64,
+}
+
+impl Lfo {
+    pub(crate) fn new(sample_rate: SampleRate) -> Self {
+        Self {
+            sample_rate: sample_rate.as_f64(),
+            phase: 0.0,
+        }
+    }
+
+    /// Returns the value in `-1.0..=1.0` of a channel offset by
+    /// `offset` cycles, call `advance` once per frame
+    pub(crate) fn value(&self, offset: f64) -> f32 {
+        ((self.phase + offset) * TAU).sin() as f32
+    }
+
+    pub(crate) fn advance(&mut self, rate: f32) {
+        self.phase = (self.phase + f64::from(rate) / self.sample_rate).fract();
+    }
+}
+
+/// Phase offset of a channel, spreading the channels evenly over
+/// half a cycle so stereo pairs are modulated in opposite directions
+pub(crate) fn channel_offset(channel: usize, channels: usize) -> f64 {
+    if channels > 1 {
+        channel as f64 / (channels - 1) as f64 * 0.5
+    } else {
+        0.0
+    }
+}
+
+/// A delay line per channel that can be read between frames
+pub(crate) struct ModulatedDelay {
+    channels: usize,
+    // interleaved ring buffer
+    line: Vec<f32>,
+    position: usize,
+}
+
+impl ModulatedDelay {
+    pub(crate) fn new(channels: usize, max_frames: usize) -> Self {
+        Self {
+            channels,
+            line: vec![0.0; (max_frames + 2) * channels],
+            position: 0,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.line.len() / self.channels.max(1)
+    }
+
+    /// Reads a channel `delay` frames before the frame that is written next,
+    /// interpolating linearly between frames
+    pub(crate) fn read(&self, channel: usize, delay: f32) -> f32 {
+        let capacity = self.capacity();
+        let delay = delay.clamp(1.0, (capacity - 2) as f32);
+        let whole = delay as usize;
+        let fraction = delay - whole as f32;
+
+        let sample = |frames: usize| {
+            self.line[(self.position + capacity - frames) % capacity * self.channels + channel]
+        };
+        let newer = sample(whole);
+        let older = sample(whole + 1);
+        newer + (older - newer) * fraction
+    }
+
+    /// Writes a channel of the next frame
+    pub(crate) fn write(&mut self, channel: usize, sample: f32) {
+        self.line[self.position * self.channels + channel] = sample;
+    }
+
+    /// Moves on to the next frame once every channel has been written
+    pub(crate) fn advance(&mut self) {
+        self.position = (self.position + 1) % self.capacity();
+    }
+}
@@ -0,0 +1,169 @@
+use std::f32::consts::FRAC_PI_4;
+
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    core::{Buffer, BufferMut},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterValues},
+    processor::{AudioProcessor, ProcessorConfiguration, from_f32, ramp_frames, to_f32},
+};
+
+pub const PAN: ParameterId = ParameterId(0);
+pub const LAW: ParameterId = ParameterId(1);
+
+/// Determines how the level of a stereo signal changes while it is panned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanLaw {
+    /// Both sides are attenuated by -6 dB in the center
+    Linear,
+    /// Both sides are attenuated by -3 dB in the center so the
+    /// perceived loudness stays the same across the stereo field
+    ConstantPower,
+    /// The center is at unity gain and panning only attenuates the opposite side
+    #[default]
+    Balance,
+}
+
+impl PanLaw {
+    const ALL: [PanLaw; 3] = [PanLaw::Linear, PanLaw::ConstantPower, PanLaw::Balance];
+
+    /// Returns the gain of the left and right channel for a pan position in `-1.0..=1.0`
+    pub fn gains(self, pan: f32) -> (f32, f32) {
+        let pan = pan.clamp(-1.0, 1.0);
+
+        match self {
+            PanLaw::Linear => ((1.0 - pan) * 0.5, (1.0 + pan) * 0.5),
+            PanLaw::ConstantPower => {
+                let angle = (pan + 1.0) * FRAC_PI_4;
+                (angle.cos(), angle.sin())
+            }
+            PanLaw::Balance => ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0)),
+        }
+    }
+}
+
+/// Positions a signal in the stereo field.
+///
+/// A mono input is spread onto two output channels. Other inputs keep their channel
+/// count and are treated as left/right pairs, a trailing odd channel stays untouched.
+pub struct Panner {
+    input_channels: usize,
+    values: ParameterValues,
+}
+
+impl Panner {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            input_channels: channels,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(PAN, "Pan", -1.0..=1.0, 0.0),
+                    ParameterDescriptor::new(LAW, "Pan Law", 0.0..=2.0, 2.0).stepped(),
+                ],
+                ramp_frames(sample_rate),
+            ),
+        }
+    }
+
+    pub fn with_pan(mut self, pan: f32) -> Self {
+        self.values.reset(PAN, pan);
+        self
+    }
+
+    pub fn with_law(mut self, law: PanLaw) -> Self {
+        let index = PanLaw::ALL.iter().position(|&l| l == law).unwrap_or(2);
+        self.values.reset(LAW, index as f32);
+        self
+    }
+}
+
+impl<T> AudioProcessor<T> for Panner
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let values = &mut self.values;
+        let mono = self.input_channels == 1;
+
+        output.map_frames_mut(
+            |frame, index| {
+                let pan = values.next_value(PAN);
+                let law = PanLaw::ALL[(values.next_value(LAW) as usize).min(2)];
+                let (left, right) = law.gains(pan);
+
+                let Some(input) = input.get_frame(index) else {
+                    return Some(());
+                };
+
+                if mono {
+                    let sample = to_f32(input[0]);
+                    frame[0] = from_f32(sample * left);
+                    frame[1] = from_f32(sample * right);
+                    return Some(());
+                }
+
+                let unpaired = input.len() % 2 == 1;
+                for (channel, (out, &sample)) in frame.iter_mut().zip(input).enumerate() {
+                    let gain = if unpaired && channel + 1 == input.len() {
+                        1.0
+                    } else if channel % 2 == 0 {
+                        left
+                    } else {
+                        right
+                    };
+                    *out = from_f32(to_f32(sample) * gain);
+                }
+                Some(())
+            },
+            None,
+        );
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.input_channels,
+            num_output_channels: self.input_channels.max(2),
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::processor::pan::PanLaw;
+
+    #[test]
+    fn pan_laws_at_center() {
+        let (left, right) = PanLaw::Linear.gains(0.0);
+        assert_eq!((left, right), (0.5, 0.5));
+
+        let (left, right) = PanLaw::ConstantPower.gains(0.0);
+        assert!((left * left + right * right - 1.0).abs() < 1e-6);
+
+        assert_eq!(PanLaw::Balance.gains(0.0), (1.0, 1.0));
+        assert_eq!(PanLaw::Balance.gains(-1.0), (1.0, 0.0));
+    }
+}
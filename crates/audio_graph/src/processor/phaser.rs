@@ -0,0 +1,162 @@
+use std::f32::consts::PI;
+
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{
+        AudioProcessor, ProcessorConfiguration,
+        modulation::{Lfo, channel_offset},
+        process_frames, ramp_frames,
+    },
+};
+
+pub const RATE: ParameterId = ParameterId(0);
+pub const FREQUENCY: ParameterId = ParameterId(1);
+pub const DEPTH: ParameterId = ParameterId(2);
+pub const FEEDBACK: ParameterId = ParameterId(3);
+pub const MIX: ParameterId = ParameterId(4);
+
+/// Number of allpass stages, every two of them add a notch
+pub const STAGES: usize = 6;
+
+#[derive(Clone, Copy, Default)]
+struct Stage {
+    input: f32,
+    output: f32,
+}
+
+/// Sweeps notches over the signal with a chain of allpass filters
+/// whose frequency is modulated around `FREQUENCY` by `DEPTH` octaves.
+pub struct Phaser {
+    channels: usize,
+    sample_rate: f32,
+    values: ParameterValues,
+    lfo: Lfo,
+    stages: Vec<[Stage; STAGES]>,
+    // output of the last stage per channel, fed back into the first
+    last: Vec<f32>,
+    scratch: Vec<f32>,
+}
+
+impl Phaser {
+    pub fn new(sample_rate: SampleRate, channels: usize) -> Self {
+        Self {
+            channels,
+            sample_rate: sample_rate.as_f64() as f32,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(RATE, "Rate", 0.01..=10.0, 0.5)
+                        .with_unit(ParameterUnit::Hertz),
+                    ParameterDescriptor::new(FREQUENCY, "Frequency", 50.0..=5000.0, 800.0)
+                        .with_unit(ParameterUnit::Hertz),
+                    ParameterDescriptor::new(DEPTH, "Depth", 0.0..=4.0, 2.0),
+                    ParameterDescriptor::new(FEEDBACK, "Feedback", -0.95..=0.95, 0.5),
+                    ParameterDescriptor::new(MIX, "Mix", 0.0..=1.0, 0.5),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            lfo: Lfo::new(sample_rate),
+            stages: vec![[Stage::default(); STAGES]; channels],
+            last: vec![0.0; channels],
+            scratch: vec![0.0; channels],
+        }
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.values.reset(RATE, rate);
+        self
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.values.reset(FREQUENCY, frequency);
+        self
+    }
+
+    /// Depth of the sweep in octaves
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.values.reset(DEPTH, depth);
+        self
+    }
+
+    pub fn with_feedback(mut self, feedback: f32) -> Self {
+        self.values.reset(FEEDBACK, feedback);
+        self
+    }
+
+    pub fn with_mix(mut self, mix: f32) -> Self {
+        self.values.reset(MIX, mix);
+        self
+    }
+}
+
+impl<T> AudioProcessor<T> for Phaser
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let channels = self.channels;
+        let nyquist = self.sample_rate * 0.49;
+
+        process_frames(input, output, &mut scratch, |_, frame| {
+            let rate = self.values.next_value(RATE);
+            let center = self.values.next_value(FREQUENCY);
+            let depth = self.values.next_value(DEPTH);
+            let feedback = self.values.next_value(FEEDBACK);
+            let mix = self.values.next_value(MIX);
+
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let modulation = self.lfo.value(channel_offset(channel, channels));
+                let frequency = (center * (depth * modulation).exp2()).min(nyquist);
+                let tan = (PI * frequency / self.sample_rate).tan();
+                let coefficient = (tan - 1.0) / (tan + 1.0);
+
+                let mut signal = *sample + self.last[channel] * feedback;
+                for stage in &mut self.stages[channel] {
+                    let output = coefficient * signal + stage.input - coefficient * stage.output;
+                    stage.input = signal;
+                    stage.output = output;
+                    signal = output;
+                }
+                self.last[channel] = signal;
+
+                *sample = *sample * (1.0 - mix) + signal * mix;
+            }
+            self.lfo.advance(rate);
+        });
+
+        self.scratch = scratch;
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
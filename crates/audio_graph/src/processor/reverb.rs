@@ -0,0 +1,245 @@
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    dasp::Sample,
+};
+use time::SampleRate;
+
+use crate::{
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{AudioProcessor, ProcessorConfiguration, process_frames, ramp_frames},
+};
+
+pub const SIZE: ParameterId = ParameterId(0);
+pub const DAMPING: ParameterId = ParameterId(1);
+pub const PRE_DELAY: ParameterId = ParameterId(2);
+pub const WIDTH: ParameterId = ParameterId(3);
+pub const MIX: ParameterId = ParameterId(4);
+
+/// Longest pre-delay in milliseconds
+pub const MAX_PRE_DELAY: f32 = 250.0;
+
+// the tunings of Freeverb, in frames at 44.1 kHz
+const COMBS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASSES: [usize; 4] = [556, 441, 341, 225];
+const STEREO_SPREAD: usize = 23;
+const TUNING_SAMPLE_RATE: f64 = 44_100.0;
+
+const INPUT_GAIN: f32 = 0.015;
+const WET_GAIN: f32 = 3.0;
+const ROOM_SCALE: f32 = 0.28;
+const ROOM_OFFSET: f32 = 0.7;
+const DAMPING_SCALE: f32 = 0.4;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+struct Comb {
+    buffer: Vec<f32>,
+    position: usize,
+    filter: f32,
+}
+
+impl Comb {
+    fn new(frames: usize) -> Self {
+        Self {
+            buffer: vec![0.0; frames.max(1)],
+            position: 0,
+            filter: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.position];
+        self.filter = output * (1.0 - damping) + self.filter * damping;
+        self.buffer[self.position] = input + self.filter * feedback;
+        self.position = (self.position + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct Allpass {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl Allpass {
+    fn new(frames: usize) -> Self {
+        Self {
+            buffer: vec![0.0; frames.max(1)],
+            position: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.position];
+        self.buffer[self.position] = input + delayed * ALLPASS_FEEDBACK;
+        self.position = (self.position + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// The combs and allpasses of one output channel
+struct Tank {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl Tank {
+    fn new(sample_rate: SampleRate, spread: usize) -> Self {
+        let scale = |frames: usize| {
+            ((frames + spread) as f64 * sample_rate.as_f64() / TUNING_SAMPLE_RATE) as usize
+        };
+        Self {
+            combs: COMBS
+                .iter()
+                .map(|&frames| Comb::new(scale(frames)))
+                .collect(),
+            allpasses: ALLPASSES
+                .iter()
+                .map(|&frames| Allpass::new(scale(frames)))
+                .collect(),
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let mut output = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(input, feedback, damping))
+            .sum();
+        for allpass in &mut self.allpasses {
+            output = allpass.process(output);
+        }
+        output
+    }
+}
+
+/// A stereo reverb after Freeverb by Jezar at Dreampoint: eight parallel
+/// combs with damped feedback followed by four allpasses per side.
+///
+/// The reverb is fully deterministic, rendering the same input from a
+/// freshly created instance always produces the same output.
+pub struct Reverb {
+    sample_rate: SampleRate,
+    values: ParameterValues,
+    tanks: [Tank; 2],
+    // mono input of the tanks, delayed by the pre-delay
+    pre_delay: Vec<f32>,
+    pre_delay_position: usize,
+    scratch: Vec<f32>,
+}
+
+impl Reverb {
+    pub fn new(sample_rate: SampleRate) -> Self {
+        let pre_delay = (f64::from(MAX_PRE_DELAY) / 1000.0 * sample_rate.as_f64()) as usize + 1;
+
+        Self {
+            sample_rate,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(SIZE, "Size", 0.0..=1.0, 0.5),
+                    ParameterDescriptor::new(DAMPING, "Damping", 0.0..=1.0, 0.5),
+                    ParameterDescriptor::new(PRE_DELAY, "Pre-Delay", 0.0..=MAX_PRE_DELAY, 0.0)
+                        .with_unit(ParameterUnit::Milliseconds),
+                    ParameterDescriptor::new(WIDTH, "Width", 0.0..=1.0, 1.0),
+                    ParameterDescriptor::new(MIX, "Mix", 0.0..=1.0, 0.3),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            tanks: [
+                Tank::new(sample_rate, 0),
+                Tank::new(sample_rate, STEREO_SPREAD),
+            ],
+            pre_delay: vec![0.0; pre_delay],
+            pre_delay_position: 0,
+            scratch: vec![0.0; 2],
+        }
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.values.reset(SIZE, size);
+        self
+    }
+
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.values.reset(DAMPING, damping);
+        self
+    }
+
+    /// Pre-delay in milliseconds
+    pub fn with_pre_delay(mut self, pre_delay: f32) -> Self {
+        self.values.reset(PRE_DELAY, pre_delay);
+        self
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.values.reset(WIDTH, width);
+        self
+    }
+
+    pub fn with_mix(mut self, mix: f32) -> Self {
+        self.values.reset(MIX, mix);
+        self
+    }
+}
+
+impl<T> AudioProcessor<T> for Reverb
+where
+    T: Sample + 'static,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let frames_per_ms = self.sample_rate.as_f64() as f32 / 1000.0;
+
+        process_frames(input, output, &mut scratch, |_, frame| {
+            let feedback = self.values.next_value(SIZE) * ROOM_SCALE + ROOM_OFFSET;
+            let damping = self.values.next_value(DAMPING) * DAMPING_SCALE;
+            let pre_delay = (self.values.next_value(PRE_DELAY) * frames_per_ms) as usize;
+            let width = self.values.next_value(WIDTH);
+            let mix = self.values.next_value(MIX);
+
+            let length = self.pre_delay.len();
+            self.pre_delay[self.pre_delay_position] = (frame[0] + frame[1]) * INPUT_GAIN;
+            let delayed = self.pre_delay
+                [(self.pre_delay_position + length - pre_delay.min(length - 1)) % length];
+            self.pre_delay_position = (self.pre_delay_position + 1) % length;
+
+            let left = self.tanks[0].process(delayed, feedback, damping);
+            let right = self.tanks[1].process(delayed, feedback, damping);
+
+            let wet = mix * WET_GAIN;
+            let direct = wet * (width / 2.0 + 0.5);
+            let crossed = wet * ((1.0 - width) / 2.0);
+            let dry = 1.0 - mix;
+            frame[0] = left * direct + right * crossed + frame[0] * dry;
+            frame[1] = right * direct + left * crossed + frame[1] * dry;
+        });
+
+        self.scratch = scratch;
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: 2,
+            num_output_channels: 2,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+}
@@ -0,0 +1,745 @@
+use std::{
+    num::NonZeroUsize,
+    ops::{Range, RangeInclusive},
+    path::Path,
+    sync::Arc,
+};
+
+use audio_buffer::{
+    SharedSample,
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    core::{
+        Buffer, BufferMut,
+        io::{mix_buffers, mix_buffers_region},
+    },
+    dasp::Sample,
+    loader::{error::LoadError, probe::probe_file},
+    symphonia::core::conv::ConvertibleSample,
+};
+use time::{FrameTime, SampleRate};
+
+use crate::{
+    event::{MidiEvent, MidiMessage},
+    parameter::{ParameterDescriptor, ParameterId, ParameterRamp, ParameterUnit, ParameterValues},
+    processor::{AudioProcessor, ProcessorConfiguration, ramp_frames},
+};
+
+/// Key that is assumed to be recorded in a sample without a `smpl` chunk
+const DEFAULT_ROOT_KEY: u8 = 60;
+
+const ALL_SOUND_OFF: u8 = 120;
+const ALL_NOTES_OFF: u8 = 123;
+
+/// Audio material of a `Zone` together with the information needed to pitch it.
+pub struct SampleData<T> {
+    pub buffer: Arc<InterleavedBuffer<T>>,
+    pub sample_rate: SampleRate,
+    /// The key at which the sample plays at its original pitch
+    pub root_key: u8,
+    /// Frames that are repeated while the note is held
+    pub loop_frames: Option<Range<usize>>,
+}
+
+impl<T> SampleData<T> {
+    pub fn new(buffer: Arc<InterleavedBuffer<T>>, sample_rate: SampleRate, root_key: u8) -> Self {
+        Self {
+            buffer,
+            sample_rate,
+            root_key,
+            loop_frames: None,
+        }
+    }
+
+    pub fn with_loop(mut self, frames: Range<usize>) -> Self {
+        self.loop_frames = (frames.start < frames.end).then_some(frames);
+        self
+    }
+}
+
+impl<T> SampleData<T>
+where
+    T: ConvertibleSample + Sample + 'static,
+{
+    /// Loads an audio file. The root key and loop points are read
+    /// from the `smpl` chunk of WAV files if there is one.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let sample_rate = probe_file(path, None)?.sample_rate;
+        let buffer = audio_buffer::loader::load(path)?;
+
+        let mut sample = Self::new(
+            Arc::new(buffer),
+            SampleRate::from(sample_rate),
+            DEFAULT_ROOT_KEY,
+        );
+        if let Some(info) = std::fs::read(path).ok().and_then(|bytes| read_smpl(&bytes)) {
+            sample.root_key = info.root_key;
+            if let Some(frames) = info.loop_frames {
+                sample = sample.with_loop(frames);
+            }
+        }
+        Ok(sample)
+    }
+}
+
+impl<T> Clone for SampleData<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone(),
+            sample_rate: self.sample_rate,
+            root_key: self.root_key,
+            loop_frames: self.loop_frames.clone(),
+        }
+    }
+}
+
+/// Maps a range of keys and velocities onto a sample.
+pub struct Zone<T> {
+    pub sample: SampleData<T>,
+    pub keys: RangeInclusive<u8>,
+    pub velocities: RangeInclusive<u8>,
+    /// One-shot zones ignore note offs and always play the whole sample, e.g. for drums
+    pub one_shot: bool,
+}
+
+impl<T> Zone<T> {
+    /// A zone that covers every key and velocity
+    pub fn new(sample: SampleData<T>) -> Self {
+        Self {
+            sample,
+            keys: 0..=127,
+            velocities: 1..=127,
+            one_shot: false,
+        }
+    }
+
+    pub fn with_keys(mut self, keys: RangeInclusive<u8>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    pub fn with_velocities(mut self, velocities: RangeInclusive<u8>) -> Self {
+        self.velocities = velocities;
+        self
+    }
+
+    pub fn one_shot(mut self) -> Self {
+        self.one_shot = true;
+        self
+    }
+
+    fn contains(&self, key: u8, velocity: u8) -> bool {
+        self.keys.contains(&key) && self.velocities.contains(&velocity)
+    }
+}
+
+/// Envelope times are in seconds, the sustain level is linear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adsr {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+impl Default for Adsr {
+    fn default() -> Self {
+        Self {
+            attack: 0.002,
+            decay: 0.0,
+            sustain: 1.0,
+            release: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Stage {
+    #[default]
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Envelope {
+    stage: Stage,
+    level: f32,
+    release_step: f32,
+}
+
+impl Envelope {
+    fn start(&mut self) {
+        self.stage = Stage::Attack;
+        self.level = 0.0;
+    }
+
+    fn release(&mut self, adsr: &Adsr, sample_rate: f32) {
+        if self.stage == Stage::Idle {
+            return;
+        }
+
+        self.stage = Stage::Release;
+        self.release_step = self.level / (adsr.release * sample_rate).max(1.0);
+    }
+
+    /// Advances the envelope by a single frame and returns its level
+    fn next_level(&mut self, adsr: &Adsr, sample_rate: f32) -> f32 {
+        match self.stage {
+            Stage::Idle => {}
+            Stage::Attack => {
+                self.level += 1.0 / (adsr.attack * sample_rate).max(1.0);
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                self.level -= (1.0 - adsr.sustain) / (adsr.decay * sample_rate).max(1.0);
+                if self.level <= adsr.sustain {
+                    self.level = adsr.sustain;
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Sustain => self.level = adsr.sustain,
+            Stage::Release => {
+                self.level -= self.release_step;
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            }
+        }
+
+        self.level
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Voice {
+    zone: usize,
+    channel: u8,
+    key: u8,
+    gain: f32,
+    // position inside the sample in frames
+    position: f64,
+    step: f64,
+    envelope: Envelope,
+    // used to find the oldest voice when one has to be stolen
+    age: u64,
+}
+
+impl Voice {
+    fn is_active(&self) -> bool {
+        self.envelope.stage != Stage::Idle
+    }
+
+    fn is_released(&self) -> bool {
+        self.envelope.stage == Stage::Release
+    }
+}
+
+pub const GAIN: ParameterId = ParameterId(0);
+pub const ATTACK: ParameterId = ParameterId(1);
+pub const DECAY: ParameterId = ParameterId(2);
+pub const SUSTAIN: ParameterId = ParameterId(3);
+pub const RELEASE: ParameterId = ParameterId(4);
+
+/// How many events the sampler takes per block. The storage is allocated up
+/// front, so that scheduling events on the audio thread doesn't allocate.
+pub const MAX_EVENTS: usize = 256;
+
+/// Plays the samples of its zones from note events.
+///
+/// Each note starts a voice in every zone that matches its key and velocity.
+/// Samples are pitched relative to their root key by resampling. When all voices
+/// are busy the oldest released voice is stolen, or the oldest voice if none is released.
+/// The input is passed through so the sampler can be placed anywhere in a chain.
+/// Gain changes are smoothed, the envelope parameters apply from the frame they
+/// are scheduled for.
+pub struct Sampler<T> {
+    zones: Vec<Zone<T>>,
+    voices: Vec<Voice>,
+    envelope: Adsr,
+    channels: usize,
+    sample_rate: SampleRate,
+    values: ParameterValues,
+    // events of the current block
+    events: Vec<MidiEvent>,
+    next_age: u64,
+}
+
+impl<T> Sampler<T> {
+    pub fn new(sample_rate: SampleRate, channels: usize, polyphony: usize) -> Self {
+        Self {
+            zones: Vec::new(),
+            voices: vec![Voice::default(); polyphony.max(1)],
+            envelope: Adsr::default(),
+            channels,
+            sample_rate,
+            values: ParameterValues::new(
+                vec![
+                    ParameterDescriptor::new(GAIN, "Gain", 0.0..=4.0, 1.0),
+                    ParameterDescriptor::new(ATTACK, "Attack", 0.0..=10.0, 0.002)
+                        .with_unit(ParameterUnit::Seconds)
+                        .stepped(),
+                    ParameterDescriptor::new(DECAY, "Decay", 0.0..=10.0, 0.0)
+                        .with_unit(ParameterUnit::Seconds)
+                        .stepped(),
+                    ParameterDescriptor::new(SUSTAIN, "Sustain", 0.0..=1.0, 1.0).stepped(),
+                    ParameterDescriptor::new(RELEASE, "Release", 0.0..=10.0, 0.05)
+                        .with_unit(ParameterUnit::Seconds)
+                        .stepped(),
+                ],
+                ramp_frames(sample_rate),
+            ),
+            events: Vec::with_capacity(MAX_EVENTS),
+            next_age: 0,
+        }
+    }
+
+    pub fn with_zone(mut self, zone: Zone<T>) -> Self {
+        self.zones.push(zone);
+        self
+    }
+
+    pub fn with_envelope(mut self, envelope: Adsr) -> Self {
+        self.values.reset(ATTACK, envelope.attack);
+        self.values.reset(DECAY, envelope.decay);
+        self.values.reset(SUSTAIN, envelope.sustain);
+        self.values.reset(RELEASE, envelope.release);
+        self.update_envelope(|values, id| values.current(id));
+        self
+    }
+
+    pub fn add_zone(&mut self, zone: Zone<T>) {
+        self.zones.push(zone);
+    }
+
+    pub fn zones(&self) -> &[Zone<T>] {
+        &self.zones
+    }
+
+    pub fn envelope(&self) -> &Adsr {
+        &self.envelope
+    }
+
+    // copies the envelope parameters into the envelope the voices follow
+    fn update_envelope(&mut self, mut value: impl FnMut(&mut ParameterValues, ParameterId) -> f32) {
+        self.envelope = Adsr {
+            attack: value(&mut self.values, ATTACK),
+            decay: value(&mut self.values, DECAY),
+            sustain: value(&mut self.values, SUSTAIN),
+            release: value(&mut self.values, RELEASE),
+        };
+    }
+
+    /// Number of voices that are currently playing
+    pub fn active_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.is_active()).count()
+    }
+
+    fn note_on(&mut self, channel: u8, key: u8, velocity: u8) {
+        for zone_index in 0..self.zones.len() {
+            let zone = &self.zones[zone_index];
+            if !zone.contains(key, velocity) {
+                continue;
+            }
+
+            let semitones = f64::from(key) - f64::from(zone.sample.root_key);
+            let step = 2f64.powf(semitones / 12.0) * zone.sample.sample_rate.as_f64()
+                / self.sample_rate.as_f64();
+
+            let age = self.next_age;
+            self.next_age += 1;
+
+            let voice = self.free_voice();
+            *voice = Voice {
+                zone: zone_index,
+                channel,
+                key,
+                gain: f32::from(velocity) / 127.0,
+                position: 0.0,
+                step,
+                envelope: Envelope::default(),
+                age,
+            };
+            voice.envelope.start();
+        }
+    }
+
+    fn note_off(&mut self, channel: u8, key: u8) {
+        let sample_rate = self.sample_rate.as_f32();
+        for voice in self.voices.iter_mut().filter(|voice| {
+            voice.is_active()
+                && !voice.is_released()
+                && voice.channel == channel
+                && voice.key == key
+        }) {
+            if !self.zones[voice.zone].one_shot {
+                voice.envelope.release(&self.envelope, sample_rate);
+            }
+        }
+    }
+
+    fn release_all(&mut self) {
+        let sample_rate = self.sample_rate.as_f32();
+        for voice in &mut self.voices {
+            voice.envelope.release(&self.envelope, sample_rate);
+        }
+    }
+
+    // an idle voice, otherwise the oldest released one, otherwise the oldest one
+    fn free_voice(&mut self) -> &mut Voice {
+        let index = self
+            .voices
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, voice)| (voice.is_active(), !voice.is_released(), voice.age))
+            .map(|(index, _)| index)
+            .expect("there is at least one voice");
+        &mut self.voices[index]
+    }
+
+    fn handle(&mut self, message: MidiMessage) {
+        match message {
+            MidiMessage::NoteOn {
+                channel,
+                key,
+                velocity,
+            } if velocity > 0 => self.note_on(channel, key, velocity),
+            MidiMessage::NoteOn { channel, key, .. }
+            | MidiMessage::NoteOff { channel, key, .. } => self.note_off(channel, key),
+            MidiMessage::ControlChange {
+                controller: ALL_SOUND_OFF,
+                ..
+            } => self.voices.fill(Voice::default()),
+            MidiMessage::ControlChange {
+                controller: ALL_NOTES_OFF,
+                ..
+            }
+            | MidiMessage::AllNotesOff => self.release_all(),
+            MidiMessage::ControlChange { .. } => {}
+        }
+    }
+}
+
+impl<T> Sampler<T>
+where
+    T: SharedSample,
+{
+    // adds the next frame of every voice to `frame`
+    fn render_frame(&mut self, frame: &mut [T], gain: f32) {
+        let sample_rate = self.sample_rate.as_f32();
+
+        for voice in self.voices.iter_mut().filter(|voice| voice.is_active()) {
+            let sample = &self.zones[voice.zone].sample;
+            let level = voice.envelope.next_level(&self.envelope, sample_rate);
+            let gain = voice.gain * level * gain;
+
+            let index = voice.position as usize;
+            let fract = (voice.position - index as f64) as f32;
+            let next = match &sample.loop_frames {
+                Some(frames) if index + 1 == frames.end => frames.start,
+                _ => index + 1,
+            };
+
+            let source_channels = sample.buffer.channels();
+            for (channel, out) in frame.iter_mut().enumerate() {
+                let read = |frame: usize| {
+                    sample
+                        .buffer
+                        .get_sample(channel % source_channels, frame)
+                        .map_or(0.0, |s| to_f32(*s))
+                };
+                let (a, b) = (read(index), read(next));
+                *out = out.add_amp(from_f32::<T>((a + (b - a) * fract) * gain).to_signed_sample());
+            }
+
+            voice.position += voice.step;
+            match &sample.loop_frames {
+                Some(frames) if voice.position >= frames.end as f64 => {
+                    voice.position -= (frames.end - frames.start) as f64;
+                }
+                _ if voice.position >= sample.buffer.frames() as f64 => {
+                    *voice = Voice::default();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Renders `frames` frames in blocks of `block_size`, e.g. to test a kit offline.
+    /// The offsets of the events are relative to the start of the rendering.
+    pub fn render(
+        &mut self,
+        events: &[MidiEvent],
+        frames: usize,
+        block_size: usize,
+    ) -> InterleavedBuffer<T> {
+        let channels = NonZeroUsize::new(self.channels).expect("the sampler has channels");
+        let block_size = block_size.max(1);
+        let input = InterleavedBuffer::with_shape(channels, FrameTime(block_size as u64));
+        let mut block = InterleavedBuffer::with_shape(channels, FrameTime(block_size as u64));
+        let mut output = InterleavedBuffer::with_shape(channels, FrameTime(frames as u64));
+
+        let mut block_events = Vec::new();
+        for start in (0..frames).step_by(block_size) {
+            block_events.clear();
+            block_events.extend(
+                events
+                    .iter()
+                    .filter(|event| (start..start + block_size).contains(&event.offset))
+                    .map(|event| MidiEvent::new(event.offset - start, event.message)),
+            );
+
+            block.set_to_equilibrium();
+            self.schedule_events(&block_events);
+            self.process_unchecked(&input, &mut block.wrap_mut());
+            let frames = block_size.min(frames - start);
+            mix_buffers_region(&block, 0, &mut output, start, frames)
+                .expect("both have the same channels");
+        }
+        output
+    }
+}
+
+impl<T> AudioProcessor<T> for Sampler<T>
+where
+    T: SharedSample,
+{
+    fn process_unchecked(
+        &mut self,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
+    ) {
+        mix_buffers(input, output, None).expect("this is the unchecked method");
+
+        let mut next_event = 0;
+        for index in 0..output.frames() {
+            while let Some(event) = self.events.get(next_event)
+                && event.offset <= index
+            {
+                self.handle(event.message);
+                next_event += 1;
+            }
+
+            let gain = self.values.next_value(GAIN);
+            self.update_envelope(ParameterValues::next_value);
+            output.with_frame_mut(index, |frame| self.render_frame(frame, gain));
+        }
+        self.events.clear();
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.channels,
+            num_output_channels: self.channels,
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterDescriptor] {
+        self.values.descriptors()
+    }
+
+    fn get_parameter(&self, id: ParameterId) -> Option<f32> {
+        self.values.get(id)
+    }
+
+    fn set_parameter(&mut self, id: ParameterId, value: f32) -> bool {
+        self.values.set(id, value)
+    }
+
+    fn automate_parameter(&mut self, id: ParameterId, ramps: &[ParameterRamp]) -> bool {
+        self.values.automate(id, ramps)
+    }
+
+    // events past `MAX_EVENTS` are dropped
+    fn schedule_events(&mut self, events: &[MidiEvent]) {
+        self.events.clear();
+        self.events
+            .extend_from_slice(&events[..events.len().min(MAX_EVENTS)]);
+    }
+}
+
+fn to_f32<T: Sample>(sample: T) -> f32 {
+    sample.to_float_sample().to_sample()
+}
+
+fn from_f32<T: Sample>(value: f32) -> T {
+    T::Float::from_sample(value).to_sample()
+}
+
+struct SmplInfo {
+    root_key: u8,
+    loop_frames: Option<Range<usize>>,
+}
+
+// reads the root key and the first loop from the `smpl` chunk of a RIFF WAVE file
+fn read_smpl(bytes: &[u8]) -> Option<SmplInfo> {
+    let u32_at = |data: &[u8], offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut position = 12;
+    while let (Some(id), Some(size)) = (
+        bytes.get(position..position + 4),
+        u32_at(bytes, position + 4),
+    ) {
+        let data = bytes.get(position + 8..position + 8 + size as usize)?;
+
+        if id == b"smpl" {
+            let root_key = u32_at(data, 12)?.min(127) as u8;
+            let loops = u32_at(data, 28)?;
+            // the end of a loop is inclusive
+            let loop_frames = (loops > 0)
+                .then(|| Some(u32_at(data, 36 + 8)? as usize..u32_at(data, 36 + 12)? as usize + 1))
+                .flatten();
+
+            return Some(SmplInfo {
+                root_key,
+                loop_frames,
+            });
+        }
+
+        // chunks are padded to an even size
+        position += 8 + size as usize + (size as usize & 1);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, sync::Arc};
+
+    use audio_buffer::{buffers::interleaved::InterleavedBuffer, core::ResizableBuffer};
+    use time::SampleRate;
+
+    use crate::{
+        event::{MidiEvent, MidiMessage},
+        parameter::ParameterRamp,
+        processor::{
+            AudioProcessor,
+            sampler::{Adsr, GAIN, MAX_EVENTS, SampleData, Sampler, Zone, read_smpl},
+        },
+    };
+
+    // a mono sample whose value is its frame index
+    fn ramp(frames: usize) -> Arc<InterleavedBuffer<f32>> {
+        let mut buffer = InterleavedBuffer::new(NonZeroUsize::new(1).unwrap());
+        buffer.resize(frames);
+        for frame in 0..frames {
+            audio_buffer::core::BufferMut::with_frame_mut(&mut buffer, frame, |f| {
+                f[0] = frame as f32
+            });
+        }
+        Arc::new(buffer)
+    }
+
+    fn on(offset: usize, key: u8) -> MidiEvent {
+        MidiEvent::new(
+            offset,
+            MidiMessage::NoteOn {
+                channel: 0,
+                key,
+                velocity: 127,
+            },
+        )
+    }
+
+    fn instant() -> Adsr {
+        Adsr {
+            attack: 0.0,
+            decay: 0.0,
+            sustain: 1.0,
+            release: 0.0,
+        }
+    }
+
+    #[test]
+    fn notes_are_pitched_relative_to_the_root_key() {
+        let sample = SampleData::new(ramp(64), SampleRate::new(100.0), 60);
+        let mut sampler = Sampler::new(SampleRate::new(100.0), 1, 4)
+            .with_zone(Zone::new(sample))
+            .with_envelope(instant());
+
+        let output = sampler.render(&[on(2, 72)], 6, 4);
+        let samples: Vec<f32> = (0..6).map(|f| *output.get_sample(0, f).unwrap()).collect();
+        assert_eq!(samples, vec![0.0, 0.0, 0.0, 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn voices_are_stolen_and_loops_repeat() {
+        let sample = SampleData::new(ramp(8), SampleRate::new(100.0), 60).with_loop(2..4);
+        let mut sampler = Sampler::new(SampleRate::new(100.0), 1, 1)
+            .with_zone(Zone::new(sample).with_keys(60..=60))
+            .with_envelope(instant());
+
+        let output = sampler.render(&[on(0, 60), on(3, 60), on(3, 61)], 8, 8);
+        let samples: Vec<f32> = (0..8).map(|f| *output.get_sample(0, f).unwrap()).collect();
+        // key 61 is outside of the zone, the second note steals the only voice
+        assert_eq!(samples, vec![0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 3.0, 2.0]);
+        assert_eq!(sampler.active_voices(), 1);
+    }
+
+    #[test]
+    fn gain_automation_is_sample_accurate() {
+        let sample = SampleData::new(ramp(64), SampleRate::new(100.0), 60).with_loop(1..2);
+        let mut sampler = Sampler::new(SampleRate::new(100.0), 1, 1)
+            .with_zone(Zone::new(sample))
+            .with_envelope(instant());
+
+        let ramps = [ParameterRamp {
+            offset: 3,
+            frames: 0,
+            value: 0.5,
+        }];
+        assert!(AudioProcessor::<f32>::automate_parameter(
+            &mut sampler,
+            GAIN,
+            &ramps
+        ));
+
+        // the loop holds the sample at 1 from the second frame on
+        let output = sampler.render(&[on(0, 60)], 6, 6);
+        let samples: Vec<f32> = (0..6).map(|f| *output.get_sample(0, f).unwrap()).collect();
+        assert_eq!(samples, vec![0.0, 1.0, 1.0, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn events_past_the_reserved_capacity_are_dropped() {
+        let mut sampler = Sampler::<f32>::new(SampleRate::new(100.0), 1, 1);
+        let capacity = sampler.events.capacity();
+
+        let events: Vec<_> = (0..MAX_EVENTS + 10).map(|offset| on(offset, 60)).collect();
+        AudioProcessor::<f32>::schedule_events(&mut sampler, &events);
+        assert_eq!(sampler.events.len(), MAX_EVENTS);
+        assert_eq!(sampler.events.capacity(), capacity);
+        assert_eq!(sampler.events.last().unwrap().offset, MAX_EVENTS - 1);
+    }
+
+    #[test]
+    fn reads_root_key_and_loop_from_smpl_chunk() {
+        let mut smpl = vec![0u8; 60];
+        smpl[12] = 48;
+        smpl[28] = 1;
+        smpl[44] = 10;
+        smpl[48] = 99;
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        bytes.extend_from_slice(b"smpl");
+        bytes.extend_from_slice(&(smpl.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&smpl);
+
+        let info = read_smpl(&bytes).unwrap();
+        assert_eq!(info.root_key, 48);
+        assert_eq!(info.loop_frames, Some(10..100));
+    }
+}
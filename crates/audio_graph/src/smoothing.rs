@@ -0,0 +1,73 @@
+/// A value that ramps linearly towards its target over a fixed number of frames,
+/// used to avoid zipper noise when parameters change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothedValue {
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining: usize,
+    ramp_frames: usize,
+}
+
+impl SmoothedValue {
+    pub fn new(value: f32, ramp_frames: usize) -> Self {
+        Self {
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining: 0,
+            ramp_frames,
+        }
+    }
+
+    /// Starts a new ramp from the current value towards `target`
+    pub fn set_target(&mut self, target: f32) {
+        self.ramp_to(target, self.ramp_frames);
+    }
+
+    /// Starts a ramp from the current value that reaches `target` after `frames` frames
+    pub fn ramp_to(&mut self, target: f32, frames: usize) {
+        self.target = target;
+
+        if frames == 0 {
+            self.set_immediate(target);
+        } else {
+            self.remaining = frames;
+            self.step = (target - self.current) / frames as f32;
+        }
+    }
+
+    /// Jumps to `value` without ramping
+    pub fn set_immediate(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.step = 0.0;
+        self.remaining = 0;
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Advances the ramp by a single frame and returns the new value
+    pub fn next_value(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 {
+                self.target
+            } else {
+                self.current + self.step
+            };
+        }
+
+        self.current
+    }
+}
@@ -4,7 +4,7 @@ use super::{MusicalTime, SampleRate, SecondsF64, SuperclockTime};
 
 /// Unit of time length in frames (samples in a single audio channel).
 #[cfg_attr(feature = "serde-derive", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Default)]
 pub struct FrameTime(pub u64);
 
 impl FrameTime {
@@ -46,12 +46,6 @@ impl FrameTime {
     }
 }
 
-impl Default for FrameTime {
-    fn default() -> Self {
-        FrameTime(0)
-    }
-}
-
 impl From<u8> for FrameTime {
     fn from(s: u8) -> Self {
         FrameTime(u64::from(s))
@@ -19,7 +19,7 @@ pub static SUPER_BEAT_TICKS_PER_BEAT: u32 = 1_241_856_000;
 /// with *exact* precision. This number is also much larger than all of the common sampling rates,
 /// allowing for sample-accurate precision even at very high sampling rates and very low BPMs.
 #[cfg_attr(feature = "serde-derive", derive(Serialize, Deserialize))]
-#[derive(Default, Debug, Clone, Copy, Hash)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MusicalTime {
     beats: u32,
     ticks: u32,
@@ -30,7 +30,7 @@ impl MusicalTime {
 
     /// * `beats` - The time in musical beats.
     /// * `ticks` - The number of ticks (after the time in `beats`) (Note this value
-    /// will be constrained to the range `[0, 1,241,856,000)`).
+    ///   will be constrained to the range `[0, 1,241,856,000)`).
     ///
     /// A "tick" is a unit of time equal to `1 / 1,241,856,000` of a beat. This number was chosen
     /// because it is nicely divisible by a whole slew of factors including `2, 3, 4, 5, 6, 7, 8, 9,
@@ -79,6 +79,14 @@ impl MusicalTime {
         (u64::from(self.beats) * u64::from(SUPER_BEAT_TICKS_PER_BEAT)) + u64::from(self.ticks)
     }
 
+    /// * `ticks` - The total number of ticks as returned by `total_ticks`.
+    pub fn from_total_ticks(ticks: u64) -> Self {
+        Self {
+            beats: (ticks / u64::from(SUPER_BEAT_TICKS_PER_BEAT)) as u32,
+            ticks: (ticks % u64::from(SUPER_BEAT_TICKS_PER_BEAT)) as u32,
+        }
+    }
+
     /// * `beats` - The time in musical beats.
     pub fn from_beats(beats: u32) -> Self {
         Self { beats, ticks: 0 }
@@ -93,140 +101,140 @@ impl MusicalTime {
 
     /// * `beats` - The time in musical beats.
     /// * `half_beats` - The number of half-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 1]`.
+    ///   constrained to the range `[0, 1]`.
     pub fn from_half_beats(beats: u32, half_beats: u32) -> Self {
         Self::from_fractional_beats::<2>(beats, half_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `quarter_beats` - The number of quarter-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 3]`.
+    ///   constrained to the range `[0, 3]`.
     pub fn from_quarter_beats(beats: u32, quarter_beats: u32) -> Self {
         Self::from_fractional_beats::<4>(beats, quarter_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `eigth_beats` - The number of eigth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 7]`.
+    ///   constrained to the range `[0, 7]`.
     pub fn from_eighth_beats(beats: u32, eigth_beats: u32) -> Self {
         Self::from_fractional_beats::<8>(beats, eigth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `sixteenth_beats` - The number of sixteenth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 15]`.
+    ///   constrained to the range `[0, 15]`.
     pub fn from_sixteenth_beats(beats: u32, sixteenth_beats: u32) -> Self {
         Self::from_fractional_beats::<16>(beats, sixteenth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_32nd_beats` - The number of 32nd-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 31]`.
+    ///   constrained to the range `[0, 31]`.
     pub fn from_32nd_beats(beats: u32, _32nd_beats: u32) -> Self {
         Self::from_fractional_beats::<32>(beats, _32nd_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_64th_beats` - The number of 64th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 63]`.
+    ///   constrained to the range `[0, 63]`.
     pub fn from_64th_beats(beats: u32, _64th_beats: u32) -> Self {
         Self::from_fractional_beats::<64>(beats, _64th_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_128th_beats` - The number of 128th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 127]`.
+    ///   constrained to the range `[0, 127]`.
     pub fn from_128th_beats(beats: u32, _128th_beats: u32) -> Self {
         Self::from_fractional_beats::<128>(beats, _128th_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_256th_beats` - The number of 256th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 255]`.
+    ///   constrained to the range `[0, 255]`.
     pub fn from_256th_beats(beats: u32, _256th_beats: u32) -> Self {
         Self::from_fractional_beats::<256>(beats, _256th_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_512th_beats` - The number of 512th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 511]`.
+    ///   constrained to the range `[0, 511]`.
     pub fn from_512th_beats(beats: u32, _512th_beats: u32) -> Self {
         Self::from_fractional_beats::<512>(beats, _512th_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_1024th_beats` - The number of 1024th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 1023]`.
+    ///   constrained to the range `[0, 1023]`.
     pub fn from_1024th_beats(beats: u32, _1024th_beats: u32) -> Self {
         Self::from_fractional_beats::<1024>(beats, _1024th_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_2048th_beats` - The number of 2048th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 2047]`.
+    ///   constrained to the range `[0, 2047]`.
     pub fn from_2048th_beats(beats: u32, _2048th_beats: u32) -> Self {
         Self::from_fractional_beats::<2048>(beats, _2048th_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `third_beats` - The number of third-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 2]`.
+    ///   constrained to the range `[0, 2]`.
     pub fn from_third_beats(beats: u32, third_beats: u32) -> Self {
         Self::from_fractional_beats::<3>(beats, third_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `fifth_beats` - The number of fifth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 4]`.
+    ///   constrained to the range `[0, 4]`.
     pub fn from_fifth_beats(beats: u32, fifth_beats: u32) -> Self {
         Self::from_fractional_beats::<5>(beats, fifth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `sixth_beats` - The number of sixth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 5]`.
+    ///   constrained to the range `[0, 5]`.
     pub fn from_sixth_beats(beats: u32, sixth_beats: u32) -> Self {
         Self::from_fractional_beats::<6>(beats, sixth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `seventh_beats` - The number of seventh-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 6]`.
+    ///   constrained to the range `[0, 6]`.
     pub fn from_seventh_beats(beats: u32, seventh_beats: u32) -> Self {
         Self::from_fractional_beats::<7>(beats, seventh_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `ninth_beats` - The number of ninth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 8]`.
+    ///   constrained to the range `[0, 8]`.
     pub fn from_ninth_beats(beats: u32, ninth_beats: u32) -> Self {
         Self::from_fractional_beats::<9>(beats, ninth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `tenth_beats` - The number of tenth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 9]`.
+    ///   constrained to the range `[0, 9]`.
     pub fn from_tenth_beats(beats: u32, tenth_beats: u32) -> Self {
         Self::from_fractional_beats::<10>(beats, tenth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `eleventh_beats` - The number of eleventh-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 10]`.
+    ///   constrained to the range `[0, 10]`.
     pub fn from_eleventh_beats(beats: u32, eleventh_beats: u32) -> Self {
         Self::from_fractional_beats::<11>(beats, eleventh_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `twelfth_beats` - The number of twelfth-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 11]`.
+    ///   constrained to the range `[0, 11]`.
     pub fn from_twelth_beats(beats: u32, twelfth_beats: u32) -> Self {
         Self::from_fractional_beats::<12>(beats, twelfth_beats)
     }
 
     /// * `beats` - The time in musical beats.
     /// * `_24th_beats` - The number of 24th-beats (after the time `beats`). This will be
-    /// constrained to the range `[0, 23]`.
+    ///   constrained to the range `[0, 23]`.
     pub fn from_24th_beats(beats: u32, _24th_beats: u32) -> Self {
         Self::from_fractional_beats::<24>(beats, _24th_beats)
     }
@@ -677,21 +685,9 @@ impl MusicalTime {
     }
 }
 
-impl PartialEq for MusicalTime {
-    fn eq(&self, other: &Self) -> bool {
-        self.beats == other.beats && self.ticks == other.ticks
-    }
-}
-
-impl Eq for MusicalTime {}
-
 impl PartialOrd for MusicalTime {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.beats != other.beats {
-            self.beats.partial_cmp(&other.beats)
-        } else {
-            self.ticks.partial_cmp(&other.ticks)
-        }
+        Some(self.cmp(other))
     }
 }
 
@@ -727,7 +723,7 @@ impl Mul<u32> for MusicalTime {
 
         if ticks >= u64::from(SUPER_BEAT_TICKS_PER_BEAT) {
             beats += (ticks / u64::from(SUPER_BEAT_TICKS_PER_BEAT)) as u32;
-            ticks = ticks % u64::from(SUPER_BEAT_TICKS_PER_BEAT);
+            ticks %= u64::from(SUPER_BEAT_TICKS_PER_BEAT);
         }
 
         Self {
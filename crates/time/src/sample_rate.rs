@@ -24,7 +24,7 @@ impl SampleRate {
     }
 
     pub fn as_f64(&self) -> f64 {
-        self.0 as f64
+        self.0
     }
 
     pub fn as_u16(&self) -> u16 {
@@ -14,7 +14,7 @@ pub static SUPER_SAMPLE_TICKS_PER_SECOND: u32 = 282_240_000;
 /// 88,200, 96,000, 176,400, 192,000, 352,800, and 384,000`. This ensures that no information is
 /// lost when switching between sample rates.
 #[cfg_attr(feature = "serde-derive", derive(Serialize, Deserialize))]
-#[derive(Default, Debug, Clone, Copy, Hash)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SuperclockTime {
     seconds: u32,
     ticks: u32,
@@ -23,7 +23,7 @@ pub struct SuperclockTime {
 impl SuperclockTime {
     /// * `seconds` - The time in seconds.
     /// * `ticks` - The number of ticks (after the time in `seconds`) (Note this value
-    /// will be constrained to the range `[0, 282,240,000)`).
+    ///   will be constrained to the range `[0, 282,240,000)`).
     ///
     /// A "tick" is a unit of time that is exactly 1 / 282,240,000 of a second. This number
     /// happens to be nicely divisible by all common sampling rates: `22,050, 24,000, 44,100,
@@ -335,21 +335,9 @@ impl SuperclockTime {
     }
 }
 
-impl PartialEq for SuperclockTime {
-    fn eq(&self, other: &Self) -> bool {
-        self.seconds == other.seconds && self.ticks == other.ticks
-    }
-}
-
-impl Eq for SuperclockTime {}
-
 impl PartialOrd for SuperclockTime {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.seconds != other.seconds {
-            self.seconds.partial_cmp(&other.seconds)
-        } else {
-            self.ticks.partial_cmp(&other.ticks)
-        }
+        Some(self.cmp(other))
     }
 }
 
@@ -385,7 +373,7 @@ impl Mul<u32> for SuperclockTime {
 
         if ticks >= u64::from(SUPER_SAMPLE_TICKS_PER_SECOND) {
             seconds += (ticks / u64::from(SUPER_SAMPLE_TICKS_PER_SECOND)) as u32;
-            ticks = ticks % u64::from(SUPER_SAMPLE_TICKS_PER_SECOND);
+            ticks %= u64::from(SUPER_SAMPLE_TICKS_PER_SECOND);
         }
 
         Self {
@@ -0,0 +1,403 @@
+use std::{f32::consts::PI, ops::Range};
+
+use audio_graph::{
+    daggy::NodeIndex,
+    parameter::{MAX_RAMPS, ParameterId, ParameterRamp},
+};
+use time::{MusicalTime, SampleRate};
+
+use crate::transport::SubBlock;
+
+/// Longest ramp used to approximate a curved segment, longer sub-blocks
+/// use longer ramps so a curve spanning them fits into `MAX_RAMPS`
+const MAX_RAMP_FRAMES: usize = 32;
+
+/// The shape of the segment between a breakpoint and the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Curve {
+    #[default]
+    Linear,
+    /// Holds the value until the next breakpoint
+    Step,
+    /// Eases in and out of the segment
+    Smooth,
+    /// Moves by a constant ratio, which sounds linear for frequencies and gains.
+    /// Falls back to linear if one of the values isn't positive.
+    Exponential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoint {
+    pub time: MusicalTime,
+    pub value: f32,
+    pub curve: Curve,
+}
+
+impl Breakpoint {
+    pub fn new(time: MusicalTime, value: f32) -> Self {
+        Self {
+            time,
+            value,
+            curve: Curve::Linear,
+        }
+    }
+
+    pub fn with_curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutomationMode {
+    /// The lane is ignored
+    Off,
+    /// The lane drives the parameter
+    #[default]
+    Read,
+    /// While the transport is running every change overwrites the lane
+    /// until recording is stopped
+    Write,
+    /// Changes are written while the parameter is touched,
+    /// playback resumes once it is released
+    Touch,
+    /// Like `Touch`, but writing continues after the parameter
+    /// is released until recording is stopped
+    Latch,
+}
+
+impl AutomationMode {
+    pub fn is_recording(&self) -> bool {
+        matches!(
+            self,
+            AutomationMode::Write | AutomationMode::Touch | AutomationMode::Latch
+        )
+    }
+}
+
+/// Breakpoints that drive a single parameter of a processor inside a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutomationLane {
+    processor: NodeIndex,
+    parameter: ParameterId,
+    mode: AutomationMode,
+    // sorted by time, at most one breakpoint per position
+    breakpoints: Vec<Breakpoint>,
+    // the parameter is being recorded so the lane must not drive it
+    pub(crate) recording: bool,
+}
+
+impl AutomationLane {
+    pub fn new(processor: NodeIndex, parameter: ParameterId) -> Self {
+        Self {
+            processor,
+            parameter,
+            mode: AutomationMode::default(),
+            breakpoints: Vec::new(),
+            recording: false,
+        }
+    }
+
+    pub fn processor(&self) -> NodeIndex {
+        self.processor
+    }
+
+    pub fn parameter(&self) -> ParameterId {
+        self.parameter
+    }
+
+    pub fn mode(&self) -> AutomationMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: AutomationMode) {
+        self.mode = mode;
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Inserts a breakpoint, replacing the one at the same position
+    pub fn insert(&mut self, breakpoint: Breakpoint) {
+        match self
+            .breakpoints
+            .binary_search_by(|existing| existing.time.cmp(&breakpoint.time))
+        {
+            Ok(index) => self.breakpoints[index] = breakpoint,
+            Err(index) => self.breakpoints.insert(index, breakpoint),
+        }
+    }
+
+    pub fn remove(&mut self, time: MusicalTime) -> Option<Breakpoint> {
+        self.breakpoints
+            .binary_search_by(|existing| existing.time.cmp(&time))
+            .ok()
+            .map(|index| self.breakpoints.remove(index))
+    }
+
+    /// Removes all breakpoints inside of `range`
+    pub fn clear_range(&mut self, range: Range<MusicalTime>) {
+        self.breakpoints
+            .retain(|breakpoint| !range.contains(&breakpoint.time));
+    }
+
+    /// Whether the lane currently drives its parameter
+    pub fn is_playing(&self) -> bool {
+        self.mode != AutomationMode::Off && !self.recording && !self.breakpoints.is_empty()
+    }
+
+    /// Evaluates the lane. Before the first and after the last breakpoint
+    /// their values are held. Returns `None` for an empty lane.
+    pub fn value_at(&self, time: MusicalTime) -> Option<f32> {
+        let next = self
+            .breakpoints
+            .partition_point(|breakpoint| breakpoint.time <= time);
+
+        match (
+            self.breakpoints.get(next.wrapping_sub(1)),
+            self.breakpoints.get(next),
+        ) {
+            (Some(previous), Some(next)) => Some(interpolate(previous, next, time)),
+            (Some(only), None) | (None, Some(only)) => Some(only.value),
+            (None, None) => None,
+        }
+    }
+
+    /// Appends the ramps that follow the lane across `sub_block` to `ramps`.
+    /// Curved segments are approximated with short linear ramps.
+    ///
+    /// `ramps` never grows past `MAX_RAMPS`, so it doesn't reallocate on the audio
+    /// thread. Once it's full the last ramp is replaced, like `ParameterValues::automate`
+    /// does, so the parameter still ends up at the right value.
+    pub fn ramps(
+        &self,
+        sub_block: &SubBlock,
+        bpm: f64,
+        sample_rate: SampleRate,
+        ramps: &mut Vec<ParameterRamp>,
+    ) {
+        let Some(value) = self.value_at(sub_block.range.start) else {
+            return;
+        };
+
+        let start = sub_block.range.start;
+        let end = sub_block.range.end;
+        let offset_of = |time: MusicalTime| {
+            (sub_block.offset + frames_between(start, time, bpm, sample_rate)).0 as usize
+        };
+
+        // the sub-block might not continue where the last one ended, e.g. after a loop wrap
+        push_ramp(
+            ramps,
+            ParameterRamp {
+                offset: sub_block.offset.0 as usize,
+                frames: 0,
+                value,
+            },
+        );
+
+        let inner = self
+            .breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.time)
+            .filter(|&time| start < time && time < end);
+
+        // a curve across the whole sub-block takes half of the ramps at most
+        let ramp_frames =
+            MAX_RAMP_FRAMES.max((sub_block.frames.0 as usize).div_ceil(MAX_RAMPS / 2));
+
+        let mut from = start;
+        for to in inner.chain(std::iter::once(end)) {
+            let next = self
+                .breakpoints
+                .partition_point(|breakpoint| breakpoint.time <= from);
+
+            let (Some(previous), Some(next)) = (
+                self.breakpoints.get(next.wrapping_sub(1)),
+                self.breakpoints.get(next),
+            ) else {
+                // the value is held before the first and after the last breakpoint
+                from = to;
+                continue;
+            };
+
+            let frames = offset_of(to) - offset_of(from);
+            match previous.curve {
+                Curve::Step => {
+                    if to == next.time {
+                        push_ramp(
+                            ramps,
+                            ParameterRamp {
+                                offset: offset_of(to),
+                                frames: 0,
+                                value: next.value,
+                            },
+                        );
+                    }
+                }
+                Curve::Linear => push_ramp(
+                    ramps,
+                    ParameterRamp {
+                        offset: offset_of(from),
+                        frames,
+                        value: interpolate(previous, next, to),
+                    },
+                ),
+                Curve::Smooth | Curve::Exponential => {
+                    let steps = frames.div_ceil(ramp_frames).max(1);
+                    let ticks = to.total_ticks() - from.total_ticks();
+
+                    for step in 0..steps {
+                        let step_start = step * frames / steps;
+                        let step_end = (step + 1) * frames / steps;
+                        let time = MusicalTime::from_total_ticks(
+                            from.total_ticks() + ticks * (step as u64 + 1) / steps as u64,
+                        );
+
+                        push_ramp(
+                            ramps,
+                            ParameterRamp {
+                                offset: offset_of(from) + step_start,
+                                frames: step_end - step_start,
+                                value: interpolate(previous, next, time),
+                            },
+                        );
+                    }
+                }
+            }
+
+            from = to;
+        }
+    }
+}
+
+fn push_ramp(ramps: &mut Vec<ParameterRamp>, ramp: ParameterRamp) {
+    if ramps.len() < MAX_RAMPS {
+        ramps.push(ramp);
+    } else if let Some(last) = ramps.last_mut() {
+        *last = ramp;
+    }
+}
+
+fn frames_between(
+    start: MusicalTime,
+    end: MusicalTime,
+    bpm: f64,
+    sample_rate: SampleRate,
+) -> time::FrameTime {
+    end.checked_sub(start)
+        .unwrap_or(MusicalTime::ZERO)
+        .to_nearest_frame_round_lossy(bpm, sample_rate)
+}
+
+fn interpolate(previous: &Breakpoint, next: &Breakpoint, time: MusicalTime) -> f32 {
+    let length = next.time.total_ticks() - previous.time.total_ticks();
+    let position = time
+        .total_ticks()
+        .saturating_sub(previous.time.total_ticks());
+    let x = (position as f64 / length as f64).clamp(0.0, 1.0) as f32;
+
+    let (a, b) = (previous.value, next.value);
+    match previous.curve {
+        Curve::Linear => a + (b - a) * x,
+        Curve::Step => {
+            if x < 1.0 {
+                a
+            } else {
+                b
+            }
+        }
+        Curve::Smooth => a + (b - a) * (1.0 - (PI * x).cos()) * 0.5,
+        Curve::Exponential if a > 0.0 && b > 0.0 => a * (b / a).powf(x),
+        Curve::Exponential => a + (b - a) * x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use audio_graph::{
+        daggy::NodeIndex,
+        parameter::{MAX_RAMPS, ParameterId},
+    };
+    use time::{FrameTime, MusicalTime, SampleRate};
+
+    use crate::{
+        automation::{AutomationLane, Breakpoint, Curve},
+        transport::SubBlock,
+    };
+
+    fn lane() -> AutomationLane {
+        let mut lane = AutomationLane::new(NodeIndex::new(2), ParameterId(0));
+        lane.insert(Breakpoint::new(MusicalTime::from_beats(1), 0.0));
+        lane.insert(Breakpoint::new(MusicalTime::from_beats(3), 1.0).with_curve(Curve::Step));
+        lane.insert(Breakpoint::new(MusicalTime::from_beats(5), 0.0));
+        lane
+    }
+
+    #[test]
+    fn evaluates_curves_and_holds_the_ends() {
+        let lane = lane();
+
+        assert_eq!(lane.value_at(MusicalTime::ZERO), Some(0.0));
+        assert_eq!(lane.value_at(MusicalTime::from_beats(2)), Some(0.5));
+        assert_eq!(lane.value_at(MusicalTime::from_beats(4)), Some(1.0));
+        assert_eq!(lane.value_at(MusicalTime::from_beats(5)), Some(0.0));
+        assert_eq!(lane.value_at(MusicalTime::from_beats(9)), Some(0.0));
+    }
+
+    #[test]
+    fn ramps_split_at_breakpoints() {
+        let lane = lane();
+        // at 60 bpm and 100 Hz a beat lasts 100 frames
+        let sub_block = SubBlock {
+            offset: FrameTime(0),
+            frames: FrameTime(400),
+            range: MusicalTime::from_beats(2)..MusicalTime::from_beats(6),
+        };
+
+        let mut ramps = Vec::new();
+        lane.ramps(&sub_block, 60.0, SampleRate::new(100.0), &mut ramps);
+
+        let ramps: Vec<_> = ramps
+            .iter()
+            .map(|ramp| (ramp.offset, ramp.frames, ramp.value))
+            .collect();
+        assert_eq!(ramps, vec![(0, 0, 0.5), (0, 100, 1.0), (300, 0, 0.0)]);
+    }
+
+    #[test]
+    fn ramps_of_a_block_fit_into_the_reserved_capacity() {
+        let mut lane = AutomationLane::new(NodeIndex::new(2), ParameterId(0));
+        for beat in 0..=8 {
+            let value = (beat % 2) as f32;
+            lane.insert(
+                Breakpoint::new(MusicalTime::from_beats(beat), value).with_curve(Curve::Smooth),
+            );
+        }
+
+        // at 60 bpm and 1000 Hz a beat lasts 1000 frames, the loop wraps halfway
+        let range = MusicalTime::ZERO..MusicalTime::from_beats(8);
+        let sub_blocks = [
+            SubBlock {
+                offset: FrameTime(0),
+                frames: FrameTime(8000),
+                range: range.clone(),
+            },
+            SubBlock {
+                offset: FrameTime(8000),
+                frames: FrameTime(8000),
+                range,
+            },
+        ];
+
+        let mut ramps = Vec::with_capacity(MAX_RAMPS);
+        for sub_block in &sub_blocks {
+            lane.ramps(sub_block, 60.0, SampleRate::new(1000.0), &mut ramps);
+        }
+
+        assert_eq!((ramps.len(), ramps.capacity()), (MAX_RAMPS, MAX_RAMPS));
+        let last = ramps.last().unwrap();
+        assert_eq!((last.offset + last.frames, last.value), (16000, 0.0));
+    }
+}
@@ -1,43 +1,89 @@
-use std::{collections::HashMap, num::NonZero, ops::Range};
+use std::{
+    collections::HashMap,
+    num::NonZero,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 use audio_buffer::{
     SharedSample,
-    buffers::interleaved::InterleavedBuffer,
-    core::{Buffer, BufferMut, io::mix_buffers},
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    core::{Buffer, BufferMut, io::mix_buffers_region},
 };
 use audio_graph::{
-    AudioGraph,
+    AudioGraph, Connection,
     daggy::{EdgeIndex, NodeIndex},
+    event::{MidiEvent, MidiMessage, sort_events},
+    parameter::ParameterId,
     pin_matrix::PinMatrix,
+    processor::AudioProcessor,
 };
 use log::error;
-use ringbuf::{HeapCons, traits::Consumer};
+use ringbuf::{
+    HeapCons, HeapProd,
+    traits::{Consumer, Observer, Producer},
+};
 use time::{FrameTime, MusicalTime, SampleRate};
 
 use crate::{
-    message::{AudioBackendCommand, AudioBackendMessage},
-    track::Track,
+    automation::AutomationLane,
+    input::{Input, InputChannels},
+    message::{
+        AudioBackendCommand, AudioBackendMessage, AudioEngineMessage, AudioEngineStatus,
+        TransportStatus,
+    },
+    midi::MidiClip,
+    mixer::MixerControl,
+    model::first_connection,
+    output::OutputMap,
+    playlist::Clip,
+    recording::Take,
+    track::{SendTap, Track},
+    transport::Transport,
 };
 
+/// The ring buffers the backend talks to the control side through
+pub struct BackendChannels<T: SharedSample> {
+    pub commands: HeapCons<AudioBackendMessage<T>>,
+    /// Transport, load and stream reports, dropped when the ring is full
+    pub statuses: HeapProd<AudioEngineMessage>,
+    /// Replies to the commands, one for each
+    pub acks: HeapProd<AudioEngineMessage>,
+}
+
 pub struct AudioBackend<T: SharedSample> {
-    pub(crate) command_consumer: HeapCons<AudioBackendMessage>,
-    // pub(crate) status_producer: HeapProd<AudioEngineMessage>,
+    pub(crate) command_consumer: HeapCons<AudioBackendMessage<T>>,
+    pub(crate) status_producer: HeapProd<AudioEngineMessage>,
+    // replies to commands, kept apart from the status messages so they are never dropped
+    pub(crate) ack_producer: HeapProd<AudioEngineMessage>,
     pub(crate) graph: AudioGraph<T, Track<T>>,
     pub(crate) master: NodeIndex,
     pub(crate) master_buffer: InterleavedBuffer<T>,
+    // frames of the master buffer that were already handed to the device
+    pub(crate) consumed_frames: usize,
+    pub(crate) output_map: OutputMap,
     pub(crate) track_buffers: HashMap<NodeIndex, InterleavedBuffer<T>>,
 
+    pub(crate) transport: Transport,
+    pub(crate) input: Option<Input<T>>,
+    pub(crate) takes: Vec<Take<T>>,
+
     pub(crate) block_size: FrameTime,
-    pub(crate) block_duration_musical: MusicalTime,
-    pub(crate) block_range: Range<MusicalTime>,
     pub(crate) bpm: f64,
     pub(crate) sample_rate: SampleRate,
 
     pub(crate) running: bool,
+    // playback stopped or jumped, so sounding notes have to be released
+    pub(crate) release_notes: bool,
+    // reused between blocks so collecting events doesn't allocate
+    pub(crate) midi_events: Vec<MidiEvent>,
+
+    pub(crate) xruns: u64,
+    pub(crate) last_callback: Option<Instant>,
 }
 
 impl<T: SharedSample> AudioBackend<T> {
-    pub fn add_track(&mut self) {
+    pub fn add_track(&mut self) -> NodeIndex {
         let index = self
             .graph
             .add_node(Track::from_config(self.sample_rate, self.block_size));
@@ -50,27 +96,375 @@ impl<T: SharedSample> AudioBackend<T> {
             index,
             InterleavedBuffer::with_shape(NonZero::new(2).unwrap(), self.block_size),
         );
+
+        index
     }
-    pub fn add_connection(&mut self, source: NodeIndex, destination: NodeIndex, matrix: PinMatrix) {
-        if let Err(e) = self.graph.add_connection(source, destination, matrix) {
-            error!(
-                "Error while adding a connection to the audio graph: {:?}",
-                e
-            );
+
+    /// Buses don't get an input buffer, so the graph feeds them
+    /// the sum of the tracks that are routed into them.
+    pub fn add_bus(&mut self) -> NodeIndex {
+        let index = self
+            .graph
+            .add_node(Track::bus(self.sample_rate, self.block_size));
+
+        self.graph
+            .add_connection(index, self.master, PinMatrix::diagonal(2, 2))
+            .expect("logic error");
+
+        index
+    }
+
+    pub fn add_connection(
+        &mut self,
+        source: NodeIndex,
+        destination: NodeIndex,
+        connection: Connection,
+    ) -> AudioEngineStatus {
+        let matrix = connection.matrix().clone();
+        match self.graph.connect(source, destination, connection) {
+            Ok(edge) => AudioEngineStatus::ConnectionAdded(edge),
+            Err(e) => {
+                error!(
+                    "Error while adding a connection to the audio graph: {:?}",
+                    e
+                );
+                AudioEngineStatus::InvalidConnection {
+                    source,
+                    destination,
+                    matrix,
+                }
+            }
+        }
+    }
+
+    pub fn set_send(&mut self, edge: EdgeIndex, tap: SendTap, gain: f32) -> AudioEngineStatus {
+        match self.graph.set_connection_tap(edge, tap.tap()) {
+            Some(_) => {
+                self.graph.set_connection_gain(edge, gain);
+                AudioEngineStatus::ConnectionUpdated(edge)
+            }
+            None => AudioEngineStatus::InvalidEdge(edge),
+        }
+    }
+
+    pub fn update_connection(&mut self, edge: EdgeIndex, matrix: PinMatrix) -> AudioEngineStatus {
+        match self.graph.update_connection(edge, matrix.clone()) {
+            Some(_) => AudioEngineStatus::ConnectionUpdated(edge),
+            None => {
+                error!("Error while updating connection");
+                AudioEngineStatus::InvalidConnectionUpdate { edge, matrix }
+            }
         }
     }
 
-    pub fn update_connection(&mut self, edge: EdgeIndex, matrix: PinMatrix) {
-        if let None = self.graph.update_connection(edge, matrix) {
-            error!("Error while updating connection");
+    /// Removes a track and all of its connections. Like every removal from the graph
+    /// this moves the last track into the freed index.
+    pub fn remove_track(&mut self, track: NodeIndex) -> AudioEngineStatus {
+        if track == self.master || self.graph.get_node(track).is_none() {
+            return AudioEngineStatus::InvalidTrack(track);
+        }
+
+        while let Some(edge) = first_connection(self.graph.get_dag(), track) {
+            self.graph.remove_connection(edge);
+        }
+
+        let last = NodeIndex::new(self.graph.get_dag().node_count() - 1);
+        if let Err(e) = self.graph.remove_node(track) {
+            error!("Error while removing track {:?}: {:?}", track, e);
+            return AudioEngineStatus::InvalidTrack(track);
+        }
+
+        self.track_buffers.remove(&track);
+        if let Some(buffer) = self.track_buffers.remove(&last) {
+            self.track_buffers.insert(track, buffer);
+        }
+
+        self.output_map.remove_track(track, last);
+
+        self.takes.retain(|take| take.track() != track);
+        for take in &mut self.takes {
+            if take.track() == last {
+                take.set_track(track);
+            }
+        }
+
+        AudioEngineStatus::TrackRemoved(track)
+    }
+
+    pub fn remove_connection(&mut self, edge: EdgeIndex) -> AudioEngineStatus {
+        match self.graph.remove_connection(edge) {
+            Some(_) => AudioEngineStatus::ConnectionRemoved(edge),
+            None => AudioEngineStatus::InvalidEdge(edge),
+        }
+    }
+
+    pub fn set_mixer_control(
+        &mut self,
+        track: NodeIndex,
+        control: MixerControl,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.mixer_mut().set(control);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn set_solo_muted(&mut self, track: NodeIndex, muted: bool) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.mixer_mut().set_solo_muted(muted);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn add_processor(
+        &mut self,
+        track: NodeIndex,
+        processor: Box<dyn AudioProcessor<T>>,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => AudioEngineStatus::ProcessorAdded {
+                track,
+                processor: node.add_processor(processor),
+            },
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn add_processor_connection(
+        &mut self,
+        track: NodeIndex,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    ) -> AudioEngineStatus {
+        let Some(node) = self.graph.get_node_mut(track) else {
+            return AudioEngineStatus::InvalidTrack(track);
+        };
+
+        match node.add_connection(source, destination, matrix.clone()) {
+            Ok(edge) => AudioEngineStatus::ProcessorConnectionAdded { track, edge },
+            Err(e) => {
+                error!(
+                    "Error while adding a connection to track {:?}: {:?}",
+                    track, e
+                );
+                AudioEngineStatus::InvalidProcessorConnection {
+                    track,
+                    source,
+                    destination,
+                    matrix,
+                }
+            }
+        }
+    }
+
+    pub fn remove_processor_connection(
+        &mut self,
+        track: NodeIndex,
+        edge: EdgeIndex,
+    ) -> AudioEngineStatus {
+        let Some(node) = self.graph.get_node_mut(track) else {
+            return AudioEngineStatus::InvalidTrack(track);
+        };
+
+        match node.remove_connection(edge) {
+            Some(_) => AudioEngineStatus::Ok,
+            None => AudioEngineStatus::InvalidProcessorEdge { track, edge },
+        }
+    }
+
+    pub fn set_parameter(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    ) -> AudioEngineStatus {
+        let Some(node) = self.graph.get_node_mut(track) else {
+            return AudioEngineStatus::InvalidTrack(track);
+        };
+
+        if node.set_parameter(processor, parameter, value) {
+            AudioEngineStatus::ParameterSet(self.transport_status())
+        } else {
+            AudioEngineStatus::InvalidParameter {
+                track,
+                processor,
+                parameter,
+            }
+        }
+    }
+
+    pub fn set_automation_lane(
+        &mut self,
+        track: NodeIndex,
+        lane: AutomationLane,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.set_automation_lane(lane);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn remove_automation_lane(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> AudioEngineStatus {
+        let Some(node) = self.graph.get_node_mut(track) else {
+            return AudioEngineStatus::InvalidTrack(track);
+        };
+
+        match node.remove_automation_lane(processor, parameter) {
+            Some(_) => AudioEngineStatus::Ok,
+            None => AudioEngineStatus::InvalidParameter {
+                track,
+                processor,
+                parameter,
+            },
+        }
+    }
+
+    pub fn set_track_input(
+        &mut self,
+        track: NodeIndex,
+        input: Option<InputChannels>,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.set_input_channels(input);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn set_track_monitoring(
+        &mut self,
+        track: NodeIndex,
+        monitoring: bool,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.set_monitoring(monitoring);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    /// Cue buses get a secondary output in the graph, so their mix
+    /// is available after the block even if they aren't routed to master
+    pub fn set_output_map(&mut self, map: OutputMap) -> AudioEngineStatus {
+        if let Some(bus) = map.cues().find(|&bus| self.graph.get_node(bus).is_none()) {
+            return AudioEngineStatus::InvalidTrack(bus);
+        }
+
+        for bus in self.output_map.cues() {
+            self.graph.remove_secondary_output(bus);
+        }
+        for bus in map.cues() {
+            self.graph
+                .add_secondary_output(bus)
+                .expect("the bus was checked to exist");
+        }
+
+        self.output_map = map;
+        AudioEngineStatus::Ok
+    }
+
+    /// Ends the running takes. Dropping them tells their writers that the take is complete.
+    pub fn stop_recording(&mut self) -> AudioEngineStatus {
+        for take in self.takes.drain(..) {
+            take.finish(self.bpm, self.sample_rate);
+        }
+        AudioEngineStatus::RecordingStopped
+    }
+
+    pub fn insert_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    ) -> AudioEngineStatus {
+        if range.start >= range.end {
+            return AudioEngineStatus::InvalidRange(range);
+        }
+
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.get_playlist_mut().insert(range, clip);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn remove_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => match node.get_playlist_mut().remove(range.clone()) {
+                Some(_) => AudioEngineStatus::Ok,
+                None => AudioEngineStatus::InvalidRange(range),
+            },
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn insert_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    ) -> AudioEngineStatus {
+        if range.start >= range.end {
+            return AudioEngineStatus::InvalidRange(range);
+        }
+
+        match self.graph.get_node_mut(track) {
+            Some(node) => {
+                node.get_playlist_mut().insert_midi(range, clip);
+                AudioEngineStatus::Ok
+            }
+            None => AudioEngineStatus::InvalidTrack(track),
+        }
+    }
+
+    pub fn remove_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> AudioEngineStatus {
+        match self.graph.get_node_mut(track) {
+            Some(node) => match node.get_playlist_mut().remove_midi(range.clone()) {
+                Some(_) => {
+                    // the clip might have had notes sounding
+                    self.release_notes = true;
+                    AudioEngineStatus::Ok
+                }
+                None => AudioEngineStatus::InvalidRange(range),
+            },
+            None => AudioEngineStatus::InvalidTrack(track),
         }
     }
 }
 
 impl<T: SharedSample> AudioBackend<T> {
     pub fn new(
-        command_consumer: HeapCons<AudioBackendMessage>,
-        // status_producer: HeapProd<AudioEngineMessage>,
+        channels: BackendChannels<T>,
         graph: AudioGraph<T, Track<T>>,
         master: NodeIndex,
         block_size: FrameTime,
@@ -78,101 +472,618 @@ impl<T: SharedSample> AudioBackend<T> {
         sample_rate: SampleRate,
     ) -> Self {
         Self {
-            command_consumer,
-            // status_producer,
+            command_consumer: channels.commands,
+            status_producer: channels.statuses,
+            ack_producer: channels.acks,
             graph,
             master,
             master_buffer: InterleavedBuffer::with_shape(NonZero::new(2).unwrap(), block_size),
+            consumed_frames: block_size.0 as usize,
+            output_map: OutputMap::new(NonZero::new(2).unwrap()),
             track_buffers: HashMap::new(),
+            transport: Transport::new(block_size, bpm, sample_rate),
+            input: None,
+            takes: Vec::new(),
             block_size,
-            block_duration_musical: block_size.to_musical_lossy(bpm, sample_rate),
-            block_range: MusicalTime::ZERO..block_size.to_musical_lossy(bpm, sample_rate),
             bpm,
             sample_rate,
             running: false,
+            release_notes: false,
+            midi_events: Vec::with_capacity(1024),
+            xruns: 0,
+            last_callback: None,
         }
     }
 
+    /// Handles one device callback: applies pending commands, fills `output`
+    /// and publishes the transport state and processing load.
+    ///
+    /// The device may ask for any number of frames. They are taken from the rendered
+    /// blocks in order and a new block is rendered whenever the last one is used up.
+    pub fn process(&mut self, output: &mut [T]) {
+        let started = Instant::now();
+
+        self.process_commands();
+
+        let channels = self.output_map.device_channels().get();
+        let block_frames = self.master_buffer.frames();
+        let frames = output.len() / channels;
+        let mut position = 0;
+        while position < frames {
+            if self.consumed_frames == block_frames {
+                // the device plays master as it is, so whole blocks are rendered in place
+                if self.output_map.is_direct() && frames - position >= block_frames {
+                    let samples = &mut output[position * channels..][..block_frames * channels];
+                    self.render_block(&mut WrapInterleavedMut::new(samples, channels));
+                    position += block_frames;
+                    continue;
+                }
+
+                self.process_block();
+                self.consumed_frames = 0;
+            }
+
+            let index = self.consumed_frames;
+            let master = self
+                .master_buffer
+                .get_frame(index)
+                .expect("frame is in bounds");
+            let graph = &self.graph;
+            self.output_map.render(
+                master,
+                |bus| graph.secondary_output(bus)?.get_frame(index),
+                &mut output[position * channels..][..channels],
+            );
+            self.consumed_frames += 1;
+            position += 1;
+        }
+        output[frames * channels..].fill(T::EQUILIBRIUM);
+
+        let frames = output.len() / channels;
+        if frames == 0 {
+            return;
+        }
+
+        let budget = Duration::from_secs_f64(frames as f64 / self.sample_rate.0);
+        let load = started.elapsed().as_secs_f64() / budget.as_secs_f64();
+
+        // the processing took longer than the audio it produced (overrun) or
+        // the host called us late so the device must have run dry (underrun)
+        let late = self
+            .last_callback
+            .is_some_and(|last| started.duration_since(last) > budget.mul_f64(1.5));
+        if load > 1.0 || late {
+            self.xruns += 1;
+            self.publish(AudioEngineStatus::Xrun(self.xruns));
+        }
+        self.last_callback = Some(started);
+
+        self.publish(AudioEngineStatus::CpuLoad(load as f32));
+        self.publish(AudioEngineStatus::Transport(self.transport_status()));
+    }
+
+    fn transport_status(&self) -> TransportStatus {
+        TransportStatus {
+            playhead: self.transport.playhead(),
+            frame: self
+                .transport
+                .playhead()
+                .to_nearest_frame_round_lossy(self.bpm, self.sample_rate),
+            running: self.running,
+        }
+    }
+
+    /// Messages are dropped if the control side doesn't keep up with reading them
+    pub(crate) fn publish(&mut self, status: AudioEngineStatus) {
+        let _ = self
+            .status_producer
+            .try_push(AudioEngineMessage { id: None, status });
+    }
+
+    /// Applies the pending commands and replies to each of them. A command is only
+    /// taken from the queue while there is room for its reply, the rest wait for
+    /// the control side to read the replies it was sent so far.
     pub fn process_commands(&mut self) {
-        while let Some(message) = self.command_consumer.try_pop() {
-            match message.command {
-                AudioBackendCommand::Start => self.running = true,
-                AudioBackendCommand::Pause => self.running = false,
+        while !self.ack_producer.is_full()
+            && let Some(message) = self.command_consumer.try_pop()
+        {
+            let status = match message.command {
+                AudioBackendCommand::Start => {
+                    self.running = true;
+                    AudioEngineStatus::Ok
+                }
+                AudioBackendCommand::Pause => {
+                    self.running = false;
+                    self.release_notes = true;
+                    AudioEngineStatus::Ok
+                }
                 AudioBackendCommand::SetPlayhead(musical_time) => {
-                    self.block_range = musical_time..musical_time + self.block_duration_musical
+                    self.transport.set_playhead(musical_time);
+                    self.release_notes = true;
+                    AudioEngineStatus::Ok
+                }
+                AudioBackendCommand::SetLoop(range) => {
+                    if self.transport.set_loop(range.clone()) {
+                        AudioEngineStatus::Ok
+                    } else {
+                        error!(
+                            "Rejected loop region {:?}: it is shorter than a block",
+                            range
+                        );
+                        AudioEngineStatus::InvalidRange(range)
+                    }
+                }
+                AudioBackendCommand::EnableLoop(enabled) => {
+                    self.transport.enable_loop(enabled);
+                    AudioEngineStatus::Ok
+                }
+                AudioBackendCommand::SetPunch(range) => {
+                    if self.transport.set_punch(range.clone()) {
+                        AudioEngineStatus::Ok
+                    } else {
+                        error!(
+                            "Rejected punch region {:?}: start must be before end",
+                            range
+                        );
+                        AudioEngineStatus::InvalidRange(range)
+                    }
                 }
-                AudioBackendCommand::AddTrack => self.add_track(),
+                AudioBackendCommand::EnablePunch(enabled) => {
+                    self.transport.enable_punch(enabled);
+                    AudioEngineStatus::Ok
+                }
+                AudioBackendCommand::AddTrack => AudioEngineStatus::TrackAdded(self.add_track()),
+                AudioBackendCommand::AddBus => AudioEngineStatus::TrackAdded(self.add_bus()),
+                AudioBackendCommand::RemoveTrack(track) => self.remove_track(track),
                 AudioBackendCommand::AddConnection {
                     source,
                     destination,
-                    matrix,
-                } => self.add_connection(source, destination, matrix),
+                    connection,
+                } => self.add_connection(source, destination, connection),
                 AudioBackendCommand::UpdateConnection { edge, matrix } => {
                     self.update_connection(edge, matrix)
                 }
-            }
+                AudioBackendCommand::RemoveConnection(edge) => self.remove_connection(edge),
+                AudioBackendCommand::SetSend { edge, tap, gain } => self.set_send(edge, tap, gain),
+                AudioBackendCommand::SetTrackGain { track, gain } => {
+                    self.set_mixer_control(track, MixerControl::Gain(gain))
+                }
+                AudioBackendCommand::SetTrackPan { track, pan } => {
+                    self.set_mixer_control(track, MixerControl::Pan(pan))
+                }
+                AudioBackendCommand::SetPanLaw { track, law } => {
+                    self.set_mixer_control(track, MixerControl::PanLaw(law))
+                }
+                AudioBackendCommand::SetTrackMute { track, mute } => {
+                    self.set_mixer_control(track, MixerControl::Mute(mute))
+                }
+                AudioBackendCommand::SetTrackSolo { track, solo } => {
+                    self.set_mixer_control(track, MixerControl::Solo(solo))
+                }
+                AudioBackendCommand::SetTrackSoloMuted { track, muted } => {
+                    self.set_solo_muted(track, muted)
+                }
+                AudioBackendCommand::AddProcessor { track, processor } => {
+                    self.add_processor(track, processor)
+                }
+                AudioBackendCommand::AddProcessorConnection {
+                    track,
+                    source,
+                    destination,
+                    matrix,
+                } => self.add_processor_connection(track, source, destination, matrix),
+                AudioBackendCommand::RemoveProcessorConnection { track, edge } => {
+                    self.remove_processor_connection(track, edge)
+                }
+                AudioBackendCommand::SetParameter {
+                    track,
+                    processor,
+                    parameter,
+                    value,
+                } => self.set_parameter(track, processor, parameter, value),
+                AudioBackendCommand::SetAutomationLane { track, lane } => {
+                    self.set_automation_lane(track, lane)
+                }
+                AudioBackendCommand::RemoveAutomationLane {
+                    track,
+                    processor,
+                    parameter,
+                } => self.remove_automation_lane(track, processor, parameter),
+                AudioBackendCommand::InsertClip { track, range, clip } => {
+                    self.insert_clip(track, range, clip)
+                }
+                AudioBackendCommand::RemoveClip { track, range } => self.remove_clip(track, range),
+                AudioBackendCommand::InsertMidiClip { track, range, clip } => {
+                    self.insert_midi_clip(track, range, clip)
+                }
+                AudioBackendCommand::RemoveMidiClip { track, range } => {
+                    self.remove_midi_clip(track, range)
+                }
+                AudioBackendCommand::SetInput(input) => {
+                    self.input = input;
+                    AudioEngineStatus::Ok
+                }
+                AudioBackendCommand::SetTrackInput { track, input } => {
+                    self.set_track_input(track, input)
+                }
+                AudioBackendCommand::SetTrackMonitoring { track, monitoring } => {
+                    self.set_track_monitoring(track, monitoring)
+                }
+                AudioBackendCommand::StartRecording(takes) => {
+                    // takes that are still running are replaced
+                    self.stop_recording();
+                    self.takes = takes;
+                    AudioEngineStatus::Ok
+                }
+                AudioBackendCommand::StopRecording => self.stop_recording(),
+                AudioBackendCommand::SetOutputMap(map) => self.set_output_map(map),
+            };
+
+            let acknowledged = self.ack_producer.try_push(AudioEngineMessage {
+                id: Some(message.id),
+                status,
+            });
+            debug_assert!(acknowledged.is_ok(), "there is room for every reply");
         }
     }
 
     // PRECONDITIONS:
-    // a) track_buffers must hold a valid buffer for each track that isn't self.master
+    // a) track_buffers must hold a valid buffer for each track that is neither self.master nor a bus
     // b) master_buffer must be a valid buffer
-    pub fn process_block(&mut self, output: &mut [T]) {
-        if !self.running {
-            return;
+    /// Renders the next block into the master buffer
+    pub fn process_block(&mut self) {
+        // moved out so the graph can render into it while `self` is borrowed,
+        // the placeholder doesn't allocate
+        let mut master = std::mem::replace(
+            &mut self.master_buffer,
+            InterleavedBuffer::new(NonZero::new(2).unwrap()),
+        );
+        self.render_block(&mut master.wrap_mut());
+        self.master_buffer = master;
+    }
+
+    // PRECONDITIONS:
+    // a) same as `process_block`
+    // b) output has two channels and as many frames as the master buffer
+    /// Renders the next block of the master track into `output`
+    fn render_block(&mut self, output: &mut WrapInterleavedMut<'_, T>) {
+        for buffer in self.track_buffers.values_mut() {
+            buffer.set_to_equilibrium();
+        }
+        output.set_to_equilibrium();
+
+        // the input is read even while stopped, so it doesn't lag behind once playback starts
+        if let Some(input) = &mut self.input {
+            input.read();
         }
 
-        for track_index in self.graph.get_dag().graph().node_indices() {
-            if track_index == self.master {
-                continue;
+        // while stopped the playlists are neither played nor recorded,
+        // but the tracks still run so monitored inputs and tails are heard
+        let sub_blocks = if self.running {
+            self.transport.advance();
+            self.transport.sub_blocks()
+        } else {
+            &[]
+        };
+
+        for sub_block in sub_blocks {
+            if let Some(input) = &self.input
+                && let Some(frames) = self.transport.punched_frames(sub_block)
+            {
+                let timeline_start = sub_block
+                    .range
+                    .start
+                    .to_nearest_frame_round_lossy(self.bpm, self.sample_rate);
+
+                for take in &mut self.takes {
+                    let channels = self
+                        .graph
+                        .get_node(take.track())
+                        .and_then(|track| track.input_channels());
+
+                    for frame in frames.start.0..frames.end.0 {
+                        let offset = FrameTime(frame) - sub_block.offset;
+                        let samples = match (channels, input.block().get_frame(frame as usize)) {
+                            (Some(channels), Some(samples)) => channels.map(samples),
+                            _ => [T::EQUILIBRIUM; 2],
+                        };
+                        take.record(
+                            sub_block.range.start
+                                + offset.to_musical_lossy(self.bpm, self.sample_rate),
+                            timeline_start + offset,
+                            samples,
+                        );
+                    }
+                }
+            }
+
+            for track_index in self.graph.get_dag().graph().node_indices() {
+                // the master track and buses only play what is routed into them
+                let Some(track_buffer) = self.track_buffers.get_mut(&track_index) else {
+                    continue;
+                };
+
+                let track = self.graph.get_node(track_index).expect("logic error");
+
+                let block_events = track.get_playlist().get_block_events(
+                    sub_block.range.clone(),
+                    self.bpm,
+                    self.sample_rate,
+                );
+
+                for block_event in block_events {
+                    // clamp to the sub-block so rounding can't spill into the next one
+                    let start = (sub_block.offset + block_event.block_offset)
+                        .min(sub_block.offset + sub_block.frames);
+                    let frames = block_event
+                        .frames
+                        .0
+                        .min((sub_block.offset + sub_block.frames - start).0);
+
+                    mix_buffers_region(
+                        &block_event.event.buffer,
+                        block_event.clip_offset.0 as usize,
+                        track_buffer,
+                        start.0 as usize,
+                        frames as usize,
+                    )
+                    .expect("precondition a");
+                }
             }
+        }
 
-            let track = self.graph.get_node(track_index).expect("logic error");
+        if let Some(input) = &self.input {
+            for (&track_index, track_buffer) in &mut self.track_buffers {
+                let track = self.graph.get_node(track_index).expect("logic error");
+                let Some(channels) = track.input_channels().filter(|_| track.is_monitoring())
+                else {
+                    continue;
+                };
 
-            let block_events = track.get_playlist().get_block_events(
-                self.block_range.clone(),
-                self.bpm,
-                self.sample_rate,
-            );
+                track_buffer.map_frames_mut(
+                    |frame, index| {
+                        if let Some(samples) = input.block().get_frame(index) {
+                            for (out, sample) in frame.iter_mut().zip(channels.map(samples)) {
+                                *out = out.add_amp(sample.to_signed_sample());
+                            }
+                        }
+                        Some(())
+                    },
+                    None,
+                );
+            }
+        }
+
+        for index in 0..self.graph.get_dag().node_count() {
+            let track = self
+                .graph
+                .get_node_mut(NodeIndex::new(index))
+                .expect("index is in bounds");
+            track.automate(sub_blocks, self.bpm, self.sample_rate);
 
-            let track_buffer = self
-                .track_buffers
-                .get_mut(&track_index)
-                .expect("precondition a");
+            self.midi_events.clear();
+            if self.release_notes {
+                self.midi_events
+                    .push(MidiEvent::new(0, MidiMessage::AllNotesOff));
+            }
+            for (i, sub_block) in sub_blocks.iter().enumerate() {
+                // the loop wrapped, notes from its end must not keep sounding
+                if i > 0 {
+                    self.midi_events.push(MidiEvent::new(
+                        sub_block.offset.0 as usize,
+                        MidiMessage::AllNotesOff,
+                    ));
+                }
 
-            for block_event in block_events {
-                mix_buffers(
-                    &block_event.event.buffer,
-                    track_buffer,
-                    Some(block_event.block_offset.0 as usize),
-                )
-                .expect("precondition a");
+                track.get_playlist().get_midi_events(
+                    sub_block,
+                    self.bpm,
+                    self.sample_rate,
+                    &mut self.midi_events,
+                );
             }
+
+            sort_events(&mut self.midi_events);
+            track.schedule_events(&self.midi_events);
         }
+        self.release_notes = false;
 
         self.graph.process_block(
             &self.track_buffers.iter().map(|(&k, v)| (k, v)).collect(),
-            &mut self.master_buffer,
+            output,
         );
+    }
+}
 
-        // TODO: make clean adapter abstraction
-        let channels = self.master_buffer.channels();
-        for (i, sample) in output.iter_mut().enumerate() {
-            let frame = i / channels;
-            let channel = i % channels;
-            *sample = *self
-                .master_buffer
-                .get_sample(channel, frame)
-                .unwrap_or(&T::EQUILIBRIUM);
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZero, sync::Arc};
+
+    use audio_buffer::{buffers::interleaved::InterleavedBuffer, core::BufferMut};
+    use audio_graph::{AudioGraph, Connection, daggy::NodeIndex, pin_matrix::PinMatrix};
+    use ringbuf::{
+        HeapRb,
+        traits::{Consumer, Observer, Producer, Split},
+    };
+    use time::{FrameTime, SampleRate};
+
+    use crate::{
+        backend::{AudioBackend, BackendChannels},
+        input::{FileInput, Input, InputChannels},
+        message::{AudioBackendCommand, AudioBackendMessage, AudioEngineStatus, MessageId},
+        mixer::MixerControl,
+        model::first_connection,
+        output::{OutputChannels, OutputMap, OutputSource},
+        track::{SendTap, Track},
+    };
+
+    // plays a ramp through a monitored track
+    fn monitoring_backend() -> AudioBackend<f32> {
+        let sample_rate = SampleRate::new(1000.0);
+        let block_size = FrameTime(64);
+
+        let mut ramp = InterleavedBuffer::with_shape(NonZero::new(1).unwrap(), FrameTime(1000));
+        ramp.map_frames_mut(
+            |frame, index| {
+                frame[0] = index as f32 / 1000.0;
+                Some(())
+            },
+            None,
+        );
+
+        let (_, command_consumer) = HeapRb::new(16).split();
+        let (status_producer, _) = HeapRb::new(64).split();
+        let (ack_producer, _) = HeapRb::new(16).split();
+        let (graph, master) =
+            AudioGraph::new(Track::bus(sample_rate, block_size), sample_rate, block_size);
+        let mut backend = AudioBackend::new(
+            BackendChannels {
+                commands: command_consumer,
+                statuses: status_producer,
+                acks: ack_producer,
+            },
+            graph,
+            master,
+            block_size,
+            120.0,
+            sample_rate,
+        );
+
+        let track = backend.add_track();
+        backend.set_track_input(track, Some(InputChannels::Mono(0)));
+        backend.set_track_monitoring(track, true);
+        backend.input = Some(Input::new(
+            Box::new(FileInput::new(Arc::new(ramp))),
+            block_size,
+        ));
+        backend.running = true;
+        backend
+    }
+
+    #[test]
+    fn output_is_contiguous_for_any_callback_size() {
+        let render = |callbacks: &[usize]| {
+            let mut backend = monitoring_backend();
+            let mut rendered = Vec::new();
+            for &frames in callbacks {
+                let mut output = vec![1.0; frames * 2];
+                backend.process(&mut output);
+                rendered.extend(output);
+            }
+            rendered
+        };
+
+        let expected = render(&[64; 10]);
+        assert!(expected.iter().any(|&sample| sample != 0.0));
+
+        assert_eq!(render(&[37, 163, 1, 99, 0, 340]), expected);
+        assert_eq!(render(&[640]), expected);
+        // callbacks shorter than a block are never rendered in place
+        assert_eq!(
+            render(&[63, 63, 63, 63, 63, 63, 63, 63, 63, 63, 10]),
+            expected
+        );
+    }
+
+    #[test]
+    fn monitored_inputs_are_heard_while_stopped() {
+        let mut backend = monitoring_backend();
+        backend.running = false;
+        let playhead = backend.transport.playhead();
+
+        let mut output = vec![0.0; 64 * 2];
+        backend.process(&mut output);
+        backend.process(&mut output);
+
+        assert!(output.iter().any(|&sample| sample != 0.0));
+        assert_eq!(backend.transport.playhead(), playhead);
+    }
+
+    #[test]
+    fn cue_buses_play_on_their_own_outputs() {
+        let mut backend = monitoring_backend();
+        let track = NodeIndex::new(1);
+
+        // a headphone mix that only gets a pre-fader send of the track
+        let cue = backend.add_bus();
+        let to_master = first_connection(backend.graph.get_dag(), cue).unwrap();
+        backend.remove_connection(to_master);
+        backend.add_connection(
+            track,
+            cue,
+            Connection::new(PinMatrix::diagonal(2, 2)).with_tap(SendTap::PreFader.tap()),
+        );
+        backend.set_mixer_control(track, MixerControl::Mute(true));
+
+        let mut map = OutputMap::new(NonZero::new(4).unwrap());
+        map.set_routes(OutputSource::Master, &[OutputChannels::Stereo(2, 3)])
+            .unwrap();
+        map.add_route(OutputSource::Cue(cue), OutputChannels::Stereo(0, 1))
+            .unwrap();
+        backend.set_output_map(map);
+
+        // the mute ramps in during the first block
+        let mut output = vec![1.0; 64 * 4];
+        backend.process(&mut output);
+        backend.process(&mut output);
+
+        let frames: Vec<_> = output.chunks_exact(4).collect();
+        assert!(
+            frames
+                .iter()
+                .all(|frame| frame[2] == 0.0 && frame[3] == 0.0)
+        );
+        assert!(frames.iter().any(|frame| frame[0] != 0.0));
+        assert!(frames.iter().all(|frame| frame[0] == frame[1]));
+    }
+
+    #[test]
+    fn replies_arrive_while_the_status_ring_is_full() {
+        let sample_rate = SampleRate::new(1000.0);
+        let block_size = FrameTime(64);
+
+        let (mut commands, command_consumer) = HeapRb::new(16).split();
+        let (status_producer, statuses) = HeapRb::new(4).split();
+        let (ack_producer, mut acks) = HeapRb::new(2).split();
+        let (graph, master) =
+            AudioGraph::new(Track::bus(sample_rate, block_size), sample_rate, block_size);
+        let mut backend = AudioBackend::<f32>::new(
+            BackendChannels {
+                commands: command_consumer,
+                statuses: status_producer,
+                acks: ack_producer,
+            },
+            graph,
+            master,
+            block_size,
+            120.0,
+            sample_rate,
+        );
+
+        // nobody reads the transport and load reports
+        let mut output = vec![0.0; 64 * 2];
+        for _ in 0..4 {
+            backend.process(&mut output);
         }
+        assert!(statuses.is_full());
 
-        for buffer in self.track_buffers.values_mut() {
-            buffer.set_to_equilibrium();
+        for id in 0..3 {
+            let message = AudioBackendMessage {
+                id: MessageId(id),
+                command: AudioBackendCommand::Start,
+            };
+            assert!(commands.try_push(message).is_ok());
         }
+        backend.process(&mut output);
 
-        self.master_buffer.set_to_equilibrium();
+        // the last command waits until its reply fits
+        let replies: Vec<_> = acks.pop_iter().map(|message| message.id).collect();
+        assert_eq!(replies, [Some(MessageId(0)), Some(MessageId(1))]);
+        assert_eq!(commands.occupied_len(), 1);
 
-        self.block_range =
-            self.block_range.end..(self.block_range.end + self.block_duration_musical)
+        backend.process(&mut output);
+        let reply = acks.try_pop().unwrap();
+        assert_eq!(reply.id, Some(MessageId(2)));
+        assert!(matches!(reply.status, AudioEngineStatus::Ok));
     }
 }
@@ -0,0 +1,439 @@
+use std::ops::RangeInclusive;
+
+use cpal::{
+    BufferSize, HostId, SampleFormat, SupportedBufferSize, SupportedStreamConfigRange,
+    traits::{DeviceTrait, HostTrait},
+};
+use time::{FrameTime, SampleRate};
+
+use crate::format::integer_bits;
+
+/// The sample rate that is asked for if neither the request nor the device prefers one
+const FALLBACK_SAMPLE_RATE: u32 = 48_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+#[derive(Debug)]
+pub enum DeviceError {
+    HostUnavailable(HostId),
+    /// There is no device with the requested name or no default device
+    DeviceNotFound(Option<String>),
+    Devices(cpal::DevicesError),
+    Configs(cpal::SupportedStreamConfigsError),
+    /// The device doesn't support any configuration the engine can convert to
+    NoConfigs,
+    /// The sample format isn't one the engine can convert to
+    UnsupportedSampleFormat(SampleFormat),
+    Build(cpal::BuildStreamError),
+    Play(cpal::PlayStreamError),
+    /// The device can't run at the sample rate the engine was created with
+    SampleRate(SampleRate),
+}
+
+/// An error a running stream reported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamFailure {
+    /// The device was disconnected or is no longer usable
+    DeviceLost,
+    Backend(String),
+}
+
+impl From<cpal::StreamError> for StreamFailure {
+    fn from(value: cpal::StreamError) -> Self {
+        match value {
+            cpal::StreamError::DeviceNotAvailable => Self::DeviceLost,
+            cpal::StreamError::BackendSpecific { err } => Self::Backend(err.description),
+        }
+    }
+}
+
+/// A range of configurations a device supports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportedConfig {
+    pub channels: u16,
+    pub sample_rates: RangeInclusive<u32>,
+    /// `None` if the host can't tell before a stream is opened
+    pub buffer_sizes: Option<RangeInclusive<u32>>,
+    pub sample_format: SampleFormat,
+}
+
+impl From<SupportedStreamConfigRange> for SupportedConfig {
+    fn from(value: SupportedStreamConfigRange) -> Self {
+        Self {
+            channels: value.channels(),
+            sample_rates: value.min_sample_rate().0..=value.max_sample_rate().0,
+            buffer_sizes: match *value.buffer_size() {
+                SupportedBufferSize::Range { min, max } => Some(min..=max),
+                SupportedBufferSize::Unknown => None,
+            },
+            sample_format: value.sample_format(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub is_default_input: bool,
+    pub is_default_output: bool,
+    pub input_configs: Vec<SupportedConfig>,
+    pub output_configs: Vec<SupportedConfig>,
+}
+
+/// What a stream should be opened with. Everything that isn't set
+/// is left to the device, or the default device of the default host.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamRequest {
+    host: Option<HostId>,
+    device: Option<String>,
+    sample_rate: Option<SampleRate>,
+    buffer_size: Option<FrameTime>,
+    channels: Option<u16>,
+}
+
+impl StreamRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_host(mut self, host: HostId) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    pub fn with_device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+
+    pub fn with_sample_rate(mut self, sample_rate: SampleRate) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// The number of frames the device should ask for per callback
+    pub fn with_buffer_size(mut self, buffer_size: FrameTime) -> Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    pub fn with_channels(mut self, channels: u16) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Whether the request names a device rather than using the default one
+    pub fn has_device(&self) -> bool {
+        self.device.is_some()
+    }
+
+    /// Whether the device the request names can be found right now
+    pub fn is_available(&self, direction: Direction) -> bool {
+        find_device(self.host, self.device.as_deref(), direction).is_ok()
+    }
+
+    /// The same request for the default device of the host
+    pub fn with_default_device(mut self) -> Self {
+        self.device = None;
+        self
+    }
+}
+
+/// The configuration a stream was actually opened with,
+/// which can differ from what was requested
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamConfig {
+    pub host: HostId,
+    pub device: String,
+    pub sample_rate: SampleRate,
+    /// `None` if the host chooses the buffer size
+    pub buffer_size: Option<FrameTime>,
+    /// The most frames the device asks for in one callback, `None` if the host can't tell
+    pub max_buffer_size: Option<FrameTime>,
+    pub channels: u16,
+    pub sample_format: SampleFormat,
+}
+
+impl StreamConfig {
+    pub fn to_cpal(&self) -> cpal::StreamConfig {
+        cpal::StreamConfig {
+            channels: self.channels,
+            sample_rate: cpal::SampleRate(self.sample_rate.0.round() as u32),
+            buffer_size: match self.buffer_size {
+                Some(frames) => BufferSize::Fixed(frames.0 as u32),
+                None => BufferSize::Default,
+            },
+        }
+    }
+}
+
+pub fn hosts() -> Vec<HostId> {
+    cpal::available_hosts()
+}
+
+/// Lists the devices of `host` with the configurations they support.
+/// Devices whose name can't be read are left out.
+pub fn devices(host: HostId) -> Result<Vec<DeviceInfo>, DeviceError> {
+    let host = cpal::host_from_id(host).map_err(|_| DeviceError::HostUnavailable(host))?;
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+
+    let devices = host
+        .devices()
+        .map_err(DeviceError::Devices)?
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            // devices without inputs or outputs fail to list the configurations for them
+            let input_configs = device
+                .supported_input_configs()
+                .map(|configs| configs.map(SupportedConfig::from).collect())
+                .unwrap_or_default();
+            let output_configs = device
+                .supported_output_configs()
+                .map(|configs| configs.map(SupportedConfig::from).collect())
+                .unwrap_or_default();
+
+            Some(DeviceInfo {
+                is_default_input: default_input.as_ref() == Some(&name),
+                is_default_output: default_output.as_ref() == Some(&name),
+                name,
+                input_configs,
+                output_configs,
+            })
+        })
+        .collect();
+
+    Ok(devices)
+}
+
+/// Returns the device called `name` on `host`,
+/// or its default device if no name is given
+pub fn find_device(
+    host: Option<HostId>,
+    name: Option<&str>,
+    direction: Direction,
+) -> Result<cpal::Device, DeviceError> {
+    let host = match host {
+        Some(id) => cpal::host_from_id(id).map_err(|_| DeviceError::HostUnavailable(id))?,
+        None => cpal::default_host(),
+    };
+
+    let device = match (name, direction) {
+        (Some(name), Direction::Input) => host
+            .input_devices()
+            .map_err(DeviceError::Devices)?
+            .find(|device| device.name().is_ok_and(|n| n == name)),
+        (Some(name), Direction::Output) => host
+            .output_devices()
+            .map_err(DeviceError::Devices)?
+            .find(|device| device.name().is_ok_and(|n| n == name)),
+        (None, Direction::Input) => host.default_input_device(),
+        (None, Direction::Output) => host.default_output_device(),
+    };
+
+    device.ok_or_else(|| DeviceError::DeviceNotFound(name.map(str::to_owned)))
+}
+
+/// Finds the device a request asks for and the configuration closest to it
+/// that the device supports, preferably in `sample_format`
+pub fn negotiate(
+    request: &StreamRequest,
+    direction: Direction,
+    sample_format: SampleFormat,
+) -> Result<(cpal::Device, StreamConfig), DeviceError> {
+    let device = find_device(request.host, request.device.as_deref(), direction)?;
+
+    let (configs, default): (Vec<SupportedConfig>, _) = match direction {
+        Direction::Input => (
+            device
+                .supported_input_configs()
+                .map_err(DeviceError::Configs)?
+                .map(SupportedConfig::from)
+                .collect(),
+            device.default_input_config().ok(),
+        ),
+        Direction::Output => (
+            device
+                .supported_output_configs()
+                .map_err(DeviceError::Configs)?
+                .map(SupportedConfig::from)
+                .collect(),
+            device.default_output_config().ok(),
+        ),
+    };
+
+    let sample_rate = match request.sample_rate {
+        Some(sample_rate) => sample_rate.0.round() as u32,
+        None => default.map_or(FALLBACK_SAMPLE_RATE, |config| config.sample_rate().0),
+    };
+    let (config, sample_format) = closest_config(
+        &configs,
+        sample_format,
+        request.channels.unwrap_or(2),
+        sample_rate,
+        request.buffer_size,
+    )?;
+
+    let buffer_size = match config.buffer_size {
+        BufferSize::Fixed(frames) => Some(FrameTime(frames as u64)),
+        BufferSize::Default => None,
+    };
+    let max_buffer_size = buffer_size.or_else(|| {
+        configs
+            .iter()
+            .filter(|supported| {
+                supported.channels == config.channels && supported.sample_format == sample_format
+            })
+            .filter_map(|supported| supported.buffer_sizes.as_ref())
+            .map(|range| FrameTime(*range.end() as u64))
+            .max()
+    });
+
+    let negotiated = StreamConfig {
+        host: request.host.unwrap_or(cpal::default_host().id()),
+        device: device.name().unwrap_or_default(),
+        sample_rate: SampleRate::new(config.sample_rate.0 as f64),
+        buffer_size,
+        max_buffer_size,
+        channels: config.channels,
+        sample_format,
+    };
+    Ok((device, negotiated))
+}
+
+/// Prefers configurations with the requested number of channels, then the ones whose
+/// sample rates are closest to the requested one and then the requested sample format,
+/// other float formats and the widest integer formats. The sample rate and buffer size
+/// are clamped to what the chosen configuration supports.
+fn closest_config(
+    configs: &[SupportedConfig],
+    sample_format: SampleFormat,
+    channels: u16,
+    sample_rate: u32,
+    buffer_size: Option<FrameTime>,
+) -> Result<(cpal::StreamConfig, SampleFormat), DeviceError> {
+    let format_rank = |format: SampleFormat| {
+        if format == sample_format {
+            0
+        } else if format.is_float() {
+            1
+        } else {
+            // the integer formats are ranked after the floats, wide ones first
+            2 + 64 - integer_bits(format).unwrap_or(0)
+        }
+    };
+
+    let clamp_rate = |config: &SupportedConfig| {
+        sample_rate.clamp(*config.sample_rates.start(), *config.sample_rates.end())
+    };
+
+    let config = configs
+        .iter()
+        .filter(|config| {
+            config.sample_format.is_float() || integer_bits(config.sample_format).is_some()
+        })
+        .min_by_key(|config| {
+            (
+                config.channels != channels,
+                clamp_rate(config).abs_diff(sample_rate),
+                format_rank(config.sample_format),
+                config.channels.abs_diff(channels),
+            )
+        })
+        .ok_or(DeviceError::NoConfigs)?;
+
+    let buffer_size = match (buffer_size, &config.buffer_sizes) {
+        (Some(frames), Some(range)) => {
+            BufferSize::Fixed((frames.0 as u32).clamp(*range.start(), *range.end()))
+        }
+        // without knowing the range a fixed size may fail to open
+        _ => BufferSize::Default,
+    };
+
+    let stream_config = cpal::StreamConfig {
+        channels: config.channels,
+        sample_rate: cpal::SampleRate(clamp_rate(config)),
+        buffer_size,
+    };
+    Ok((stream_config, config.sample_format))
+}
+
+#[cfg(test)]
+mod tests {
+    use cpal::{BufferSize, SampleFormat};
+    use time::FrameTime;
+
+    use crate::device::{DeviceError, StreamFailure, SupportedConfig, closest_config};
+
+    fn config(channels: u16, rates: (u32, u32), format: SampleFormat) -> SupportedConfig {
+        SupportedConfig {
+            channels,
+            sample_rates: rates.0..=rates.1,
+            buffer_sizes: Some(64..=1024),
+            sample_format: format,
+        }
+    }
+
+    #[test]
+    fn prefers_the_requested_channels_and_clamps_the_rest() {
+        let configs = [
+            config(2, (44_100, 44_100), SampleFormat::F32),
+            config(6, (8_000, 192_000), SampleFormat::F32),
+            config(2, (8_000, 192_000), SampleFormat::I16),
+            config(2, (44_100, 44_100), SampleFormat::I32),
+        ];
+
+        let (chosen, format) = closest_config(
+            &configs,
+            SampleFormat::F32,
+            2,
+            44_100,
+            Some(FrameTime(4096)),
+        )
+        .unwrap();
+        assert_eq!((chosen.channels, format), (2, SampleFormat::F32));
+        assert_eq!(chosen.buffer_size, BufferSize::Fixed(1024));
+
+        // the sample rate matters more than the format, which is converted
+        let (chosen, format) =
+            closest_config(&configs, SampleFormat::F32, 2, 48_000, None).unwrap();
+        assert_eq!((chosen.sample_rate.0, format), (48_000, SampleFormat::I16));
+        assert_eq!(chosen.buffer_size, BufferSize::Default);
+
+        let (chosen, format) =
+            closest_config(&configs, SampleFormat::F32, 6, 48_000, None).unwrap();
+        assert_eq!((chosen.channels, format), (6, SampleFormat::F32));
+
+        // other floats are preferred over integers, wide integers over narrow ones
+        let (_, format) = closest_config(&configs, SampleFormat::F64, 2, 44_100, None).unwrap();
+        assert_eq!(format, SampleFormat::F32);
+        let (_, format) =
+            closest_config(&configs[2..], SampleFormat::F64, 2, 44_100, None).unwrap();
+        assert_eq!(format, SampleFormat::I32);
+
+        assert!(matches!(
+            closest_config(&[], SampleFormat::F32, 2, 48_000, None),
+            Err(DeviceError::NoConfigs)
+        ));
+    }
+
+    #[test]
+    fn stream_errors_map_to_failures() {
+        assert_eq!(
+            StreamFailure::from(cpal::StreamError::DeviceNotAvailable),
+            StreamFailure::DeviceLost
+        );
+
+        let err = cpal::BackendSpecificError {
+            description: "xrun recovery failed".into(),
+        };
+        assert_eq!(
+            StreamFailure::from(cpal::StreamError::BackendSpecific { err }),
+            StreamFailure::Backend("xrun recovery failed".into())
+        );
+    }
+}
@@ -1,87 +1,480 @@
-use std::marker::PhantomData;
-use std::{path::Path, sync::Arc};
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use audio_buffer::SharedSample;
+use audio_buffer::dasp::sample::{FloatSample, FromSample};
 use audio_buffer::symphonia::core::conv::ConvertibleSample;
 use audio_buffer::{buffers::interleaved::InterleavedBuffer, loader::error::LoadError};
-use audio_graph::AudioGraph;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use ringbuf::traits::{Producer, Split};
+use audio_graph::daggy::{EdgeIndex, NodeIndex};
+use audio_graph::parameter::ParameterId;
+use audio_graph::pin_matrix::PinMatrix;
+use audio_graph::processor::AudioProcessor;
+use audio_graph::{AudioGraph, Connection};
+use cpal::SampleFormat;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
 use ringbuf::{HeapCons, HeapProd, HeapRb};
-use time::{FrameTime, SampleRate};
+use time::{FrameTime, MusicalTime, SampleRate};
 
-use crate::backend::AudioBackend;
-use crate::message::{AudioBackendCommand, AudioBackendMessage, AudioEngineMessage, MessageId};
-use crate::track::Track;
+use crate::automation::{AutomationLane, AutomationMode, Breakpoint};
+use crate::backend::{AudioBackend, BackendChannels};
+use crate::device::{self, DeviceError, Direction, StreamConfig, StreamFailure, StreamRequest};
+use crate::format::{OutputConverter, float_format};
+use crate::history::{Edit, History, Transaction};
+use crate::input::{
+    Input, InputChannels, InputError, InputSource, open_input_stream, start_input_stream,
+};
+use crate::message::{
+    AudioBackendCommand, AudioBackendMessage, AudioEngineMessage, AudioEngineStatus, MessageId,
+    TransportStatus,
+};
+use crate::midi::{self, MidiClip, MidiLoadError};
+use crate::mixer::{MixerControl, PanLaw};
+use crate::model::{ConnectionId, EngineModel, ModelError, ProcessorModel};
+use crate::output::{OutputChannels, OutputError, OutputMap, OutputSource};
+use crate::playlist::Clip;
+use crate::recording::{DiskWriter, RecordingError};
+use crate::track::{SendTap, Track, TrackKind};
+
+// how many replies to commands are held for `poll_status`
+const PENDING_ACKS: usize = 256;
+// how many errors of a stream are kept until they are polled
+const STREAM_FAILURES: usize = 16;
+// how long an edit waits for the audio thread before the engine processes it itself
+const EDIT_TIMEOUT: Duration = Duration::from_millis(250);
+// frames converted at once when the host can't tell how large its callbacks get
+const FALLBACK_CALLBACK_FRAMES: usize = 4096;
 
 #[derive(Debug)]
 pub enum AudioEngineError {
     QueueFull,
+    /// The backend didn't answer in time, e.g. because the stream isn't running
+    Timeout(MessageId),
+    /// The backend processed the command but rejected it
+    Rejected(AudioEngineStatus),
+    /// The edit was rejected by the model before it was sent to the backend
+    Invalid(ModelError),
+    Output(OutputError),
+}
+
+impl From<ModelError> for AudioEngineError {
+    fn from(value: ModelError) -> Self {
+        Self::Invalid(value)
+    }
+}
+
+impl From<OutputError> for AudioEngineError {
+    fn from(value: OutputError) -> Self {
+        Self::Output(value)
+    }
+}
+
+// parameter changes that are being written to an automation lane
+struct AutomationPass {
+    track: NodeIndex,
+    processor: NodeIndex,
+    parameter: ParameterId,
+    // the lane before the pass started, restored on undo
+    original: AutomationLane,
+    points: Vec<Breakpoint>,
+}
+
+// what the callbacks of the streams share with the engine
+struct StreamShared<T: SharedSample> {
+    // locked by the engine only while the stream is rebuilt,
+    // the audio thread plays silence instead of waiting for it
+    backend: Mutex<AudioBackend<T>>,
+    device_lost: AtomicBool,
+    input_lost: AtomicBool,
+}
+
+impl<T: SharedSample> StreamShared<T> {
+    fn lost(&self, direction: Direction) -> &AtomicBool {
+        match direction {
+            Direction::Input => &self.input_lost,
+            Direction::Output => &self.device_lost,
+        }
+    }
 }
 
+/// Processes audio in `T`, which is `f32` or `f64`, and converts
+/// it to whatever format the devices were opened with.
+///
+/// Errors of the output stream are reported as `StreamError` through `poll_status`,
+/// those of the input stream as `InputStreamError`. The engine doesn't recover from
+/// them by itself, `check_stream` has to be called regularly to rebuild a stream
+/// once its device was lost.
 pub struct AudioEngine<T>
 where
-    T: audio_buffer::dasp::Sample + 'static,
+    T: SharedSample,
 {
     _block_size: FrameTime,
     _sample_rate: SampleRate,
     _bpm: f64,
 
     next_message_id: u64,
-    command_producer: HeapProd<AudioBackendMessage>,
-    _status_consumer: HeapCons<AudioEngineMessage>,
+    command_producer: HeapProd<AudioBackendMessage<T>>,
+    status_consumer: HeapCons<AudioEngineMessage>,
+    ack_consumer: HeapCons<AudioEngineMessage>,
+    // replies that were received while waiting for the reply to another command
+    pending_acks: VecDeque<AudioEngineMessage>,
+    model: EngineModel<T>,
+    history: History<T>,
+    // the latest transport state reported by the backend
+    transport: Option<TransportStatus>,
+    passes: Vec<AutomationPass>,
+    // writers of the takes that are being recorded
+    writers: Vec<(NodeIndex, DiskWriter)>,
+    next_take: u64,
+    // what the output device was actually opened with
+    stream_config: StreamConfig,
+    // what it was asked to be opened with, used to reopen it
+    stream_request: StreamRequest,
+    // the stream runs on the default device because the requested one was lost
+    on_fallback: bool,
+    output_map: OutputMap,
+    shared: Arc<StreamShared<T>>,
     _stream: Option<cpal::Stream>,
-    _marker: PhantomData<T>,
+    // errors the output stream reported, pushed by its error callback
+    stream_failures: Option<HeapCons<StreamFailure>>,
+    _input_stream: Option<cpal::Stream>,
+    // what the input device was asked to be opened with, `None` without an input stream
+    input_request: Option<StreamRequest>,
+    input_failures: Option<HeapCons<StreamFailure>>,
 }
 
 impl<T> AudioEngine<T>
 where
-    T: SharedSample + cpal::SizedSample,
+    T: SharedSample + FloatSample,
 {
+    /// Opens the default output device, asking for `sample_rate` and `block_size`.
+    ///
+    /// # Panics
+    /// If there is no output device that supports the sample format `T`.
     pub fn new(bpm: f64, sample_rate: SampleRate, block_size: FrameTime) -> Self {
-        let (cmd_prod, cmd_cons) = HeapRb::<AudioBackendMessage>::new(256).split();
-        let (_status_prod, status_cons) = HeapRb::<AudioEngineMessage>::new(256).split();
+        let request = StreamRequest::new()
+            .with_sample_rate(sample_rate)
+            .with_buffer_size(block_size);
+
+        Self::with_device(bpm, block_size, &request).expect("failed to open the output device")
+    }
+
+    /// Opens the output device `request` asks for. The backend runs at the sample rate
+    /// the device was opened with, which `stream_config` reports.
+    pub fn with_device(
+        bpm: f64,
+        block_size: FrameTime,
+        request: &StreamRequest,
+    ) -> Result<Self, DeviceError> {
+        let (device, stream_config) =
+            device::negotiate(request, Direction::Output, float_format::<T>())?;
+        let mut engine = Self::without_stream(bpm, block_size, request, stream_config);
+        let (stream, failures) =
+            Self::start_stream(&device, &engine.stream_config, &engine.shared)?;
+        engine._stream = Some(stream);
+        engine.stream_failures = Some(failures);
+        Ok(engine)
+    }
+
+    // Nothing is rendered until a stream is started for the backend
+    fn without_stream(
+        bpm: f64,
+        block_size: FrameTime,
+        request: &StreamRequest,
+        stream_config: StreamConfig,
+    ) -> Self {
+        let sample_rate = stream_config.sample_rate;
 
-        let master_track = Track::from_config(sample_rate, block_size);
+        let (cmd_prod, cmd_cons) = HeapRb::<AudioBackendMessage<T>>::new(256).split();
+        let (status_prod, status_cons) = HeapRb::<AudioEngineMessage>::new(1024).split();
+        let (ack_prod, ack_cons) = HeapRb::<AudioEngineMessage>::new(PENDING_ACKS).split();
+
+        let master_track = Track::bus(sample_rate, block_size);
         let (graph, master_idx) = AudioGraph::new(master_track, sample_rate, block_size);
 
-        let backend = AudioBackend::new(cmd_cons, graph, master_idx, block_size, bpm, sample_rate);
+        let mut backend = AudioBackend::new(
+            BackendChannels {
+                commands: cmd_cons,
+                statuses: status_prod,
+                acks: ack_prod,
+            },
+            graph,
+            master_idx,
+            block_size,
+            bpm,
+            sample_rate,
+        );
+
+        let output_map = OutputMap::new(
+            NonZeroUsize::new(stream_config.channels as usize)
+                .expect("devices have at least one channel"),
+        );
+        backend.set_output_map(output_map.clone());
 
-        let stream = Self::start_stream(backend);
+        let shared = Arc::new(StreamShared {
+            backend: Mutex::new(backend),
+            device_lost: AtomicBool::new(false),
+            input_lost: AtomicBool::new(false),
+        });
 
         Self {
             _block_size: block_size,
             _sample_rate: sample_rate,
             _bpm: bpm,
-            _stream: Some(stream),
-            _marker: PhantomData,
+            stream_request: request.clone(),
+            on_fallback: false,
+            stream_config,
+            output_map,
+            shared,
+            _stream: None,
+            stream_failures: None,
+            _input_stream: None,
+            input_request: None,
+            input_failures: None,
+            writers: Vec::new(),
+            next_take: 0,
+            model: EngineModel::new(),
+            history: History::new(),
+            transport: None,
+            passes: Vec::new(),
             command_producer: cmd_prod,
-            _status_consumer: status_cons,
+            status_consumer: status_cons,
+            ack_consumer: ack_cons,
+            pending_acks: VecDeque::new(),
             next_message_id: 0,
         }
     }
 
-    fn start_stream(mut backend: AudioBackend<T>) -> cpal::Stream {
-        let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .expect("no output device available");
-        let config = device.default_output_config().unwrap();
+    /// The backend renders in `T`, straight into the device's buffer if its format
+    /// is `T` and otherwise into a scratch buffer that is converted to it
+    fn start_stream(
+        device: &cpal::Device,
+        config: &StreamConfig,
+        shared: &Arc<StreamShared<T>>,
+    ) -> Result<(cpal::Stream, HeapCons<StreamFailure>), DeviceError> {
+        match config.sample_format {
+            SampleFormat::I8 => Self::build_output_stream::<i8>(device, config, shared),
+            SampleFormat::I16 => Self::build_output_stream::<i16>(device, config, shared),
+            SampleFormat::I24 => Self::build_output_stream::<cpal::I24>(device, config, shared),
+            SampleFormat::I32 => Self::build_output_stream::<i32>(device, config, shared),
+            SampleFormat::I64 => Self::build_output_stream::<i64>(device, config, shared),
+            SampleFormat::U8 => Self::build_output_stream::<u8>(device, config, shared),
+            SampleFormat::U16 => Self::build_output_stream::<u16>(device, config, shared),
+            SampleFormat::U32 => Self::build_output_stream::<u32>(device, config, shared),
+            SampleFormat::U64 => Self::build_output_stream::<u64>(device, config, shared),
+            SampleFormat::F32 => Self::build_output_stream::<f32>(device, config, shared),
+            SampleFormat::F64 => Self::build_output_stream::<f64>(device, config, shared),
+            format => Err(DeviceError::UnsupportedSampleFormat(format)),
+        }
+    }
+
+    fn build_output_stream<D>(
+        device: &cpal::Device,
+        config: &StreamConfig,
+        shared: &Arc<StreamShared<T>>,
+    ) -> Result<(cpal::Stream, HeapCons<StreamFailure>), DeviceError>
+    where
+        D: cpal::SizedSample + FromSample<f64> + 'static,
+    {
+        let frames = config
+            .max_buffer_size
+            .map_or(FALLBACK_CALLBACK_FRAMES, |frames| frames.0 as usize);
+        let mut converter = OutputConverter::new(
+            config.sample_format,
+            config.channels as usize,
+            frames.max(1),
+        );
+
+        let data_shared = shared.clone();
+        let (on_error, failures) = Self::error_callback(shared, Direction::Output);
 
         let stream = device
             .build_output_stream(
-                &config.config(),
-                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                    backend.process_commands();
-                    backend.process_block(data);
+                &config.to_cpal(),
+                move |data: &mut [D], _: &cpal::OutputCallbackInfo| {
+                    converter.process(data, |rendered| match data_shared.backend.try_lock() {
+                        Ok(mut backend) => backend.process(rendered),
+                        Err(_) => rendered.fill(T::EQUILIBRIUM),
+                    });
                 },
-                |err| eprintln!("Stream error: {}", err),
+                on_error,
                 None,
             )
-            .expect("failed to build stream");
+            .map_err(DeviceError::Build)?;
+
+        stream.play().map_err(DeviceError::Play)?;
+        Ok((stream, failures))
+    }
+
+    // The error callback of a stream, which can run on the audio thread and
+    // so must not wait for a lock. A lost device is flagged for `check_stream`.
+    fn error_callback(
+        shared: &Arc<StreamShared<T>>,
+        direction: Direction,
+    ) -> (
+        impl FnMut(cpal::StreamError) + Send + 'static,
+        HeapCons<StreamFailure>,
+    ) {
+        let shared = shared.clone();
+        let (mut failure_producer, failures) =
+            HeapRb::<StreamFailure>::new(STREAM_FAILURES).split();
+
+        let on_error = move |err| {
+            let failure = StreamFailure::from(err);
+            if failure == StreamFailure::DeviceLost {
+                shared.lost(direction).store(true, Ordering::Release);
+            }
+            let _ = failure_producer.try_push(failure);
+        };
+        (on_error, failures)
+    }
+
+    /// Rebuilds the output stream if its device was lost, or moves it back to the
+    /// requested device once that is plugged in again while it runs on a fallback.
+    /// Meant to be called regularly, e.g. together with `poll_status`.
+    ///
+    /// The backend keeps its graph, playlists and playhead. If the device has a
+    /// different number of channels the output map is reset. Returns the config
+    /// of the new stream, which is also published as `StreamRestored`.
+    ///
+    /// A lost input device is reopened the same way, on the default input device if
+    /// it's gone, and the new config is published as `InputStreamRestored`.
+    pub fn check_stream(&mut self) -> Result<Option<StreamConfig>, DeviceError> {
+        let lost = self.shared.device_lost.load(Ordering::Acquire);
+        let returned = self.on_fallback && self.stream_request.is_available(Direction::Output);
+
+        let restored = if lost || returned {
+            Some(self.reopen_stream()?)
+        } else {
+            None
+        };
+
+        if self.shared.input_lost.load(Ordering::Acquire) {
+            self.reopen_input()?;
+        }
+        Ok(restored)
+    }
+
+    fn reopen_input(&mut self) -> Result<(), DeviceError> {
+        let Some(request) = self.input_request.clone() else {
+            return Ok(());
+        };
+        self._input_stream = None;
+
+        let (device, config, _) = reopen_config(&request, self._sample_rate, |request| {
+            device::negotiate(request, Direction::Input, float_format::<T>())
+        })?;
+
+        self.shared.input_lost.store(false, Ordering::Release);
+        let (on_error, failures) = Self::error_callback(&self.shared, Direction::Input);
+        let (stream, source) = start_input_stream(&device, &config, self._block_size, on_error)?;
+
+        {
+            let mut backend = self
+                .shared
+                .backend
+                .lock()
+                .expect("the audio thread doesn't panic while holding the backend");
+
+            // the tracks keep their input channels, channels the new device lacks are silent
+            backend.input = Some(Input::new(Box::new(source), self._block_size));
+            backend.publish(AudioEngineStatus::InputStreamRestored(config));
+        }
+
+        self._input_stream = Some(stream);
+        self.input_failures = Some(failures);
+        Ok(())
+    }
+
+    fn reopen_stream(&mut self) -> Result<StreamConfig, DeviceError> {
+        // the old stream can't be used anymore, and on the same device the new one
+        // may fail to open while the old one exists
+        self._stream = None;
+
+        let (device, config, on_fallback) =
+            reopen_config(&self.stream_request, self._sample_rate, |request| {
+                device::negotiate(request, Direction::Output, float_format::<T>())
+            })?;
+
+        {
+            let mut backend = self
+                .shared
+                .backend
+                .lock()
+                .expect("the audio thread doesn't panic while holding the backend");
+
+            let device_channels = NonZeroUsize::new(config.channels as usize)
+                .expect("devices have at least one channel");
+            if device_channels != self.output_map.device_channels() {
+                self.output_map = OutputMap::new(device_channels);
+                backend.set_output_map(self.output_map.clone());
+            }
+            backend.publish(AudioEngineStatus::StreamRestored(config.clone()));
+        }
+
+        self.shared.device_lost.store(false, Ordering::Release);
+        // what the old stream reported is superseded by `StreamRestored`
+        let (stream, failures) = Self::start_stream(&device, &config, &self.shared)?;
+        self._stream = Some(stream);
+        self.stream_failures = Some(failures);
+        self.stream_config = config.clone();
+        self.on_fallback = on_fallback;
+        Ok(config)
+    }
+
+    /// The configuration the output device was opened with
+    pub fn stream_config(&self) -> &StreamConfig {
+        &self.stream_config
+    }
+
+    /// Which channels of the output device master and the cue buses are played on
+    pub fn output_map(&self) -> &OutputMap {
+        &self.output_map
+    }
+
+    /// Replaces the output map. It has to be made for the channels of the device
+    /// and every cue has to be a bus. This isn't part of the history.
+    pub fn set_output_map(&mut self, map: OutputMap) -> Result<(), AudioEngineError> {
+        let device_channels = self.output_map.device_channels();
+        if map.device_channels() != device_channels {
+            return Err(OutputError::DeviceChannels(device_channels).into());
+        }
+        for bus in map.cues() {
+            self.model.validate_bus(bus)?;
+        }
+
+        self.dispatch_edit(AudioBackendCommand::SetOutputMap(map.clone()))?;
+        self.output_map = map;
+        Ok(())
+    }
 
-        stream.play().expect("failed to play stream");
-        stream
+    /// Adds a bus that is played on `channels` of the output device instead of
+    /// the master track, e.g. a headphone mix that tracks are sent to
+    pub fn add_cue(&mut self, channels: OutputChannels) -> Result<NodeIndex, AudioEngineError> {
+        // the bus gets the next index
+        let mut map = self.output_map.clone();
+        map.add_route(
+            OutputSource::Cue(NodeIndex::new(self.model.tracks().count())),
+            channels,
+        )?;
+
+        self.history.begin();
+        let bus = self.add_bus().and_then(|bus| {
+            for edge in self.model.outputs(bus) {
+                self.remove_connection(edge)?;
+            }
+            Ok(bus)
+        });
+        self.history.end();
+        let bus = bus?;
+
+        self.set_output_map(map)?;
+        Ok(bus)
     }
 
     fn next_message_id(&mut self) -> MessageId {
@@ -90,33 +483,1966 @@ where
         MessageId(id)
     }
 
-    fn new_message(&mut self, command: AudioBackendCommand) -> AudioBackendMessage {
+    fn new_message(&mut self, command: AudioBackendCommand<T>) -> AudioBackendMessage<T> {
         AudioBackendMessage {
             id: self.next_message_id(),
             command,
         }
     }
 
-    pub fn dispatch_command(
+    /// Sends a command to the backend without waiting for it to be processed.
+    /// The reply can later be matched against the returned id.
+    ///
+    /// Only the transport commands are sent this way, edits go through `dispatch_edit`
+    /// so the model isn't updated before the backend accepted them.
+    pub(crate) fn dispatch_command(
         &mut self,
-        command: AudioBackendCommand,
-    ) -> Result<(), AudioEngineError> {
+        command: AudioBackendCommand<T>,
+    ) -> Result<MessageId, AudioEngineError> {
+        // the backend stops taking commands while their replies aren't read
+        while let Some(message) = self.ack_consumer.try_pop() {
+            self.keep_ack(message);
+        }
+
         let message = self.new_message(command);
+        let id = message.id;
 
         self.command_producer
             .try_push(message)
             .map_err(|_| AudioEngineError::QueueFull)?;
 
+        Ok(id)
+    }
+
+    /// Sends an edit to the backend and returns its reply once it was processed,
+    /// so the model is only updated with edits the backend accepted.
+    ///
+    /// The audio thread processes the commands at the start of each block. If no stream
+    /// is running, e.g. because its device was lost, the engine processes them itself.
+    fn dispatch_edit(
+        &mut self,
+        command: AudioBackendCommand<T>,
+    ) -> Result<AudioEngineStatus, AudioEngineError> {
+        let id = self.dispatch_command(command)?;
+        self.wait_or_process(id, EDIT_TIMEOUT)
+    }
+
+    /// Sends a transport command through one of the methods returning its id and
+    /// blocks until the backend replied to it, e.g.
+    /// `engine.dispatch_and_wait(AudioEngine::play, timeout)`.
+    /// Like edits, the command is processed by the engine itself if no stream is running.
+    pub fn dispatch_and_wait(
+        &mut self,
+        dispatch: impl FnOnce(&mut Self) -> Result<MessageId, AudioEngineError>,
+        timeout: Duration,
+    ) -> Result<AudioEngineStatus, AudioEngineError> {
+        let id = dispatch(self)?;
+        self.wait_or_process(id, timeout)
+    }
+
+    // waits for the reply while the audio thread processes the commands
+    fn wait_or_process(
+        &mut self,
+        id: MessageId,
+        timeout: Duration,
+    ) -> Result<AudioEngineStatus, AudioEngineError> {
+        let rendering = self._stream.is_some() && !self.shared.device_lost.load(Ordering::Acquire);
+        let timeout = if rendering { timeout } else { Duration::ZERO };
+
+        match self.wait_for_reply(id, timeout) {
+            Err(AudioEngineError::Timeout(_)) => {
+                self.shared
+                    .backend
+                    .lock()
+                    .expect("the audio thread doesn't panic while holding the backend")
+                    .process_commands();
+                self.wait_for_reply(id, Duration::ZERO)
+            }
+            result => result,
+        }
+    }
+
+    /// Blocks until the backend replied to the command `id` was returned for,
+    /// e.g. by `play`. A command the backend rejected is returned as `Rejected`.
+    /// Replies to other commands stay available through `poll_status`.
+    pub fn wait_for_reply(
+        &mut self,
+        id: MessageId,
+        timeout: Duration,
+    ) -> Result<AudioEngineStatus, AudioEngineError> {
+        let deadline = Instant::now() + timeout;
+
+        // the reply may have been put aside while another command was sent
+        let mut reply = self
+            .pending_acks
+            .iter()
+            .position(|message| message.id == Some(id))
+            .and_then(|index| self.pending_acks.remove(index));
+
+        loop {
+            while reply.is_none()
+                && let Some(message) = self.ack_consumer.try_pop()
+            {
+                if message.id == Some(id) {
+                    reply = Some(message);
+                } else {
+                    self.keep_ack(message);
+                }
+            }
+
+            if let Some(message) = reply {
+                return if message.status.is_error() {
+                    Err(AudioEngineError::Rejected(message.status))
+                } else {
+                    Ok(message.status)
+                };
+            }
+
+            if Instant::now() >= deadline {
+                return Err(AudioEngineError::Timeout(id));
+            }
+
+            std::thread::sleep(Duration::from_micros(500));
+        }
+    }
+
+    // Only the latest replies are kept for `poll_status`, most of them belong
+    // to edits whose outcome the model already knows
+    fn keep_ack(&mut self, message: AudioEngineMessage) {
+        if self.pending_acks.len() == PENDING_ACKS {
+            self.pending_acks.pop_front();
+        }
+        self.pending_acks.push_back(message);
+    }
+
+    /// Returns the next message published by the backend, if there is one.
+    /// Errors of the streams come first, then replies to commands.
+    pub fn poll_status(&mut self) -> Option<AudioEngineMessage> {
+        let message = self
+            .stream_failures
+            .as_mut()
+            .and_then(|failures| failures.try_pop())
+            .map(stream_error)
+            .or_else(|| {
+                self.input_failures
+                    .as_mut()
+                    .and_then(|failures| failures.try_pop())
+                    .map(input_stream_error)
+            })
+            .or_else(|| self.pending_acks.pop_front())
+            .or_else(|| self.ack_consumer.try_pop())
+            .or_else(|| self.status_consumer.try_pop());
+
+        if let Some(AudioEngineStatus::Transport(transport)) = message.as_ref().map(|m| &m.status) {
+            self.transport = Some(*transport);
+        }
+        message
+    }
+
+    /// Drains all messages the backend has published so far.
+    pub fn statuses(&mut self) -> impl Iterator<Item = AudioEngineMessage> + '_ {
+        let transport = &mut self.transport;
+
+        self.stream_failures
+            .iter_mut()
+            .flat_map(|failures| failures.pop_iter())
+            .map(stream_error)
+            .chain(
+                self.input_failures
+                    .iter_mut()
+                    .flat_map(|failures| failures.pop_iter())
+                    .map(input_stream_error),
+            )
+            .chain(self.pending_acks.drain(..))
+            .chain(self.ack_consumer.pop_iter())
+            .chain(self.status_consumer.pop_iter())
+            .inspect(move |message| {
+                if let AudioEngineStatus::Transport(status) = message.status {
+                    *transport = Some(status);
+                }
+            })
+    }
+
+    /// The latest transport state that was received from the backend
+    pub fn transport(&self) -> Option<TransportStatus> {
+        self.transport
+    }
+
+    /// Starts playback. The transport commands aren't part of the model, the reply
+    /// to each of them can be waited for with `dispatch_and_wait` or `wait_for_reply`,
+    /// or matched against the returned id in `poll_status`.
+    pub fn play(&mut self) -> Result<MessageId, AudioEngineError> {
+        self.dispatch_command(AudioBackendCommand::Start)
+    }
+
+    /// Stops playback and releases the notes that are still sounding
+    pub fn pause(&mut self) -> Result<MessageId, AudioEngineError> {
+        self.finish_automation_passes()?;
+        self.dispatch_command(AudioBackendCommand::Pause)
+    }
+
+    /// Finishes the automation passes before the playhead moves
+    pub fn set_playhead(&mut self, playhead: MusicalTime) -> Result<MessageId, AudioEngineError> {
+        self.finish_automation_passes()?;
+        self.dispatch_command(AudioBackendCommand::SetPlayhead(playhead))
+    }
+
+    /// The backend rejects regions shorter than a block with `InvalidRange`
+    pub fn set_loop(&mut self, range: Range<MusicalTime>) -> Result<MessageId, AudioEngineError> {
+        self.dispatch_command(AudioBackendCommand::SetLoop(range))
+    }
+
+    pub fn enable_loop(&mut self, enabled: bool) -> Result<MessageId, AudioEngineError> {
+        self.dispatch_command(AudioBackendCommand::EnableLoop(enabled))
+    }
+
+    /// The backend rejects ranges that don't start before they end with `InvalidRange`
+    pub fn set_punch(&mut self, range: Range<MusicalTime>) -> Result<MessageId, AudioEngineError> {
+        self.dispatch_command(AudioBackendCommand::SetPunch(range))
+    }
+
+    pub fn enable_punch(&mut self, enabled: bool) -> Result<MessageId, AudioEngineError> {
+        self.dispatch_command(AudioBackendCommand::EnablePunch(enabled))
+    }
+
+    /// The control-side mirror of the backend's tracks, routing and playlists.
+    pub fn model(&self) -> &EngineModel<T> {
+        &self.model
+    }
+
+    pub fn history(&self) -> &History<T> {
+        &self.history
+    }
+
+    /// Groups all following edits until the matching `end_transaction`
+    /// so they are undone and redone together.
+    pub fn begin_transaction(&mut self) {
+        self.history.begin();
+    }
+
+    pub fn end_transaction(&mut self) {
+        self.history.end();
+    }
+
+    /// Reverts the latest transaction. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self) -> Result<bool, AudioEngineError> {
+        self.finish_automation_passes()?;
+
+        let Some(transaction) = self.history.pop_undo() else {
+            return Ok(false);
+        };
+
+        if let Err(e) = self.check_revert(&transaction) {
+            self.history.push_undo(transaction);
+            return Err(e);
+        }
+
+        let redo = self.revert(transaction)?;
+        self.history.push_redo(redo);
+        Ok(true)
+    }
+
+    /// Re-applies the latest undone transaction. Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self) -> Result<bool, AudioEngineError> {
+        self.finish_automation_passes()?;
+
+        let Some(transaction) = self.history.pop_redo() else {
+            return Ok(false);
+        };
+
+        if let Err(e) = self.check_revert(&transaction) {
+            self.history.push_redo(transaction);
+            return Err(e);
+        }
+
+        let undo = self.revert(transaction)?;
+        self.history.push_undo(undo);
+        Ok(true)
+    }
+
+    // Catches what would make a transaction fail halfway before any of it is applied
+    fn check_revert(&self, transaction: &Transaction<T>) -> Result<(), AudioEngineError> {
+        if self.command_producer.vacant_len() < transaction.len() {
+            return Err(AudioEngineError::QueueFull);
+        }
+
+        for edit in transaction.edits() {
+            if let Edit::RemoveTrack(track) = *edit
+                && self
+                    .model
+                    .track(track)
+                    .is_some_and(|model| model.has_processor_edits())
+            {
+                return Err(ModelError::ProcessorsNotRecorded(track).into());
+            }
+        }
         Ok(())
     }
 
-    pub fn load_audio_file(
+    fn revert(&mut self, transaction: Transaction<T>) -> Result<Transaction<T>, AudioEngineError> {
+        let mut inverses = Vec::with_capacity(transaction.len());
+        for edit in transaction.into_edits() {
+            match self.apply(edit) {
+                Ok(inverse) => inverses.push(inverse),
+                Err(e) => {
+                    // the model no longer matches what the history expects
+                    self.history.clear();
+                    return Err(e);
+                }
+            }
+        }
+
+        inverses.reverse();
+        Ok(Transaction::new(inverses))
+    }
+
+    /// Applies an edit without recording it and returns the edit that reverts it.
+    fn apply(&mut self, edit: Edit<T>) -> Result<Edit<T>, AudioEngineError> {
+        Ok(match edit {
+            Edit::AddTrack(output) => {
+                Edit::RemoveTrack(self.apply_add_track(TrackKind::Audio, output)?)
+            }
+            Edit::AddBus(output) => {
+                Edit::RemoveTrack(self.apply_add_track(TrackKind::Bus, output)?)
+            }
+            Edit::RemoveTrack(track) => match self.apply_remove_track(track)? {
+                (TrackKind::Audio, output) => Edit::AddTrack(output),
+                (TrackKind::Bus, output) => Edit::AddBus(output),
+            },
+            Edit::AddConnection {
+                id,
+                source,
+                destination,
+                connection,
+            } => {
+                let edge = self.apply_add_connection(source, destination, connection)?;
+                self.model.restore_connection_id(edge, id);
+                Edit::RemoveConnection(id)
+            }
+            Edit::RemoveConnection(id) => {
+                let (source, destination, connection) =
+                    self.apply_remove_connection(self.connection_edge(id)?)?;
+                Edit::AddConnection {
+                    id,
+                    source,
+                    destination,
+                    connection,
+                }
+            }
+            Edit::UpdateConnection { id, matrix } => Edit::UpdateConnection {
+                id,
+                matrix: self.apply_update_connection(self.connection_edge(id)?, matrix)?,
+            },
+            Edit::SetSend { id, tap, gain } => {
+                let (tap, gain) = self.apply_set_send(self.connection_edge(id)?, tap, gain)?;
+                Edit::SetSend { id, tap, gain }
+            }
+            Edit::InsertClip { track, range, clip } => {
+                self.apply_insert_clip(track, range.clone(), clip)?;
+                Edit::RemoveClip { track, range }
+            }
+            Edit::RemoveClip { track, range } => Edit::InsertClip {
+                clip: self.apply_remove_clip(track, range.clone())?,
+                track,
+                range,
+            },
+            Edit::InsertMidiClip { track, range, clip } => {
+                self.apply_insert_midi_clip(track, range.clone(), clip)?;
+                Edit::RemoveMidiClip { track, range }
+            }
+            Edit::RemoveMidiClip { track, range } => Edit::InsertMidiClip {
+                clip: self.apply_remove_midi_clip(track, range.clone())?,
+                track,
+                range,
+            },
+            Edit::SetMixerControl { track, control } => Edit::SetMixerControl {
+                track,
+                control: self.apply_mixer_control(track, control)?,
+            },
+            Edit::SetParameter {
+                track,
+                processor,
+                parameter,
+                value,
+            } => Edit::SetParameter {
+                track,
+                processor,
+                parameter,
+                value: self.apply_parameter(track, processor, parameter, value)?.0,
+            },
+            Edit::SetAutomationLane {
+                track,
+                processor,
+                parameter,
+                lane,
+            } => Edit::SetAutomationLane {
+                track,
+                processor,
+                parameter,
+                lane: self.apply_automation_lane(track, processor, parameter, lane)?,
+            },
+        })
+    }
+
+    /// Adds a stereo track that is routed to the master track.
+    /// Returns the index the backend gave the track.
+    pub fn add_track(&mut self) -> Result<NodeIndex, AudioEngineError> {
+        let track = self.apply_add_track(TrackKind::Audio, None)?;
+        self.history.record(Edit::RemoveTrack(track));
+        Ok(track)
+    }
+
+    /// Adds a stereo bus that is routed to the master track. Buses have no
+    /// playlist, they process the sum of the tracks that are sent or routed to them.
+    pub fn add_bus(&mut self) -> Result<NodeIndex, AudioEngineError> {
+        let bus = self.apply_add_track(TrackKind::Bus, None)?;
+        self.history.record(Edit::RemoveTrack(bus));
+        Ok(bus)
+    }
+
+    pub fn add_connection(
         &mut self,
-        path: impl AsRef<Path>,
-    ) -> Result<Arc<InterleavedBuffer<T>>, LoadError>
-    where
-        T: ConvertibleSample,
-    {
-        audio_buffer::loader::load(path).map(|buffer| Arc::new(buffer))
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    ) -> Result<EdgeIndex, AudioEngineError> {
+        let edge = self.apply_add_connection(source, destination, Connection::new(matrix))?;
+        self.history
+            .record(Edit::RemoveConnection(self.connection_id(edge)?));
+        Ok(edge)
+    }
+
+    /// Sends a track to a bus in addition to its output, with the
+    /// linear `gain` of the send. Sends are removed with `remove_connection`.
+    pub fn add_send(
+        &mut self,
+        track: NodeIndex,
+        bus: NodeIndex,
+        tap: SendTap,
+        gain: f32,
+    ) -> Result<EdgeIndex, AudioEngineError> {
+        self.model.validate_send(track, bus, gain)?;
+        let connection = Connection::new(PinMatrix::diagonal(2, 2))
+            .with_tap(tap.tap())
+            .with_gain(gain);
+
+        let edge = self.apply_add_connection(track, bus, connection)?;
+        self.history
+            .record(Edit::RemoveConnection(self.connection_id(edge)?));
+        Ok(edge)
+    }
+
+    /// Changes the tap and gain of a send, returning the old ones
+    pub fn set_send(
+        &mut self,
+        edge: EdgeIndex,
+        tap: SendTap,
+        gain: f32,
+    ) -> Result<(SendTap, f32), AudioEngineError> {
+        let id = self.connection_id(edge)?;
+        let (old_tap, old_gain) = self.apply_set_send(edge, tap, gain)?;
+        self.history.record(Edit::SetSend {
+            id,
+            tap: old_tap,
+            gain: old_gain,
+        });
+        Ok((old_tap, old_gain))
+    }
+
+    pub fn remove_connection(&mut self, edge: EdgeIndex) -> Result<(), AudioEngineError> {
+        let id = self.connection_id(edge)?;
+        let (source, destination, connection) = self.apply_remove_connection(edge)?;
+        self.history.record(Edit::AddConnection {
+            id,
+            source,
+            destination,
+            connection,
+        });
+        Ok(())
+    }
+
+    /// Routes the output of a track to a bus, e.g. a group, or back to the master
+    /// track. Replaces all of the track's post-fader connections but keeps its
+    /// pre-fader sends. The change is undone as a whole.
+    pub fn route_track(
+        &mut self,
+        track: NodeIndex,
+        destination: NodeIndex,
+    ) -> Result<EdgeIndex, AudioEngineError> {
+        self.model.validate_route(track, destination)?;
+
+        self.history.begin();
+        let result = self.route_outputs(track, destination);
+        self.history.end();
+        result
+    }
+
+    fn route_outputs(
+        &mut self,
+        track: NodeIndex,
+        destination: NodeIndex,
+    ) -> Result<EdgeIndex, AudioEngineError> {
+        for edge in self.model.outputs(track) {
+            self.remove_connection(edge)?;
+        }
+        self.add_connection(track, destination, PinMatrix::diagonal(2, 2))
+    }
+
+    /// Replaces the matrix of a connection between two tracks, returning the old one.
+    pub fn update_connection(
+        &mut self,
+        edge: EdgeIndex,
+        matrix: PinMatrix,
+    ) -> Result<PinMatrix, AudioEngineError> {
+        let id = self.connection_id(edge)?;
+        let old = self.apply_update_connection(edge, matrix)?;
+        self.history.record(Edit::UpdateConnection {
+            id,
+            matrix: old.clone(),
+        });
+        Ok(old)
+    }
+
+    /// Sets a control of the mixer strip of a track.
+    /// Returns the previous value of the control.
+    pub fn set_mixer_control(
+        &mut self,
+        track: NodeIndex,
+        control: MixerControl,
+    ) -> Result<MixerControl, AudioEngineError> {
+        let old = self.apply_mixer_control(track, control)?;
+        self.history.record(Edit::SetMixerControl {
+            track,
+            control: old,
+        });
+        Ok(old)
+    }
+
+    /// Sets the linear fader gain of a track
+    pub fn set_track_gain(&mut self, track: NodeIndex, gain: f32) -> Result<(), AudioEngineError> {
+        self.set_mixer_control(track, MixerControl::Gain(gain))
+            .map(|_| ())
+    }
+
+    /// Sets the pan position of a track from `-1.0` (left) to `1.0` (right)
+    pub fn set_track_pan(&mut self, track: NodeIndex, pan: f32) -> Result<(), AudioEngineError> {
+        self.set_mixer_control(track, MixerControl::Pan(pan))
+            .map(|_| ())
+    }
+
+    pub fn set_pan_law(&mut self, track: NodeIndex, law: PanLaw) -> Result<(), AudioEngineError> {
+        self.set_mixer_control(track, MixerControl::PanLaw(law))
+            .map(|_| ())
+    }
+
+    pub fn set_track_mute(&mut self, track: NodeIndex, mute: bool) -> Result<(), AudioEngineError> {
+        self.set_mixer_control(track, MixerControl::Mute(mute))
+            .map(|_| ())
+    }
+
+    pub fn set_track_solo(&mut self, track: NodeIndex, solo: bool) -> Result<(), AudioEngineError> {
+        self.set_mixer_control(track, MixerControl::Solo(solo))
+            .map(|_| ())
+    }
+
+    /// Processors can't be recreated once they were sent to the backend, so adding
+    /// them isn't recorded in the history and the track can't be removed by an undo
+    /// anymore. Undoing its creation fails with `ModelError::ProcessorsNotRecorded`.
+    pub fn add_processor(
+        &mut self,
+        track: NodeIndex,
+        processor: Box<dyn AudioProcessor<T>>,
+    ) -> Result<NodeIndex, AudioEngineError> {
+        self.model.validate_track(track)?;
+
+        let parameters = processor.parameters().to_vec();
+        let values = parameters
+            .iter()
+            .map(|descriptor| {
+                processor
+                    .get_parameter(descriptor.id)
+                    .unwrap_or(descriptor.default)
+            })
+            .collect();
+        let model = ProcessorModel::with_parameters(processor.config(), parameters, values);
+
+        let status = self.dispatch_edit(AudioBackendCommand::AddProcessor { track, processor })?;
+        let AudioEngineStatus::ProcessorAdded { processor, .. } = status else {
+            unreachable!("the backend answers AddProcessor with ProcessorAdded");
+        };
+
+        let added = self.model.add_processor(track, model);
+        debug_assert_eq!(
+            added, processor,
+            "the model adds processors like the backend"
+        );
+        Ok(processor)
+    }
+
+    /// Sets a parameter of a processor, clamping the value to the parameter's range.
+    /// Returns the previous value.
+    ///
+    /// While the transport is running, changes to a parameter with a lane in a
+    /// recording mode are written to the lane once the pass is finished. They are
+    /// written at the position of the block the backend applied them in.
+    pub fn set_parameter(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    ) -> Result<f32, AudioEngineError> {
+        let (old, transport) = self.apply_parameter(track, processor, parameter, value)?;
+
+        // the lane edit of the pass reverts written changes as a whole
+        if !self.write_automation(track, processor, parameter, transport)? {
+            self.history.record(Edit::SetParameter {
+                track,
+                processor,
+                parameter,
+                value: old,
+            });
+        }
+        Ok(old)
+    }
+
+    pub fn get_parameter(
+        &self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Option<f32> {
+        self.model
+            .track(track)?
+            .processor(processor)?
+            .parameter(parameter)
+    }
+
+    /// Inserts an automation lane, replacing the lane of the same parameter.
+    /// Returns the previous lane.
+    pub fn set_automation_lane(
+        &mut self,
+        track: NodeIndex,
+        lane: AutomationLane,
+    ) -> Result<Option<AutomationLane>, AudioEngineError> {
+        let (processor, parameter) = (lane.processor(), lane.parameter());
+        self.finish_automation_pass(track, processor, parameter)?;
+
+        let old = self.apply_automation_lane(track, processor, parameter, Some(lane))?;
+        self.history.record(Edit::SetAutomationLane {
+            track,
+            processor,
+            parameter,
+            lane: old.clone(),
+        });
+        Ok(old)
+    }
+
+    pub fn remove_automation_lane(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Result<Option<AutomationLane>, AudioEngineError> {
+        self.finish_automation_pass(track, processor, parameter)?;
+
+        let old = self.apply_automation_lane(track, processor, parameter, None)?;
+        self.history.record(Edit::SetAutomationLane {
+            track,
+            processor,
+            parameter,
+            lane: old.clone(),
+        });
+        Ok(old)
+    }
+
+    /// Changes the mode of a lane, creating an empty lane if the parameter doesn't have one
+    pub fn set_automation_mode(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        mode: AutomationMode,
+    ) -> Result<(), AudioEngineError> {
+        self.model
+            .validate_automation_lane(track, processor, parameter)?;
+
+        let mut lane = self
+            .model
+            .track(track)
+            .and_then(|model| model.automation_lane(processor, parameter))
+            .cloned()
+            .unwrap_or_else(|| AutomationLane::new(processor, parameter));
+        lane.set_mode(mode);
+        lane.recording = false;
+
+        self.set_automation_lane(track, lane).map(|_| ())
+    }
+
+    /// Starts writing a parameter whose lane is in `Touch` or `Latch` mode,
+    /// e.g. when its control is grabbed. Does nothing while the transport is stopped.
+    pub fn begin_touch(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Result<(), AudioEngineError> {
+        let Some(value) = self.get_parameter(track, processor, parameter) else {
+            return Err(ModelError::InvalidParameter(parameter).into());
+        };
+
+        if self.automation_pass(track, processor, parameter).is_some() {
+            return Ok(());
+        }
+        let Some(playhead) = self.transport.filter(|t| t.running).map(|t| t.playhead) else {
+            return Ok(());
+        };
+
+        let touchable = self
+            .model
+            .track(track)
+            .and_then(|model| model.automation_lane(processor, parameter))
+            .is_some_and(|lane| {
+                matches!(lane.mode(), AutomationMode::Touch | AutomationMode::Latch)
+            });
+        if touchable {
+            self.begin_automation_pass(track, processor, parameter, value, playhead)?;
+        }
+        Ok(())
+    }
+
+    /// Releases a touched parameter. In `Touch` mode this finishes the pass
+    /// and the lane takes over again, `Latch` keeps writing the last value.
+    pub fn end_touch(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Result<(), AudioEngineError> {
+        let touch = self
+            .model
+            .track(track)
+            .and_then(|model| model.automation_lane(processor, parameter))
+            .is_some_and(|lane| lane.mode() == AutomationMode::Touch);
+        if touch {
+            self.finish_automation_pass(track, processor, parameter)?;
+        }
+        Ok(())
+    }
+
+    /// Writes all recorded changes to their lanes. Stopping the transport or
+    /// moving the playhead does this as well.
+    pub fn finish_automation_passes(&mut self) -> Result<(), AudioEngineError> {
+        self.history.begin();
+        let mut result = Ok(());
+        while let Some(pass) = self.passes.first() {
+            let (track, processor, parameter) = (pass.track, pass.processor, pass.parameter);
+            result = self.finish_automation_pass(track, processor, parameter);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.history.end();
+        result
+    }
+
+    // adds a point to the pass of a parameter, starting one if the mode allows it.
+    // returns whether the change was written
+    fn write_automation(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        transport: TransportStatus,
+    ) -> Result<bool, AudioEngineError> {
+        if !transport.running {
+            return Ok(false);
+        }
+        let playhead = transport.playhead;
+        let value = self
+            .get_parameter(track, processor, parameter)
+            .expect("parameter was validated");
+
+        if let Some(pass) = self.automation_pass(track, processor, parameter) {
+            pass.points.push(Breakpoint::new(playhead, value));
+            return Ok(true);
+        }
+
+        let mode = self
+            .model
+            .track(track)
+            .and_then(|model| model.automation_lane(processor, parameter))
+            .map(|lane| lane.mode());
+        match mode {
+            Some(AutomationMode::Write | AutomationMode::Latch) => {
+                self.begin_automation_pass(track, processor, parameter, value, playhead)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // stops the lane from driving the parameter and starts collecting points
+    fn begin_automation_pass(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+        playhead: MusicalTime,
+    ) -> Result<(), AudioEngineError> {
+        let Some(original) = self
+            .model
+            .track(track)
+            .and_then(|model| model.automation_lane(processor, parameter))
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        let mut recording = original.clone();
+        recording.recording = true;
+        self.apply_automation_lane(track, processor, parameter, Some(recording))?;
+
+        self.passes.push(AutomationPass {
+            track,
+            processor,
+            parameter,
+            original,
+            points: vec![Breakpoint::new(playhead, value)],
+        });
+        Ok(())
+    }
+
+    // replaces the lane inside of the written range with the recorded points
+    fn finish_automation_pass(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Result<(), AudioEngineError> {
+        let Some(index) = self.passes.iter().position(|pass| {
+            pass.track == track && pass.processor == processor && pass.parameter == parameter
+        }) else {
+            return Ok(());
+        };
+        let mut pass = self.passes.remove(index);
+
+        // the value is held until the pass is finished. The playhead was
+        // polled earlier than the backend applied the last change, so it may lag
+        if let (Some(last), Some(transport)) = (pass.points.last().copied(), self.transport)
+            && transport.playhead > last.time
+        {
+            pass.points
+                .push(Breakpoint::new(transport.playhead, last.value));
+        }
+
+        let mut lane = pass.original.clone();
+        let start = pass.points.iter().map(|point| point.time).min();
+        let end = pass.points.iter().map(|point| point.time).max();
+        if let (Some(start), Some(end)) = (start, end) {
+            lane.clear_range(start..end);
+        }
+        for point in pass.points {
+            lane.insert(point);
+        }
+
+        self.apply_automation_lane(track, processor, parameter, Some(lane))?;
+        self.history.record(Edit::SetAutomationLane {
+            track,
+            processor,
+            parameter,
+            lane: Some(pass.original),
+        });
+        Ok(())
+    }
+
+    fn automation_pass(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Option<&mut AutomationPass> {
+        self.passes.iter_mut().find(|pass| {
+            pass.track == track && pass.processor == processor && pass.parameter == parameter
+        })
+    }
+
+    /// Like adding processors this isn't recorded in the history, see `add_processor`
+    pub fn add_processor_connection(
+        &mut self,
+        track: NodeIndex,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    ) -> Result<EdgeIndex, AudioEngineError> {
+        self.model
+            .validate_processor_connection(track, source, destination, &matrix)?;
+        let status = self.dispatch_edit(AudioBackendCommand::AddProcessorConnection {
+            track,
+            source,
+            destination,
+            matrix: matrix.clone(),
+        })?;
+        let AudioEngineStatus::ProcessorConnectionAdded { edge, .. } = status else {
+            unreachable!(
+                "the backend answers AddProcessorConnection with ProcessorConnectionAdded"
+            );
+        };
+
+        let added = self
+            .model
+            .add_processor_connection(track, source, destination, matrix);
+        debug_assert_eq!(added, edge, "the model adds connections like the backend");
+        Ok(edge)
+    }
+
+    /// Isn't recorded in the history, see `add_processor`
+    pub fn remove_processor_connection(
+        &mut self,
+        track: NodeIndex,
+        edge: EdgeIndex,
+    ) -> Result<(), AudioEngineError> {
+        self.model.validate_processor_edge(track, edge)?;
+        self.dispatch_edit(AudioBackendCommand::RemoveProcessorConnection { track, edge })?;
+
+        self.model.remove_processor_connection(track, edge);
+        Ok(())
+    }
+
+    /// Inserts a clip into the playlist of a track.
+    /// Returns the clip that previously occupied the same range.
+    pub fn insert_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    ) -> Result<Option<Clip<T>>, AudioEngineError> {
+        self.model.validate_clip_insert(track, &range)?;
+
+        self.history.begin();
+        let result = self.replace_clip(track, range, clip);
+        self.history.end();
+        result
+    }
+
+    pub fn remove_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> Result<Clip<T>, AudioEngineError> {
+        let clip = self.apply_remove_clip(track, range.clone())?;
+        self.history.record(Edit::InsertClip {
+            track,
+            range,
+            clip: clip.clone(),
+        });
+        Ok(clip)
+    }
+
+    /// Moves a clip so it starts at `start`, keeping its length.
+    /// Returns the new range of the clip.
+    pub fn move_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        start: MusicalTime,
+    ) -> Result<Range<MusicalTime>, AudioEngineError> {
+        self.model.validate_clip_removal(track, &range)?;
+        let length = range.end.checked_sub(range.start).unwrap_or_default();
+        let new_range = start..start + length;
+
+        self.history.begin();
+        let result = self
+            .remove_clip(track, range)
+            .and_then(|clip| self.replace_clip(track, new_range.clone(), clip));
+        self.history.end();
+
+        result.map(|_| new_range)
+    }
+
+    /// Changes the range of a clip while the material keeps its position on the timeline,
+    /// moving the start of the range forward cuts off the beginning of the clip.
+    pub fn trim_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        new_range: Range<MusicalTime>,
+    ) -> Result<(), AudioEngineError> {
+        self.model.validate_clip_removal(track, &range)?;
+        self.model.validate_clip_insert(track, &new_range)?;
+
+        let trimmed = self
+            .model
+            .track(track)
+            .and_then(|model| model.playlist().get(range.clone()))
+            .and_then(|clip| clip.trimmed(&range, &new_range))
+            .ok_or(ModelError::InvalidRange(new_range.clone()))?;
+
+        self.history.begin();
+        let result = self
+            .remove_clip(track, range)
+            .and_then(|_| self.replace_clip(track, new_range, trimmed));
+        self.history.end();
+
+        result.map(|_| ())
+    }
+
+    /// Inserts a MIDI clip into the playlist of a track.
+    /// Returns the MIDI clip that previously occupied the same range.
+    pub fn insert_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    ) -> Result<Option<MidiClip>, AudioEngineError> {
+        self.model.validate_clip_insert(track, &range)?;
+
+        self.history.begin();
+        let result = self.replace_midi_clip(track, range, clip);
+        self.history.end();
+        result
+    }
+
+    pub fn remove_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> Result<MidiClip, AudioEngineError> {
+        let clip = self.apply_remove_midi_clip(track, range.clone())?;
+        self.history.record(Edit::InsertMidiClip {
+            track,
+            range,
+            clip: clip.clone(),
+        });
+        Ok(clip)
+    }
+
+    // inserts a clip and records it, removing the clip at the same range first
+    fn replace_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    ) -> Result<Option<Clip<T>>, AudioEngineError> {
+        let occupied = self
+            .model
+            .track(track)
+            .and_then(|model| model.playlist().get(range.clone()))
+            .is_some();
+
+        let replaced = if occupied {
+            Some(self.remove_clip(track, range.clone())?)
+        } else {
+            None
+        };
+
+        self.apply_insert_clip(track, range.clone(), clip)?;
+        self.history.record(Edit::RemoveClip { track, range });
+        Ok(replaced)
+    }
+
+    fn replace_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    ) -> Result<Option<MidiClip>, AudioEngineError> {
+        let occupied = self
+            .model
+            .track(track)
+            .and_then(|model| model.playlist().get_midi(range.clone()))
+            .is_some();
+
+        let replaced = if occupied {
+            Some(self.remove_midi_clip(track, range.clone())?)
+        } else {
+            None
+        };
+
+        self.apply_insert_midi_clip(track, range.clone(), clip)?;
+        self.history.record(Edit::RemoveMidiClip { track, range });
+        Ok(replaced)
+    }
+
+    /// Opens an input stream on the device called `device`, or on the default input
+    /// device of the output's host, and makes it the source tracks record and monitor.
+    /// Returns the configuration the input device was opened with.
+    pub fn open_input(
+        &mut self,
+        device: Option<&str>,
+        channels: Option<u16>,
+    ) -> Result<StreamConfig, InputError> {
+        let mut request = StreamRequest::new()
+            .with_host(self.stream_config.host)
+            .with_sample_rate(self._sample_rate);
+        if let Some(device) = device {
+            request = request.with_device(device);
+        }
+        if let Some(channels) = channels {
+            request = request.with_channels(channels);
+        }
+
+        self.shared.input_lost.store(false, Ordering::Release);
+        let (on_error, failures) = Self::error_callback(&self.shared, Direction::Input);
+        let (stream, source, config) = open_input_stream(&request, self._block_size, on_error)?;
+        self.set_input_source(Box::new(source))
+            .map_err(InputError::Engine)?;
+        self._input_stream = Some(stream);
+        self.input_request = Some(request);
+        self.input_failures = Some(failures);
+        Ok(config)
+    }
+
+    /// Replaces the input, e.g. with a `FileInput` to record without a device
+    pub fn set_input_source(
+        &mut self,
+        source: Box<dyn InputSource<T>>,
+    ) -> Result<(), AudioEngineError> {
+        let input = Input::new(source, self._block_size);
+        self.dispatch_edit(AudioBackendCommand::SetInput(Some(input)))?;
+        self._input_stream = None;
+        self.input_request = None;
+        self.input_failures = None;
+        Ok(())
+    }
+
+    /// Assigns channels of the input to a track, `None` disconnects it
+    pub fn set_track_input(
+        &mut self,
+        track: NodeIndex,
+        input: Option<InputChannels>,
+    ) -> Result<(), AudioEngineError> {
+        self.model.validate_audio_track(track)?;
+        self.dispatch_edit(AudioBackendCommand::SetTrackInput { track, input })?;
+
+        self.model.set_input_channels(track, input);
+        Ok(())
+    }
+
+    /// Plays the input of a track through its processors, also while the transport is stopped
+    pub fn set_track_monitoring(
+        &mut self,
+        track: NodeIndex,
+        monitoring: bool,
+    ) -> Result<(), AudioEngineError> {
+        self.model.validate_audio_track(track)?;
+        self.dispatch_edit(AudioBackendCommand::SetTrackMonitoring { track, monitoring })?;
+
+        self.model.set_monitoring(track, monitoring);
+        Ok(())
+    }
+
+    /// Armed tracks record their input once recording is started
+    pub fn arm_track(&mut self, track: NodeIndex, armed: bool) -> Result<(), AudioEngineError> {
+        self.model.validate_audio_track(track)?;
+        self.model.set_armed(track, armed);
+        Ok(())
+    }
+
+    /// Starts a take on every armed track, written to a file in `directory`.
+    /// Only the part inside of the punch region is recorded if punching is enabled.
+    pub fn start_recording(&mut self, directory: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let tracks = self
+            .model
+            .validate_recording()
+            .map_err(AudioEngineError::from)?;
+
+        let mut takes = Vec::with_capacity(tracks.len());
+        let mut writers = Vec::with_capacity(tracks.len());
+        for track in tracks {
+            let path = directory.as_ref().join(format!(
+                "track-{}-take-{}.wav",
+                track.index(),
+                self.next_take
+            ));
+            let (writer, take) = DiskWriter::spawn(track, path, self._sample_rate)?;
+            takes.push(take);
+            writers.push((track, writer));
+        }
+
+        self.dispatch_edit(AudioBackendCommand::StartRecording(takes))?;
+        self.next_take += 1;
+        self.writers = writers;
+        Ok(())
+    }
+
+    /// Ends the takes and places each of them as a clip on the playlist of its
+    /// track at the range it was recorded at. The clips are undone together.
+    pub fn stop_recording(&mut self) -> Result<Vec<(NodeIndex, Range<MusicalTime>)>, RecordingError>
+    where
+        T: ConvertibleSample,
+    {
+        self.dispatch_edit(AudioBackendCommand::StopRecording)?;
+
+        // a take that fails doesn't keep the others from being placed,
+        // the first error is returned once all of them were handled
+        let mut recorded = Vec::with_capacity(self.writers.len());
+        let mut result = Ok(());
+        self.history.begin();
+        for (track, writer) in std::mem::take(&mut self.writers) {
+            match self.place_take(track, writer) {
+                Ok(Some(range)) => recorded.push((track, range)),
+                Ok(None) => {}
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+        self.history.end();
+
+        result.map(|()| recorded)
+    }
+
+    /// Finishes the take's file and inserts it as a clip, unless nothing was recorded
+    fn place_take(
+        &mut self,
+        track: NodeIndex,
+        writer: DiskWriter,
+    ) -> Result<Option<Range<MusicalTime>>, RecordingError>
+    where
+        T: ConvertibleSample,
+    {
+        let take = writer.finish()?;
+
+        let Some(range) = take.range else {
+            // nothing was recorded
+            std::fs::remove_file(take.path)?;
+            return Ok(None);
+        };
+
+        let buffer = audio_buffer::loader::load(&take.path)?;
+        self.insert_clip(track, range.clone(), Clip::new(Arc::new(buffer)))?;
+        Ok(Some(range))
+    }
+
+    fn connection_id(&self, edge: EdgeIndex) -> Result<ConnectionId, AudioEngineError> {
+        self.model
+            .connection_id(edge)
+            .ok_or(ModelError::InvalidEdge(edge).into())
+    }
+
+    fn connection_edge(&self, id: ConnectionId) -> Result<EdgeIndex, AudioEngineError> {
+        self.model
+            .connection_edge(id)
+            .ok_or(ModelError::InvalidConnection(id).into())
+    }
+
+    /// `output` is the id the connection to the master track had before the
+    /// track was removed, so that older edits of it still find it
+    fn apply_add_track(
+        &mut self,
+        kind: TrackKind,
+        output: Option<ConnectionId>,
+    ) -> Result<NodeIndex, AudioEngineError> {
+        let status = self.dispatch_edit(match kind {
+            TrackKind::Audio => AudioBackendCommand::AddTrack,
+            TrackKind::Bus => AudioBackendCommand::AddBus,
+        })?;
+        let AudioEngineStatus::TrackAdded(track) = status else {
+            unreachable!("the backend answers AddTrack and AddBus with TrackAdded");
+        };
+
+        let added = self.model.add_track(kind);
+        debug_assert_eq!(added, track, "the model adds tracks like the backend");
+        if let (Some(id), [edge]) = (output, &self.model.outputs(track)[..]) {
+            self.model.restore_connection_id(*edge, id);
+        }
+        self.update_solo_mutes()?;
+        Ok(track)
+    }
+
+    /// Returns the kind of the removed track and the id of its connection to the master track
+    fn apply_remove_track(
+        &mut self,
+        track: NodeIndex,
+    ) -> Result<(TrackKind, Option<ConnectionId>), AudioEngineError> {
+        self.model.validate_track_removal(track)?;
+        let master = self.model.master_index();
+        let output = self
+            .model
+            .outputs(track)
+            .into_iter()
+            .find(|&edge| {
+                self.model
+                    .connection(edge)
+                    .is_some_and(|(_, destination, _)| destination == master)
+            })
+            .and_then(|edge| self.model.connection_id(edge));
+
+        self.dispatch_edit(AudioBackendCommand::RemoveTrack(track))?;
+
+        // mirrors `AudioBackend::remove_track`, the take's writer finishes on its own
+        let last = NodeIndex::new(self.model.tracks().count() - 1);
+        self.output_map.remove_track(track, last);
+        self.writers.retain(|(index, _)| *index != track);
+        for (index, _) in &mut self.writers {
+            if *index == last {
+                *index = track;
+            }
+        }
+
+        let kind = self
+            .model
+            .remove_track(track)
+            .expect("track was validated")
+            .kind();
+        self.update_solo_mutes()?;
+        Ok((kind, output))
+    }
+
+    fn apply_add_connection(
+        &mut self,
+        source: NodeIndex,
+        destination: NodeIndex,
+        connection: Connection,
+    ) -> Result<EdgeIndex, AudioEngineError> {
+        self.model
+            .validate_connection(source, destination, connection.matrix())?;
+        let status = self.dispatch_edit(AudioBackendCommand::AddConnection {
+            source,
+            destination,
+            connection: connection.clone(),
+        })?;
+        let AudioEngineStatus::ConnectionAdded(edge) = status else {
+            unreachable!("the backend answers AddConnection with ConnectionAdded");
+        };
+
+        let added = self.model.add_connection(source, destination, connection);
+        debug_assert_eq!(added, edge, "the model adds connections like the backend");
+        self.update_solo_mutes()?;
+        Ok(edge)
+    }
+
+    fn apply_set_send(
+        &mut self,
+        edge: EdgeIndex,
+        tap: SendTap,
+        gain: f32,
+    ) -> Result<(SendTap, f32), AudioEngineError> {
+        self.model.validate_send_update(edge, gain)?;
+        self.dispatch_edit(AudioBackendCommand::SetSend { edge, tap, gain })?;
+
+        Ok(self.model.set_send(edge, tap, gain))
+    }
+
+    fn apply_remove_connection(
+        &mut self,
+        edge: EdgeIndex,
+    ) -> Result<(NodeIndex, NodeIndex, Connection), AudioEngineError> {
+        self.model.validate_edge(edge)?;
+        self.dispatch_edit(AudioBackendCommand::RemoveConnection(edge))?;
+
+        let removed = self
+            .model
+            .remove_connection(edge)
+            .expect("edge was validated");
+        self.update_solo_mutes()?;
+        Ok(removed)
+    }
+
+    fn apply_update_connection(
+        &mut self,
+        edge: EdgeIndex,
+        matrix: PinMatrix,
+    ) -> Result<PinMatrix, AudioEngineError> {
+        self.model.validate_connection_update(edge, &matrix)?;
+        self.dispatch_edit(AudioBackendCommand::UpdateConnection {
+            edge,
+            matrix: matrix.clone(),
+        })?;
+
+        Ok(self.model.update_connection(edge, matrix))
+    }
+
+    fn apply_mixer_control(
+        &mut self,
+        track: NodeIndex,
+        control: MixerControl,
+    ) -> Result<MixerControl, AudioEngineError> {
+        self.model.validate_mixer_control(track, control)?;
+        self.dispatch_edit(AudioBackendCommand::set_mixer_control(track, control))?;
+
+        let old = self.model.set_mixer_control(track, control);
+        if let MixerControl::Solo(_) = control {
+            self.update_solo_mutes()?;
+        }
+        Ok(old)
+    }
+
+    // Solo depends on the routing between the tracks, so it's worked out here
+    // instead of on the audio thread. Isn't part of the history, undoing the
+    // edit that changed the solos or the routing updates it again.
+    fn update_solo_mutes(&mut self) -> Result<(), AudioEngineError> {
+        for (track, muted) in self.model.solo_changes() {
+            self.dispatch_edit(AudioBackendCommand::SetTrackSoloMuted { track, muted })?;
+            self.model.set_solo_muted(track, muted);
+        }
+        Ok(())
+    }
+
+    fn apply_parameter(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    ) -> Result<(f32, TransportStatus), AudioEngineError> {
+        let value = self
+            .model
+            .validate_parameter(track, processor, parameter, value)?;
+        let status = self.dispatch_edit(AudioBackendCommand::SetParameter {
+            track,
+            processor,
+            parameter,
+            value,
+        })?;
+        let AudioEngineStatus::ParameterSet(transport) = status else {
+            unreachable!("the backend answers SetParameter with ParameterSet");
+        };
+
+        let old = self.model.set_parameter(track, processor, parameter, value);
+        Ok((old, transport))
+    }
+
+    fn apply_automation_lane(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        lane: Option<AutomationLane>,
+    ) -> Result<Option<AutomationLane>, AudioEngineError> {
+        self.model
+            .validate_automation_lane(track, processor, parameter)?;
+
+        let command = match lane.clone() {
+            Some(lane) => AudioBackendCommand::SetAutomationLane { track, lane },
+            None => AudioBackendCommand::RemoveAutomationLane {
+                track,
+                processor,
+                parameter,
+            },
+        };
+        self.dispatch_edit(command)?;
+
+        Ok(self
+            .model
+            .set_automation_lane(track, processor, parameter, lane))
+    }
+
+    fn apply_insert_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    ) -> Result<(), AudioEngineError> {
+        self.model.validate_clip_insert(track, &range)?;
+        self.dispatch_edit(AudioBackendCommand::InsertClip {
+            track,
+            range: range.clone(),
+            clip: clip.clone(),
+        })?;
+
+        self.model.insert_clip(track, range, clip);
+        Ok(())
+    }
+
+    fn apply_remove_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> Result<Clip<T>, AudioEngineError> {
+        self.model.validate_clip_removal(track, &range)?;
+        self.dispatch_edit(AudioBackendCommand::RemoveClip {
+            track,
+            range: range.clone(),
+        })?;
+
+        Ok(self
+            .model
+            .remove_clip(track, range)
+            .expect("clip was validated"))
+    }
+
+    fn apply_insert_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    ) -> Result<(), AudioEngineError> {
+        self.model.validate_clip_insert(track, &range)?;
+        self.dispatch_edit(AudioBackendCommand::InsertMidiClip {
+            track,
+            range: range.clone(),
+            clip: clip.clone(),
+        })?;
+
+        self.model.insert_midi_clip(track, range, clip);
+        Ok(())
+    }
+
+    fn apply_remove_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> Result<MidiClip, AudioEngineError> {
+        self.model.validate_midi_clip_removal(track, &range)?;
+        self.dispatch_edit(AudioBackendCommand::RemoveMidiClip {
+            track,
+            range: range.clone(),
+        })?;
+
+        Ok(self
+            .model
+            .remove_midi_clip(track, range)
+            .expect("clip was validated"))
+    }
+
+    /// Loads a Standard MIDI File into a clip that spans the whole file
+    pub fn load_midi_file(&mut self, path: impl AsRef<Path>) -> Result<MidiClip, MidiLoadError> {
+        midi::load(path)
+    }
+
+    pub fn load_audio_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Arc<InterleavedBuffer<T>>, LoadError>
+    where
+        T: ConvertibleSample,
+    {
+        audio_buffer::loader::load(path).map(Arc::new)
+    }
+}
+
+fn stream_error(failure: StreamFailure) -> AudioEngineMessage {
+    AudioEngineMessage {
+        id: None,
+        status: AudioEngineStatus::StreamError(failure),
+    }
+}
+
+fn input_stream_error(failure: StreamFailure) -> AudioEngineMessage {
+    AudioEngineMessage {
+        id: None,
+        status: AudioEngineStatus::InputStreamError(failure),
+    }
+}
+
+/// Negotiates what a lost stream is reopened with through `negotiate`. The requested
+/// device is preferred, if it's gone the default device is used and the returned flag
+/// is set. The graph was set up for `sample_rate`, so no other rate is accepted.
+fn reopen_config<D>(
+    request: &StreamRequest,
+    sample_rate: SampleRate,
+    mut negotiate: impl FnMut(&StreamRequest) -> Result<(D, StreamConfig), DeviceError>,
+) -> Result<(D, StreamConfig, bool), DeviceError> {
+    let request = request.clone().with_sample_rate(sample_rate);
+    let (device, config, on_fallback) = match negotiate(&request) {
+        Ok((device, config)) => (device, config, false),
+        Err(DeviceError::DeviceNotFound(_)) if request.has_device() => {
+            let (device, config) = negotiate(&request.with_default_device())?;
+            (device, config, true)
+        }
+        Err(e) => return Err(e),
+    };
+
+    if config.sample_rate != sample_rate {
+        return Err(DeviceError::SampleRate(config.sample_rate));
+    }
+    Ok((device, config, on_fallback))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+
+    use audio_graph::{
+        daggy::{EdgeIndex, NodeIndex},
+        processor::gain::{GAIN, Gain},
+    };
+    use cpal::SampleFormat;
+    use time::{FrameTime, MusicalTime, SampleRate};
+
+    use crate::{
+        automation::{AutomationMode, Breakpoint},
+        device::DeviceError,
+        device::{Direction, StreamConfig, StreamFailure, StreamRequest},
+        engine::{AudioEngine, AudioEngineError, PENDING_ACKS, reopen_config},
+        message::{AudioBackendCommand, AudioEngineStatus},
+        model::ModelError,
+        track::SendTap,
+    };
+
+    fn engine() -> AudioEngine<f32> {
+        let config = StreamConfig {
+            host: cpal::default_host().id(),
+            device: String::new(),
+            sample_rate: SampleRate::new(1000.0),
+            buffer_size: Some(FrameTime(64)),
+            max_buffer_size: Some(FrameTime(64)),
+            channels: 2,
+            sample_format: SampleFormat::F32,
+        };
+        AudioEngine::without_stream(120.0, FrameTime(64), &StreamRequest::new(), config)
+    }
+
+    // runs `control` while another thread stands in for the audio callback
+    fn with_backend<R>(
+        engine: &mut AudioEngine<f32>,
+        control: impl FnOnce(&mut AudioEngine<f32>) -> R,
+    ) -> R {
+        let shared = engine.shared.clone();
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Acquire) {
+                    shared.backend.lock().unwrap().process_commands();
+                    std::thread::sleep(Duration::from_micros(100));
+                }
+            });
+            let result = control(engine);
+            done.store(true, Ordering::Release);
+            result
+        })
+    }
+
+    fn send_and_wait(
+        engine: &mut AudioEngine<f32>,
+        command: AudioBackendCommand<f32>,
+        timeout: Duration,
+    ) -> Result<AudioEngineStatus, AudioEngineError> {
+        let id = engine.dispatch_command(command)?;
+        engine.wait_for_reply(id, timeout)
+    }
+
+    #[test]
+    fn waits_for_the_reply_to_its_own_command() {
+        let mut engine = engine();
+
+        let status = with_backend(&mut engine, |engine| {
+            engine.dispatch_command(AudioBackendCommand::Start).unwrap();
+            send_and_wait(
+                engine,
+                AudioBackendCommand::AddTrack,
+                Duration::from_secs(5),
+            )
+        });
+        assert!(
+            matches!(status, Ok(AudioEngineStatus::TrackAdded(track)) if track == NodeIndex::new(1))
+        );
+
+        // the reply to the command before it is still there
+        let reply = engine.poll_status().unwrap();
+        assert!(reply.id.is_some());
+        assert!(matches!(reply.status, AudioEngineStatus::Ok));
+    }
+
+    #[test]
+    fn rejected_and_unanswered_commands_are_errors() {
+        let mut engine = engine();
+
+        let status = with_backend(&mut engine, |engine| {
+            let missing = NodeIndex::new(7);
+            send_and_wait(
+                engine,
+                AudioBackendCommand::RemoveTrack(missing),
+                Duration::from_secs(5),
+            )
+        });
+        assert!(matches!(
+            status,
+            Err(AudioEngineError::Rejected(AudioEngineStatus::InvalidTrack(
+                _
+            )))
+        ));
+
+        // nothing processes the command
+        let status = send_and_wait(
+            &mut engine,
+            AudioBackendCommand::Start,
+            Duration::from_millis(10),
+        );
+        assert!(matches!(status, Err(AudioEngineError::Timeout(_))));
+    }
+
+    #[test]
+    fn transport_commands_can_be_waited_for() {
+        let mut engine = engine();
+
+        let status = engine.dispatch_and_wait(AudioEngine::play, Duration::from_secs(5));
+        assert!(matches!(status, Ok(AudioEngineStatus::Ok)));
+
+        let start = MusicalTime::new(0, 0);
+        let status = engine.dispatch_and_wait(
+            |engine| engine.set_loop(start..start),
+            Duration::from_secs(5),
+        );
+        assert!(matches!(
+            status,
+            Err(AudioEngineError::Rejected(AudioEngineStatus::InvalidRange(
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn unread_replies_dont_stall_the_backend() {
+        let mut engine = engine();
+
+        for _ in 0..PENDING_ACKS * 3 {
+            engine.dispatch_command(AudioBackendCommand::Start).unwrap();
+            engine.shared.backend.lock().unwrap().process_commands();
+        }
+        let status = with_backend(&mut engine, |engine| {
+            send_and_wait(engine, AudioBackendCommand::Pause, Duration::from_secs(5))
+        });
+        assert!(status.is_ok());
+        assert_eq!(engine.statuses().count(), PENDING_ACKS);
+    }
+
+    #[test]
+    fn edits_the_backend_rejects_stay_out_of_the_model() {
+        let mut engine = engine();
+
+        let track = engine.add_track().unwrap();
+        let bus = engine.add_bus().unwrap();
+        assert_eq!((track, bus), (NodeIndex::new(1), NodeIndex::new(2)));
+
+        // the backend loses the bus behind the model's back
+        let id = engine
+            .dispatch_command(AudioBackendCommand::RemoveTrack(bus))
+            .unwrap();
+        engine.shared.backend.lock().unwrap().process_commands();
+        assert!(engine.wait_for_reply(id, Duration::ZERO).is_ok());
+
+        let connections = engine.model().connections().count();
+        assert!(matches!(
+            engine.add_send(track, bus, SendTap::PostFader, 1.0),
+            Err(AudioEngineError::Rejected(
+                AudioEngineStatus::InvalidConnection { .. }
+            ))
+        ));
+        assert_eq!(engine.model().connections().count(), connections);
+    }
+
+    #[test]
+    fn soloing_a_track_keeps_the_bus_it_feeds_audible() {
+        let mut engine = engine();
+
+        let solo = engine.add_track().unwrap();
+        let other = engine.add_track().unwrap();
+        let group = engine.add_bus().unwrap();
+        engine.route_track(solo, group).unwrap();
+
+        engine.set_track_solo(solo, true).unwrap();
+        let muted =
+            |engine: &AudioEngine<f32>, track| engine.model().track(track).unwrap().is_solo_muted();
+        assert!(!muted(&engine, solo) && !muted(&engine, group));
+        assert!(muted(&engine, other));
+
+        // sharing the group with the soloed track isn't enough
+        engine.route_track(other, group).unwrap();
+        assert!(muted(&engine, other));
+
+        // but it feeds the group once that is soloed as well
+        engine.set_track_solo(group, true).unwrap();
+        assert!(!muted(&engine, other));
+
+        engine.undo().unwrap();
+        assert!(muted(&engine, other));
+        engine.set_track_solo(solo, false).unwrap();
+        assert!(!muted(&engine, other));
+    }
+
+    // the gains of the connections between two tracks
+    fn gains(engine: &AudioEngine<f32>, source: NodeIndex, destination: NodeIndex) -> Vec<f32> {
+        engine
+            .model()
+            .connections()
+            .filter(|&(_, s, d, _)| (s, d) == (source, destination))
+            .map(|(_, _, _, connection)| connection.gain())
+            .collect()
+    }
+
+    #[test]
+    fn undo_follows_connections_whose_edge_index_moved() {
+        let mut engine = engine();
+
+        let track = engine.add_track().unwrap();
+        let bus = engine.add_bus().unwrap();
+        let master = engine.model().master_index();
+        let send = engine
+            .add_send(track, bus, SendTap::PostFader, 0.5)
+            .unwrap();
+        engine.set_send(send, SendTap::PostFader, 0.25).unwrap();
+
+        // removing the track's output moves the send into its index
+        engine.remove_connection(EdgeIndex::new(0)).unwrap();
+        assert_eq!(engine.model().connection(EdgeIndex::new(0)).unwrap().1, bus);
+
+        assert!(engine.undo().unwrap() && engine.undo().unwrap());
+        assert_eq!(gains(&engine, track, bus), vec![0.5]);
+        assert_eq!(gains(&engine, track, master), vec![1.0]);
+
+        assert!(engine.redo().unwrap() && engine.redo().unwrap());
+        assert_eq!(gains(&engine, track, bus), vec![0.25]);
+        assert!(gains(&engine, track, master).is_empty());
+    }
+
+    #[test]
+    fn redone_tracks_keep_the_ids_of_their_connections() {
+        let mut engine = engine();
+
+        let track = engine.add_track().unwrap();
+        let bus = engine.add_bus().unwrap();
+        let master = engine.model().master_index();
+        engine.route_track(track, bus).unwrap();
+
+        while engine.undo().unwrap() {}
+        assert_eq!(engine.model().tracks().count(), 1);
+
+        // the routing removes the connections the redone tracks were added with
+        while engine.redo().unwrap() {}
+        assert_eq!(gains(&engine, track, bus), vec![1.0]);
+        assert!(gains(&engine, track, master).is_empty());
+        assert_eq!(gains(&engine, bus, master), vec![1.0]);
+    }
+
+    #[test]
+    fn undo_stops_at_tracks_with_processors() {
+        let mut engine = engine();
+
+        let track = engine.add_track().unwrap();
+        let gain = engine
+            .add_processor(track, Box::new(Gain::new(SampleRate::new(1000.0), 2)))
+            .unwrap();
+        engine.set_parameter(track, gain, GAIN, -6.0).unwrap();
+
+        assert!(engine.undo().unwrap());
+        assert_eq!(engine.get_parameter(track, gain, GAIN), Some(0.0));
+
+        // removing the track would lose the processor
+        assert!(matches!(
+            engine.undo(),
+            Err(AudioEngineError::Invalid(ModelError::ProcessorsNotRecorded(t))) if t == track
+        ));
+        assert!(engine.model().track(track).is_some());
+
+        assert!(engine.redo().unwrap());
+        assert_eq!(engine.get_parameter(track, gain, GAIN), Some(-6.0));
+        assert!(!engine.redo().unwrap());
+
+        assert!(engine.undo().unwrap());
+        assert_eq!(engine.get_parameter(track, gain, GAIN), Some(0.0));
+    }
+
+    #[test]
+    fn written_changes_land_at_the_block_they_were_applied_in() {
+        let mut engine = engine();
+        let track = engine.add_track().unwrap();
+        let gain = engine
+            .add_processor(track, Box::new(Gain::new(SampleRate::new(1000.0), 2)))
+            .unwrap();
+        engine
+            .set_automation_mode(track, gain, GAIN, AutomationMode::Write)
+            .unwrap();
+
+        let mut output = vec![0.0; 128];
+        engine.play().unwrap();
+        engine.shared.backend.lock().unwrap().process(&mut output);
+        engine.set_parameter(track, gain, GAIN, -6.0).unwrap();
+        engine.shared.backend.lock().unwrap().process(&mut output);
+        engine.set_parameter(track, gain, GAIN, -12.0).unwrap();
+
+        // the status of neither block was polled
+        engine.pause().unwrap();
+        assert!(engine.passes.is_empty());
+
+        let at = |frames| FrameTime(frames).to_musical_lossy(120.0, SampleRate::new(1000.0));
+        let lane = engine
+            .model()
+            .track(track)
+            .and_then(|model| model.automation_lane(gain, GAIN))
+            .unwrap();
+        assert_eq!(
+            lane.breakpoints(),
+            [
+                Breakpoint::new(at(64), -6.0),
+                Breakpoint::new(at(128), -12.0)
+            ]
+        );
+
+        assert!(engine.undo().unwrap());
+        let lane = engine
+            .model()
+            .track(track)
+            .and_then(|model| model.automation_lane(gain, GAIN))
+            .unwrap();
+        assert!(lane.breakpoints().is_empty());
+    }
+
+    #[test]
+    fn input_stream_errors_are_reported_and_flag_the_lost_device() {
+        let mut engine = engine();
+
+        let (mut on_error, failures) =
+            AudioEngine::error_callback(&engine.shared, Direction::Input);
+        engine.input_failures = Some(failures);
+
+        on_error(cpal::StreamError::DeviceNotAvailable);
+        assert!(engine.shared.input_lost.load(Ordering::Acquire));
+        assert!(!engine.shared.device_lost.load(Ordering::Acquire));
+
+        let message = engine.poll_status().unwrap();
+        assert!(matches!(
+            message.status,
+            AudioEngineStatus::InputStreamError(StreamFailure::DeviceLost)
+        ));
+    }
+
+    #[test]
+    fn lost_streams_reopen_at_the_engine_rate() {
+        let rate = SampleRate::new(1000.0);
+        let config = |rate| StreamConfig {
+            sample_rate: rate,
+            ..engine().stream_config().clone()
+        };
+        let request = StreamRequest::new().with_device("Interface");
+
+        // the requested device is back
+        let (_, _, fallback) = reopen_config(&request, rate, |asked| {
+            assert_eq!(asked, &request.clone().with_sample_rate(rate));
+            Ok(((), config(rate)))
+        })
+        .unwrap();
+        assert!(!fallback);
+
+        // it's gone, so the default device is asked for instead
+        let mut asked = Vec::new();
+        let (_, _, fallback) = reopen_config(&request, rate, |request| {
+            asked.push(request.clone());
+            if request.has_device() {
+                Err(DeviceError::DeviceNotFound(Some("Interface".into())))
+            } else {
+                Ok(((), config(rate)))
+            }
+        })
+        .unwrap();
+        assert!(fallback);
+        assert_eq!(asked[1], StreamRequest::new().with_sample_rate(rate));
+
+        // the graph can't change its rate
+        let result = reopen_config(&StreamRequest::new(), rate, |_| {
+            Ok(((), config(SampleRate::new(2000.0))))
+        });
+        assert!(matches!(result, Err(DeviceError::SampleRate(_))));
+
+        // only a missing device falls back
+        let result = reopen_config(&request, rate, |_| {
+            Err::<((), _), _>(DeviceError::NoConfigs)
+        });
+        assert!(matches!(result, Err(DeviceError::NoConfigs)));
     }
 }
@@ -0,0 +1,179 @@
+use std::any::TypeId;
+
+use audio_buffer::dasp::sample::{FloatSample, FromSample, ToSample};
+use cpal::SampleFormat;
+
+/// The device format that matches the engine's sample type, which needs no conversion
+pub fn float_format<T: FloatSample>() -> SampleFormat {
+    match size_of::<T>() {
+        4 => SampleFormat::F32,
+        _ => SampleFormat::F64,
+    }
+}
+
+/// The number of bits of an integer sample format, `None` for floating point formats
+pub fn integer_bits(sample_format: SampleFormat) -> Option<u32> {
+    match sample_format {
+        SampleFormat::I8 | SampleFormat::U8 => Some(8),
+        SampleFormat::I16 | SampleFormat::U16 => Some(16),
+        SampleFormat::I24 => Some(24),
+        SampleFormat::I32 | SampleFormat::U32 => Some(32),
+        SampleFormat::I64 | SampleFormat::U64 => Some(64),
+        _ => None,
+    }
+}
+
+/// Triangular (TPDF) dither, which decorrelates the quantization error
+/// from the signal when it is rounded to an integer format
+struct Dither {
+    // one step of the integer format in the range of float samples
+    step: f64,
+    state: u32,
+}
+
+impl Dither {
+    fn new(bits: u32) -> Self {
+        Self {
+            step: 2f64.powi(1 - bits as i32),
+            state: 0x9E37_79B9,
+        }
+    }
+
+    // xorshift, good enough for noise and doesn't allocate
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state as f64 / u32::MAX as f64
+    }
+
+    /// Adds the dither and rounds to the nearest step, clipping to the range of the format
+    fn quantize(&mut self, sample: f64) -> f64 {
+        let noise = (self.uniform() - self.uniform()) * self.step;
+        let steps = ((sample + noise) / self.step).round();
+        (steps * self.step).clamp(-1.0, 1.0 - self.step)
+    }
+}
+
+/// Converts the samples the backend renders in `T` to the format of the output device.
+/// Integer formats are dithered.
+pub struct OutputConverter<T> {
+    // the rendered samples of up to `frames` frames, allocated up front
+    scratch: Vec<T>,
+    dither: Option<Dither>,
+}
+
+impl<T: FloatSample + 'static> OutputConverter<T> {
+    /// `frames` is the most the device asks for in one callback. Larger
+    /// callbacks are rendered and converted in several parts.
+    pub fn new(sample_format: SampleFormat, channels: usize, frames: usize) -> Self {
+        Self {
+            scratch: vec![T::EQUILIBRIUM; channels * frames],
+            dither: integer_bits(sample_format).map(Dither::new),
+        }
+    }
+
+    /// Lets `render` fill `output`, in whole frames. A device whose format
+    /// is `T` is rendered to directly, anything else is converted.
+    pub fn process<D>(&mut self, output: &mut [D], mut render: impl FnMut(&mut [T]))
+    where
+        D: FromSample<f64> + 'static,
+    {
+        if let Some(output) = cast_mut::<D, T>(output) {
+            render(output);
+            return;
+        }
+
+        for chunk in output.chunks_mut(self.scratch.len()) {
+            let rendered = &mut self.scratch[..chunk.len()];
+            render(rendered);
+
+            for (out, &sample) in chunk.iter_mut().zip(rendered.iter()) {
+                let sample: f64 = sample.to_sample();
+                *out = D::from_sample_(match &mut self.dither {
+                    Some(dither) => dither.quantize(sample),
+                    None => sample,
+                });
+            }
+        }
+    }
+}
+
+// Reinterprets the samples as `F` if that is what `T` is
+fn cast_mut<T: 'static, F: 'static>(samples: &mut [T]) -> Option<&mut [F]> {
+    // SAFETY: `T` and `F` are the same type
+    (TypeId::of::<T>() == TypeId::of::<F>())
+        .then(|| unsafe { &mut *(samples as *mut [T] as *mut [F]) })
+}
+
+/// Converts a sample captured in the device's format to the engine's sample type
+pub fn from_device<D, T>(sample: D) -> T
+where
+    D: ToSample<f64>,
+    T: FloatSample,
+{
+    T::from_sample_(sample.to_sample_())
+}
+
+#[cfg(test)]
+mod tests {
+    use cpal::SampleFormat;
+
+    use crate::format::OutputConverter;
+
+    #[test]
+    fn dithered_integers_stay_close_to_the_signal() {
+        let signal: Vec<f32> = (0..4096).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+
+        let mut converter = OutputConverter::<f32>::new(SampleFormat::I16, 1, signal.len());
+        let mut output = vec![0i16; signal.len()];
+        converter.process(&mut output, |rendered| rendered.copy_from_slice(&signal));
+
+        let step = 1.0 / 32768.0;
+        let mut error = 0.0;
+        for (&out, &sample) in output.iter().zip(&signal) {
+            let difference = out as f64 * step - sample as f64;
+            // triangular dither spans one step to either side, plus rounding
+            assert!(difference.abs() <= 1.5 * step);
+            error += difference;
+        }
+        // the error doesn't accumulate into an offset
+        assert!((error / signal.len() as f64).abs() < 0.1 * step);
+
+        // unsigned formats are centered around half of their range
+        let mut converter = OutputConverter::<f32>::new(SampleFormat::U8, 1, 4);
+        let mut output = vec![0u8; 4];
+        converter.process(&mut output, |rendered| {
+            rendered.copy_from_slice(&[0.0, 0.0, 1.0, -1.0])
+        });
+        assert!(output[0].abs_diff(128) <= 1);
+        assert!(output[2] >= 254 && output[3] <= 1);
+
+        // floats are passed through untouched
+        let mut converter = OutputConverter::<f32>::new(SampleFormat::F64, 1, 2);
+        let mut output = vec![0f64; 2];
+        converter.process(&mut output, |rendered| {
+            rendered.copy_from_slice(&[0.25, -0.5])
+        });
+        assert_eq!(output, [0.25, -0.5]);
+    }
+
+    #[test]
+    fn larger_callbacks_are_rendered_in_whole_frames() {
+        let mut converter = OutputConverter::<f32>::new(SampleFormat::F64, 2, 3);
+        let mut output = vec![0f64; 16];
+        let mut lengths = Vec::new();
+        converter.process(&mut output, |rendered| {
+            lengths.push(rendered.len());
+            rendered.fill(0.5);
+        });
+        assert_eq!(lengths, [6, 6, 4]);
+        assert!(output.iter().all(|&sample| sample == 0.5));
+
+        // the device's own format is rendered to in one go
+        let mut output = vec![0f32; 16];
+        lengths.clear();
+        converter.process(&mut output, |rendered| lengths.push(rendered.len()));
+        assert_eq!(lengths, [16]);
+    }
+}
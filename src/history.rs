@@ -0,0 +1,230 @@
+use std::ops::Range;
+
+use audio_graph::{Connection, daggy::NodeIndex, parameter::ParameterId, pin_matrix::PinMatrix};
+use time::MusicalTime;
+
+use crate::{
+    automation::AutomationLane, midi::MidiClip, mixer::MixerControl, model::ConnectionId,
+    playlist::Clip, track::SendTap,
+};
+
+/// A single change to the engine state that can be applied through the `AudioEngine`.
+/// Applying an edit yields the edit that reverts it.
+#[derive(Clone)]
+pub enum Edit<T> {
+    /// Adds a track whose connection to the master track gets the given id
+    AddTrack(Option<ConnectionId>),
+    AddBus(Option<ConnectionId>),
+    RemoveTrack(NodeIndex),
+    AddConnection {
+        id: ConnectionId,
+        source: NodeIndex,
+        destination: NodeIndex,
+        connection: Connection,
+    },
+    RemoveConnection(ConnectionId),
+    UpdateConnection {
+        id: ConnectionId,
+        matrix: PinMatrix,
+    },
+    SetSend {
+        id: ConnectionId,
+        tap: SendTap,
+        gain: f32,
+    },
+    InsertClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    },
+    RemoveClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    },
+    InsertMidiClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    },
+    RemoveMidiClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    },
+    SetMixerControl {
+        track: NodeIndex,
+        control: MixerControl,
+    },
+    SetParameter {
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    },
+    /// Replaces the lane of a parameter, `None` removes it
+    SetAutomationLane {
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        lane: Option<AutomationLane>,
+    },
+}
+
+/// A group of edits that is undone and redone as a whole.
+pub struct Transaction<T> {
+    // the edits that revert this transaction, in the order they have to be applied
+    edits: Vec<Edit<T>>,
+}
+
+impl<T> Transaction<T> {
+    pub(crate) fn new(edits: Vec<Edit<T>>) -> Self {
+        Self { edits }
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub(crate) fn edits(&self) -> &[Edit<T>] {
+        &self.edits
+    }
+
+    pub(crate) fn into_edits(self) -> Vec<Edit<T>> {
+        self.edits
+    }
+}
+
+/// Undo and redo stacks of the edits made through the `AudioEngine`.
+///
+/// Edits are reverted strictly in reverse order. Since removing a node from a
+/// graph moves the last one into the freed index, this is what keeps the track
+/// indices stored in older transactions valid. Connections can be removed in any
+/// order, so they are stored by their `ConnectionId` instead of their edge index.
+pub struct History<T> {
+    undo: Vec<Transaction<T>>,
+    redo: Vec<Transaction<T>>,
+
+    // inverses of the edits made since the outermost transaction was opened
+    open: Vec<Edit<T>>,
+    depth: usize,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            open: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.open.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open.clear();
+    }
+
+    /// Transactions can be nested, only the outermost one ends up on the undo stack.
+    pub(crate) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn end(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.close();
+        }
+    }
+
+    /// Records the inverse of an edit that has just been applied.
+    /// Outside of a transaction every edit is its own transaction.
+    pub(crate) fn record(&mut self, inverse: Edit<T>) {
+        self.redo.clear();
+        self.open.push(inverse);
+
+        if self.depth == 0 {
+            self.close();
+        }
+    }
+
+    /// Pops the transaction that reverts the latest edits.
+    /// A transaction that is still open is ended first.
+    pub(crate) fn pop_undo(&mut self) -> Option<Transaction<T>> {
+        self.depth = 0;
+        self.close();
+        self.undo.pop()
+    }
+
+    pub(crate) fn push_undo(&mut self, transaction: Transaction<T>) {
+        self.undo.push(transaction);
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Transaction<T>> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn push_redo(&mut self, transaction: Transaction<T>) {
+        self.redo.push(transaction);
+    }
+
+    fn close(&mut self) {
+        if self.open.is_empty() {
+            return;
+        }
+
+        let mut edits = std::mem::take(&mut self.open);
+        edits.reverse();
+        self.undo.push(Transaction::new(edits));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use audio_graph::daggy::NodeIndex;
+
+    use crate::history::{Edit, History, Transaction};
+
+    #[test]
+    fn transactions_group_edits_and_new_edits_clear_redo() {
+        let mut history = History::<f32>::new();
+
+        history.begin();
+        history.record(Edit::RemoveTrack(NodeIndex::new(1)));
+        history.begin();
+        history.record(Edit::AddBus(None));
+        history.end();
+        history.end();
+        history.record(Edit::RemoveTrack(NodeIndex::new(2)));
+
+        assert_eq!(history.pop_undo().map(|t| t.len()), Some(1));
+
+        let transaction = history.pop_undo().unwrap();
+        let edits = transaction.into_edits();
+        // the latest edit has to be reverted first
+        assert!(matches!(edits[0], Edit::AddBus(_)));
+        assert!(matches!(edits[1], Edit::RemoveTrack(_)));
+
+        history.push_redo(Transaction::new(vec![Edit::AddTrack(None)]));
+        assert!(history.can_redo());
+        history.record(Edit::RemoveTrack(NodeIndex::new(1)));
+        assert!(!history.can_redo());
+    }
+}
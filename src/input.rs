@@ -0,0 +1,242 @@
+use std::{num::NonZeroUsize, path::Path, sync::Arc};
+
+use audio_buffer::{
+    SharedSample,
+    buffers::interleaved::InterleavedBuffer,
+    core::{Buffer, BufferMut},
+    dasp::sample::{FloatSample, ToSample},
+    loader::error::LoadError,
+    symphonia::core::conv::ConvertibleSample,
+};
+use cpal::{
+    SampleFormat,
+    traits::{DeviceTrait, StreamTrait},
+};
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+use time::FrameTime;
+
+use crate::{
+    device::{self, DeviceError, Direction, StreamConfig, StreamRequest},
+    engine::AudioEngineError,
+    format::{self, float_format},
+};
+
+/// The channels of the input device a track listens to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputChannels {
+    /// A single channel that is fed to both sides of the track
+    Mono(usize),
+    Stereo(usize, usize),
+}
+
+impl InputChannels {
+    /// Returns the left and right sample of a track for an input frame.
+    /// Channels the frame doesn't have are silent.
+    pub fn map<T: SharedSample>(self, frame: &[T]) -> [T; 2] {
+        let sample = |channel: usize| frame.get(channel).copied().unwrap_or(T::EQUILIBRIUM);
+        match self {
+            InputChannels::Mono(channel) => [sample(channel); 2],
+            InputChannels::Stereo(left, right) => [sample(left), sample(right)],
+        }
+    }
+}
+
+/// Supplies the audio thread with the audio captured from an input device.
+pub trait InputSource<T>: Send {
+    fn channels(&self) -> NonZeroUsize;
+
+    /// Overwrites `block` with the next frames.
+    /// Frames that didn't arrive in time are left silent.
+    fn read(&mut self, block: &mut InterleavedBuffer<T>);
+}
+
+/// An input source together with the buffer the backend reads a block into.
+/// It is created on the control side, so the audio thread doesn't allocate.
+pub struct Input<T> {
+    source: Box<dyn InputSource<T>>,
+    block: InterleavedBuffer<T>,
+}
+
+impl<T: SharedSample> Input<T> {
+    pub fn new(source: Box<dyn InputSource<T>>, block_size: FrameTime) -> Self {
+        let block = InterleavedBuffer::with_shape(source.channels(), block_size);
+        Self { source, block }
+    }
+
+    /// Reads the next block from the source
+    pub fn read(&mut self) {
+        self.block.set_to_equilibrium();
+        self.source.read(&mut self.block);
+    }
+
+    /// The block that was read last
+    pub fn block(&self) -> &InterleavedBuffer<T> {
+        &self.block
+    }
+}
+
+/// Plays back a buffer as if it was captured from a device, e.g. to test recording.
+/// After the end of the buffer it is silent.
+pub struct FileInput<T> {
+    buffer: Arc<InterleavedBuffer<T>>,
+    position: usize,
+}
+
+impl<T: SharedSample> FileInput<T> {
+    pub fn new(buffer: Arc<InterleavedBuffer<T>>) -> Self {
+        Self {
+            buffer,
+            position: 0,
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError>
+    where
+        T: ConvertibleSample,
+    {
+        audio_buffer::loader::load(path).map(|buffer| Self::new(Arc::new(buffer)))
+    }
+}
+
+impl<T: SharedSample> InputSource<T> for FileInput<T> {
+    fn channels(&self) -> NonZeroUsize {
+        NonZeroUsize::new(self.buffer.channels()).expect("buffers have at least one channel")
+    }
+
+    fn read(&mut self, block: &mut InterleavedBuffer<T>) {
+        let start = self.position;
+        block.map_frames_mut(
+            |frame, index| {
+                if let Some(input) = self.buffer.get_frame(start + index) {
+                    frame.copy_from_slice(input);
+                }
+                Some(())
+            },
+            None,
+        );
+        self.position += block.frames();
+    }
+}
+
+/// Reads the samples an input stream pushed into a ring buffer
+pub struct StreamInput<T> {
+    consumer: HeapCons<T>,
+    channels: NonZeroUsize,
+}
+
+impl<T: SharedSample> InputSource<T> for StreamInput<T> {
+    fn channels(&self) -> NonZeroUsize {
+        self.channels
+    }
+
+    fn read(&mut self, block: &mut InterleavedBuffer<T>) {
+        block.map_frames_mut(
+            |frame, _| {
+                // only whole frames are taken, so the channels never get out of step
+                if self.consumer.occupied_len() >= frame.len() {
+                    self.consumer.pop_slice(frame);
+                }
+                Some(())
+            },
+            None,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Device(DeviceError),
+    Engine(AudioEngineError),
+}
+
+impl From<DeviceError> for InputError {
+    fn from(value: DeviceError) -> Self {
+        Self::Device(value)
+    }
+}
+
+/// Opens an input stream on the device `request` asks for and returns it together
+/// with the source the backend reads it from and the negotiated config. The samples
+/// are converted from the format of the device to `T`.
+///
+/// Errors of the stream are passed to `on_error`, which can be called on the audio thread.
+pub fn open_input_stream<T>(
+    request: &StreamRequest,
+    block_size: FrameTime,
+    on_error: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<(cpal::Stream, StreamInput<T>, StreamConfig), InputError>
+where
+    T: SharedSample + FloatSample,
+{
+    let (device, config) = device::negotiate(request, Direction::Input, float_format::<T>())?;
+    let (stream, source) = start_input_stream(&device, &config, block_size, on_error)?;
+    Ok((stream, source, config))
+}
+
+/// Starts an input stream on a device that was already negotiated, see `open_input_stream`
+pub fn start_input_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    block_size: FrameTime,
+    on_error: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<(cpal::Stream, StreamInput<T>), DeviceError>
+where
+    T: SharedSample + FloatSample,
+{
+    let channels =
+        NonZeroUsize::new(config.channels as usize).expect("devices have at least one channel");
+
+    // a few blocks of headroom for callbacks that aren't in step with the output
+    let capacity = block_size.0 as usize * channels.get() * 8;
+    let (producer, consumer) = HeapRb::<T>::new(capacity).split();
+
+    let stream = match config.sample_format {
+        SampleFormat::I8 => build_input_stream::<i8, T>(device, config, producer, on_error),
+        SampleFormat::I16 => build_input_stream::<i16, T>(device, config, producer, on_error),
+        SampleFormat::I24 => build_input_stream::<cpal::I24, T>(device, config, producer, on_error),
+        SampleFormat::I32 => build_input_stream::<i32, T>(device, config, producer, on_error),
+        SampleFormat::I64 => build_input_stream::<i64, T>(device, config, producer, on_error),
+        SampleFormat::U8 => build_input_stream::<u8, T>(device, config, producer, on_error),
+        SampleFormat::U16 => build_input_stream::<u16, T>(device, config, producer, on_error),
+        SampleFormat::U32 => build_input_stream::<u32, T>(device, config, producer, on_error),
+        SampleFormat::U64 => build_input_stream::<u64, T>(device, config, producer, on_error),
+        SampleFormat::F32 => build_input_stream::<f32, T>(device, config, producer, on_error),
+        SampleFormat::F64 => build_input_stream::<f64, T>(device, config, producer, on_error),
+        format => Err(DeviceError::UnsupportedSampleFormat(format)),
+    }?;
+    stream.play().map_err(DeviceError::Play)?;
+
+    Ok((stream, StreamInput { consumer, channels }))
+}
+
+fn build_input_stream<D, T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mut producer: HeapProd<T>,
+    on_error: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<cpal::Stream, DeviceError>
+where
+    D: cpal::SizedSample + ToSample<f64>,
+    T: SharedSample + FloatSample,
+{
+    let channels = config.channels as usize;
+    device
+        .build_input_stream(
+            &config.to_cpal(),
+            move |data: &[D], _: &cpal::InputCallbackInfo| {
+                // frames that don't fit are dropped until the backend catches up
+                let samples = producer.vacant_len() / channels * channels;
+                producer.push_iter(
+                    data[..samples.min(data.len())]
+                        .iter()
+                        .map(|&sample| format::from_device(sample)),
+                );
+            },
+            on_error,
+            None,
+        )
+        .map_err(DeviceError::Build)
+}
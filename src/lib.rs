@@ -1,5 +1,16 @@
+pub mod automation;
 pub mod backend;
-pub mod message;
+pub mod device;
 pub mod engine;
+pub mod format;
+pub mod history;
+pub mod input;
+pub mod message;
+pub mod midi;
+pub mod mixer;
+pub mod model;
+pub mod output;
 pub mod playlist;
+pub mod recording;
 pub mod track;
+pub mod transport;
@@ -1,47 +1,264 @@
+use std::ops::Range;
+
 use audio_graph::{
+    Connection,
     daggy::{EdgeIndex, NodeIndex},
+    parameter::ParameterId,
     pin_matrix::PinMatrix,
+    processor::AudioProcessor,
+};
+use time::{FrameTime, MusicalTime};
+
+use crate::{
+    automation::AutomationLane,
+    device::{StreamConfig, StreamFailure},
+    input::{Input, InputChannels},
+    midi::MidiClip,
+    mixer::{MixerControl, PanLaw},
+    output::OutputMap,
+    playlist::Clip,
+    recording::Take,
+    track::SendTap,
 };
-use time::MusicalTime;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MessageId(pub u64);
 
 #[derive(Debug, Clone)]
 pub struct AudioEngineMessage {
-    pub id: MessageId,
+    /// The id of the command this message acknowledges or `None`
+    /// for reports that the backend publishes on its own.
+    pub id: Option<MessageId>,
     pub status: AudioEngineStatus,
 }
 
 #[derive(Debug, Clone)]
 pub enum AudioEngineStatus {
+    TrackAdded(NodeIndex),
+    TrackRemoved(NodeIndex),
+    ConnectionAdded(EdgeIndex),
+    ConnectionUpdated(EdgeIndex),
+    ConnectionRemoved(EdgeIndex),
+    ProcessorAdded {
+        track: NodeIndex,
+        processor: NodeIndex,
+    },
+    ProcessorConnectionAdded {
+        track: NodeIndex,
+        edge: EdgeIndex,
+    },
+    /// A parameter was set. The change takes effect with the block that
+    /// starts at the playhead, which is where automation records it.
+    ParameterSet(TransportStatus),
+    InvalidTrack(NodeIndex),
     InvalidConnection {
         source: NodeIndex,
         destination: NodeIndex,
         matrix: PinMatrix,
     },
+    InvalidConnectionUpdate {
+        edge: EdgeIndex,
+        matrix: PinMatrix,
+    },
+    InvalidEdge(EdgeIndex),
+    InvalidProcessorConnection {
+        track: NodeIndex,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    },
+    InvalidProcessorEdge {
+        track: NodeIndex,
+        edge: EdgeIndex,
+    },
+    InvalidParameter {
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    },
+    InvalidRange(Range<MusicalTime>),
+    /// Recording was stopped. What was recorded is
+    /// reported by the `DiskWriter` of each take.
+    RecordingStopped,
+    Transport(TransportStatus),
+    /// Time spent processing the last callback relative to its real-time duration
+    CpuLoad(f32),
+    /// Total number of over- and underruns since the stream was started
+    Xrun(u64),
+    /// The output stream reported an error. If the device was lost the engine
+    /// rebuilds the stream on the next `AudioEngine::check_stream`.
+    StreamError(StreamFailure),
+    /// The output stream was rebuilt, possibly on a fallback device
+    StreamRestored(StreamConfig),
+    /// The input stream reported an error. If the device was lost the engine
+    /// reopens it on the next `AudioEngine::check_stream`.
+    InputStreamError(StreamFailure),
+    /// The input stream was reopened, possibly on the default input device
+    InputStreamRestored(StreamConfig),
     Ok,
 }
 
-#[derive(Debug, Clone)]
-pub struct AudioBackendMessage {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransportStatus {
+    pub playhead: MusicalTime,
+    pub frame: FrameTime,
+    pub running: bool,
+}
+
+impl AudioEngineStatus {
+    /// Whether this status reports a command the backend rejected
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            AudioEngineStatus::InvalidConnection { .. }
+                | AudioEngineStatus::InvalidTrack(_)
+                | AudioEngineStatus::InvalidConnectionUpdate { .. }
+                | AudioEngineStatus::InvalidEdge(_)
+                | AudioEngineStatus::InvalidProcessorConnection { .. }
+                | AudioEngineStatus::InvalidProcessorEdge { .. }
+                | AudioEngineStatus::InvalidParameter { .. }
+                | AudioEngineStatus::InvalidRange(_)
+        )
+    }
+}
+
+pub struct AudioBackendMessage<T: audio_buffer::dasp::Sample> {
     pub id: MessageId,
-    pub command: AudioBackendCommand,
+    pub command: AudioBackendCommand<T>,
 }
 
-#[derive(Debug, Clone)]
-pub enum AudioBackendCommand {
+pub enum AudioBackendCommand<T: audio_buffer::dasp::Sample> {
     Start,
     Pause,
     SetPlayhead(MusicalTime),
+    SetLoop(Range<MusicalTime>),
+    EnableLoop(bool),
+    SetPunch(Range<MusicalTime>),
+    EnablePunch(bool),
     AddTrack,
+    /// Adds a stereo bus that is routed to the master track
+    AddBus,
+    /// Removes a track together with all of its connections
+    RemoveTrack(NodeIndex),
     AddConnection {
         source: NodeIndex,
         destination: NodeIndex,
-        matrix: PinMatrix,
+        connection: Connection,
     },
     UpdateConnection {
         edge: EdgeIndex,
         matrix: PinMatrix,
     },
+    RemoveConnection(EdgeIndex),
+    /// Changes where a connection taps its source and its linear gain
+    SetSend {
+        edge: EdgeIndex,
+        tap: SendTap,
+        gain: f32,
+    },
+    /// Linear fader gain of a track
+    SetTrackGain {
+        track: NodeIndex,
+        gain: f32,
+    },
+    SetTrackPan {
+        track: NodeIndex,
+        pan: f32,
+    },
+    SetPanLaw {
+        track: NodeIndex,
+        law: PanLaw,
+    },
+    SetTrackMute {
+        track: NodeIndex,
+        mute: bool,
+    },
+    SetTrackSolo {
+        track: NodeIndex,
+        solo: bool,
+    },
+    /// Mutes a track because other tracks are soloed. The engine works out which
+    /// tracks that are from the solos and the routing, see `EngineModel::solo_changes`.
+    SetTrackSoloMuted {
+        track: NodeIndex,
+        muted: bool,
+    },
+    AddProcessor {
+        track: NodeIndex,
+        processor: Box<dyn AudioProcessor<T>>,
+    },
+    AddProcessorConnection {
+        track: NodeIndex,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    },
+    RemoveProcessorConnection {
+        track: NodeIndex,
+        edge: EdgeIndex,
+    },
+    /// Changes take effect at the start of the next block
+    SetParameter {
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    },
+    /// Inserts a lane, replacing the lane of the same parameter
+    SetAutomationLane {
+        track: NodeIndex,
+        lane: AutomationLane,
+    },
+    RemoveAutomationLane {
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    },
+    InsertClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    },
+    InsertMidiClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    },
+    RemoveMidiClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    },
+    RemoveClip {
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    },
+    /// Replaces the source of the audio that tracks can record and monitor
+    SetInput(Option<Input<T>>),
+    SetTrackInput {
+        track: NodeIndex,
+        input: Option<InputChannels>,
+    },
+    /// Plays the input through the track's processors, also while the transport is stopped
+    SetTrackMonitoring {
+        track: NodeIndex,
+        monitoring: bool,
+    },
+    /// Records the input of the tracks of the takes inside of the punch region
+    StartRecording(Vec<Take<T>>),
+    /// Ends all takes and drops them, which lets their writers finish
+    StopRecording,
+    /// Replaces which channels of the device master and the cue buses are played on
+    SetOutputMap(OutputMap),
+}
+
+impl<T: audio_buffer::dasp::Sample> AudioBackendCommand<T> {
+    pub fn set_mixer_control(track: NodeIndex, control: MixerControl) -> Self {
+        match control {
+            MixerControl::Gain(gain) => AudioBackendCommand::SetTrackGain { track, gain },
+            MixerControl::Pan(pan) => AudioBackendCommand::SetTrackPan { track, pan },
+            MixerControl::PanLaw(law) => AudioBackendCommand::SetPanLaw { track, law },
+            MixerControl::Mute(mute) => AudioBackendCommand::SetTrackMute { track, mute },
+            MixerControl::Solo(solo) => AudioBackendCommand::SetTrackSolo { track, solo },
+        }
+    }
 }
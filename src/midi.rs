@@ -0,0 +1,362 @@
+use std::{ops::Range, path::Path, sync::Arc};
+
+use audio_graph::event::{MidiEvent, MidiMessage};
+use midly::{Smf, Timing, TrackEventKind};
+use time::{MusicalTime, SUPER_BEAT_TICKS_PER_BEAT, SampleRate};
+
+use crate::transport::SubBlock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    /// Position of the note inside its clip
+    pub start: MusicalTime,
+    pub length: MusicalTime,
+    pub channel: u8,
+    pub key: u8,
+    pub velocity: u8,
+}
+
+impl Note {
+    pub fn new(start: MusicalTime, length: MusicalTime, key: u8, velocity: u8) -> Self {
+        Self {
+            start,
+            length,
+            channel: 0,
+            key,
+            velocity,
+        }
+    }
+
+    pub fn end(&self) -> MusicalTime {
+        self.start + self.length
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlChange {
+    /// Position of the change inside its clip
+    pub time: MusicalTime,
+    pub channel: u8,
+    pub controller: u8,
+    pub value: u8,
+}
+
+#[derive(Debug)]
+pub enum MidiLoadError {
+    Io(std::io::Error),
+    Parse(midly::Error),
+    /// Files that are timed in SMPTE frames instead of beats aren't supported
+    UnsupportedTiming,
+}
+
+/// Notes and control changes that are placed on the timeline through a `Playlist`.
+#[derive(Debug, Clone, Default)]
+pub struct MidiClip {
+    // sorted by start
+    notes: Arc<[Note]>,
+    // sorted by time
+    controls: Arc<[ControlChange]>,
+    // bounds how far back note offs have to be searched
+    longest_note: MusicalTime,
+    /// Position inside the clip that lines up with the start of the clip's range
+    pub offset: MusicalTime,
+}
+
+impl MidiClip {
+    pub fn new(mut notes: Vec<Note>, mut controls: Vec<ControlChange>) -> Self {
+        notes.sort_by_key(|note| note.start);
+        controls.sort_by_key(|control| control.time);
+
+        Self {
+            longest_note: notes
+                .iter()
+                .map(|note| note.length)
+                .max()
+                .unwrap_or_default(),
+            notes: notes.into(),
+            controls: controls.into(),
+            offset: MusicalTime::ZERO,
+        }
+    }
+
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    pub fn controls(&self) -> &[ControlChange] {
+        &self.controls
+    }
+
+    /// The end of the last note or control change
+    pub fn length(&self) -> MusicalTime {
+        let notes = self.notes.iter().map(Note::end);
+        let controls = self.controls.iter().map(|control| control.time);
+        notes.chain(controls).max().unwrap_or_default()
+    }
+
+    /// Appends the events of the clip at `range` that fall into `sub_block`.
+    ///
+    /// Notes that are still sounding when the clip ends are released at its end,
+    /// notes that would start and end on the same frame are skipped.
+    pub fn collect_events(
+        &self,
+        range: &Range<MusicalTime>,
+        sub_block: &SubBlock,
+        bpm: f64,
+        sample_rate: SampleRate,
+        events: &mut Vec<MidiEvent>,
+    ) {
+        if sub_block.frames.0 == 0 {
+            return;
+        }
+
+        // positions inside the clip
+        let local =
+            |time: MusicalTime| time.checked_sub(range.start).unwrap_or_default() + self.offset;
+        let from = local(range.start.max(sub_block.range.start));
+        let to = local(range.end.min(sub_block.range.end));
+        let clip_end = local(range.end);
+
+        let last_frame = (sub_block.offset + sub_block.frames).0 as usize - 1;
+        let offset_of = |time: MusicalTime| {
+            let time = range.start + time.checked_sub(self.offset).unwrap_or_default();
+            let frames = time
+                .checked_sub(sub_block.range.start)
+                .unwrap_or_default()
+                .to_nearest_frame_round_lossy(bpm, sample_rate);
+            ((sub_block.offset + frames).0 as usize).min(last_frame)
+        };
+
+        let earliest = from.checked_sub(self.longest_note).unwrap_or_default();
+        let first = self.notes.partition_point(|note| note.start < earliest);
+        let last = self.notes.partition_point(|note| note.start < to);
+
+        // notes before the offset have been cut off by trimming the clip
+        for note in self.notes[first..last]
+            .iter()
+            .filter(|note| note.start >= self.offset)
+        {
+            let end = note.end().min(clip_end);
+            let on = (from <= note.start).then(|| offset_of(note.start));
+            let off = (from <= end && (end < to || end == clip_end)).then(|| offset_of(end));
+
+            if on.is_some() && on == off {
+                continue;
+            }
+
+            if let Some(offset) = on {
+                events.push(MidiEvent::new(
+                    offset,
+                    MidiMessage::NoteOn {
+                        channel: note.channel,
+                        key: note.key,
+                        velocity: note.velocity,
+                    },
+                ));
+            }
+            if let Some(offset) = off {
+                events.push(MidiEvent::new(
+                    offset,
+                    MidiMessage::NoteOff {
+                        channel: note.channel,
+                        key: note.key,
+                        velocity: 0,
+                    },
+                ));
+            }
+        }
+
+        let first = self.controls.partition_point(|control| control.time < from);
+        for control in self.controls[first..]
+            .iter()
+            .take_while(|control| control.time < to)
+        {
+            events.push(MidiEvent::new(
+                offset_of(control.time),
+                MidiMessage::ControlChange {
+                    channel: control.channel,
+                    controller: control.controller,
+                    value: control.value,
+                },
+            ));
+        }
+    }
+}
+
+/// Loads a Standard MIDI File, see `parse`
+pub fn load(path: impl AsRef<Path>) -> Result<MidiClip, MidiLoadError> {
+    let bytes = std::fs::read(path).map_err(MidiLoadError::Io)?;
+    parse(&bytes)
+}
+
+/// Parses a Standard MIDI File into a single clip. The tracks of the file are
+/// merged and tempo changes are ignored since positions are kept in beats.
+pub fn parse(bytes: &[u8]) -> Result<MidiClip, MidiLoadError> {
+    let smf = Smf::parse(bytes).map_err(MidiLoadError::Parse)?;
+    let Timing::Metrical(ticks_per_beat) = smf.header.timing else {
+        return Err(MidiLoadError::UnsupportedTiming);
+    };
+    let ticks_per_beat = u64::from(ticks_per_beat.as_int().max(1));
+    let to_musical = |ticks: u64| {
+        MusicalTime::from_total_ticks(ticks * u64::from(SUPER_BEAT_TICKS_PER_BEAT) / ticks_per_beat)
+    };
+
+    let mut notes = Vec::new();
+    let mut controls = Vec::new();
+
+    for track in &smf.tracks {
+        // notes that have been started but not released yet
+        let mut sounding: Vec<(u8, u8, u8, u64)> = Vec::new();
+        let mut ticks = 0;
+
+        for event in track {
+            ticks += u64::from(event.delta.as_int());
+            let TrackEventKind::Midi { channel, message } = event.kind else {
+                continue;
+            };
+            let channel = channel.as_int();
+
+            match message {
+                midly::MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => {
+                    sounding.push((channel, key.as_int(), vel.as_int(), ticks));
+                }
+                midly::MidiMessage::NoteOn { key, .. }
+                | midly::MidiMessage::NoteOff { key, .. } => {
+                    let Some(index) = sounding
+                        .iter()
+                        .position(|&(c, k, _, _)| c == channel && k == key.as_int())
+                    else {
+                        continue;
+                    };
+
+                    let (_, key, velocity, start) = sounding.remove(index);
+                    let start = to_musical(start);
+                    notes.push(Note {
+                        start,
+                        length: to_musical(ticks).checked_sub(start).unwrap_or_default(),
+                        channel,
+                        key,
+                        velocity,
+                    });
+                }
+                midly::MidiMessage::Controller { controller, value } => {
+                    controls.push(ControlChange {
+                        time: to_musical(ticks),
+                        channel,
+                        controller: controller.as_int(),
+                        value: value.as_int(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        // notes without a release last until the end of the track
+        for (channel, key, velocity, start) in sounding {
+            let start = to_musical(start);
+            notes.push(Note {
+                start,
+                length: to_musical(ticks).checked_sub(start).unwrap_or_default(),
+                channel,
+                key,
+                velocity,
+            });
+        }
+    }
+
+    Ok(MidiClip::new(notes, controls))
+}
+
+#[cfg(test)]
+mod tests {
+    use audio_graph::event::{MidiEvent, MidiMessage};
+    use time::{FrameTime, MusicalTime, SampleRate};
+
+    use crate::{
+        midi::{MidiClip, Note, parse},
+        transport::SubBlock,
+    };
+
+    #[test]
+    fn parses_notes_and_controls() {
+        #[rustfmt::skip]
+        let bytes = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 17,
+            0x00, 0x90, 60, 100,
+            0x30, 0xB0, 7, 64,
+            0x30, 0x90, 60, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+
+        let clip = parse(&bytes).unwrap();
+        assert_eq!(
+            clip.notes(),
+            &[Note::new(
+                MusicalTime::ZERO,
+                MusicalTime::from_beats(1),
+                60,
+                100
+            )]
+        );
+        assert_eq!(clip.controls()[0].time, MusicalTime::from_half_beats(0, 1));
+        assert_eq!(clip.length(), MusicalTime::from_beats(1));
+    }
+
+    #[test]
+    fn notes_are_released_at_the_end_of_the_clip() {
+        let clip = MidiClip::new(
+            vec![
+                Note::new(MusicalTime::ZERO, MusicalTime::from_beats(1), 60, 100),
+                Note::new(
+                    MusicalTime::from_beats(1),
+                    MusicalTime::from_beats(4),
+                    62,
+                    100,
+                ),
+            ],
+            Vec::new(),
+        );
+        // at 60 bpm and 100 Hz a beat lasts 100 frames
+        let sub_block = SubBlock {
+            offset: FrameTime(0),
+            frames: FrameTime(400),
+            range: MusicalTime::from_beats(1)..MusicalTime::from_beats(5),
+        };
+
+        let mut events = Vec::new();
+        let range = MusicalTime::from_beats(1)..MusicalTime::from_beats(3);
+        clip.collect_events(
+            &range,
+            &sub_block,
+            60.0,
+            SampleRate::new(100.0),
+            &mut events,
+        );
+
+        let off = |offset, key| {
+            MidiEvent::new(
+                offset,
+                MidiMessage::NoteOff {
+                    channel: 0,
+                    key,
+                    velocity: 0,
+                },
+            )
+        };
+        let on = |offset, key| {
+            MidiEvent::new(
+                offset,
+                MidiMessage::NoteOn {
+                    channel: 0,
+                    key,
+                    velocity: 100,
+                },
+            )
+        };
+        assert_eq!(
+            events,
+            vec![on(0, 60), off(100, 60), on(100, 62), off(200, 62)]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use audio_buffer::{
+    buffers::compatability::slice::WrapInterleavedMut,
+    core::{Buffer, BufferMut},
+    dasp::Sample,
+    kernels,
+};
+pub use audio_graph::processor::pan::PanLaw;
+use audio_graph::{processor::ramp_frames, smoothing::SmoothedValue};
+use time::SampleRate;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MixerSettings {
+    /// Linear fader gain
+    pub gain: f32,
+    /// Pan position from `-1.0` (left) to `1.0` (right)
+    pub pan: f32,
+    pub pan_law: PanLaw,
+    pub mute: bool,
+    pub solo: bool,
+}
+
+impl Default for MixerSettings {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            pan: 0.0,
+            pan_law: PanLaw::default(),
+            mute: false,
+            solo: false,
+        }
+    }
+}
+
+/// A single control of a mixer strip together with its value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MixerControl {
+    Gain(f32),
+    Pan(f32),
+    PanLaw(PanLaw),
+    Mute(bool),
+    Solo(bool),
+}
+
+impl MixerControl {
+    /// Gains have to be finite and positive, pans inside of `-1.0..=1.0`
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            MixerControl::Gain(gain) => gain.is_finite() && gain >= 0.0,
+            MixerControl::Pan(pan) => (-1.0..=1.0).contains(&pan),
+            _ => true,
+        }
+    }
+}
+
+impl MixerSettings {
+    /// Sets a control and returns its previous value
+    pub fn apply(&mut self, control: MixerControl) -> MixerControl {
+        match control {
+            MixerControl::Gain(gain) => MixerControl::Gain(std::mem::replace(&mut self.gain, gain)),
+            MixerControl::Pan(pan) => MixerControl::Pan(std::mem::replace(&mut self.pan, pan)),
+            MixerControl::PanLaw(law) => {
+                MixerControl::PanLaw(std::mem::replace(&mut self.pan_law, law))
+            }
+            MixerControl::Mute(mute) => MixerControl::Mute(std::mem::replace(&mut self.mute, mute)),
+            MixerControl::Solo(solo) => MixerControl::Solo(std::mem::replace(&mut self.solo, solo)),
+        }
+    }
+}
+
+/// Fader, panner, mute and solo that are applied to the output of a track.
+pub struct MixerStrip {
+    settings: MixerSettings,
+    // muted because other tracks are soloed
+    solo_muted: bool,
+
+    // the fader gain including mutes
+    gain: SmoothedValue,
+    pan: SmoothedValue,
+}
+
+impl MixerStrip {
+    pub fn new(sample_rate: SampleRate) -> Self {
+        let ramp_frames = ramp_frames(sample_rate);
+        let settings = MixerSettings::default();
+
+        Self {
+            settings,
+            solo_muted: false,
+            gain: SmoothedValue::new(settings.gain, ramp_frames),
+            pan: SmoothedValue::new(settings.pan, ramp_frames),
+        }
+    }
+
+    pub fn settings(&self) -> &MixerSettings {
+        &self.settings
+    }
+
+    pub fn set(&mut self, control: MixerControl) {
+        self.settings.apply(control);
+        self.settings.pan = self.settings.pan.clamp(-1.0, 1.0);
+
+        self.update_gain();
+        if self.settings.pan != self.pan.target() {
+            self.pan.set_target(self.settings.pan);
+        }
+    }
+
+    pub fn is_solo_muted(&self) -> bool {
+        self.solo_muted
+    }
+
+    pub fn set_solo_muted(&mut self, solo_muted: bool) {
+        if self.solo_muted != solo_muted {
+            self.solo_muted = solo_muted;
+            self.update_gain();
+        }
+    }
+
+    fn update_gain(&mut self) {
+        let target = if self.settings.mute || self.solo_muted {
+            0.0
+        } else {
+            self.settings.gain
+        };
+
+        if target != self.gain.target() {
+            self.gain.set_target(target);
+        }
+    }
+
+    /// Applies gain and pan to `buffer`. Panning only affects stereo buffers.
+    pub fn process<T: Sample + 'static>(&mut self, buffer: &mut WrapInterleavedMut<'_, T>) {
+        let law = self.settings.pan_law;
+        let stereo = buffer.channels() == 2;
+
+        if !self.gain.is_smoothing() && !self.pan.is_smoothing() {
+            let gain = self.gain.current();
+            let (left, right) = if stereo {
+                law.gains(self.pan.current())
+            } else {
+                (1.0, 1.0)
+            };
+
+            if gain == 1.0 && left == 1.0 && right == 1.0 {
+                return;
+            }
+
+            // without a pan every sample gets the same gain
+            if left == right {
+                let samples = buffer
+                    .as_interleaved_mut()
+                    .expect("the buffer is interleaved");
+                if gain == 0.0 {
+                    kernels::fill(samples, T::EQUILIBRIUM);
+                } else {
+                    kernels::apply_gain(samples, gain * left);
+                }
+                return;
+            }
+
+            buffer.map_frames_mut(
+                |frame, _| {
+                    apply_gains(frame, gain, left, right, stereo);
+                    Some(())
+                },
+                None,
+            );
+            return;
+        }
+
+        buffer.map_frames_mut(
+            |frame, _| {
+                let gain = self.gain.next_value();
+                let (left, right) = law.gains(self.pan.next_value());
+                apply_gains(frame, gain, left, right, stereo);
+                Some(())
+            },
+            None,
+        );
+    }
+}
+
+fn apply_gains<T: Sample>(frame: &mut [T], gain: f32, left: f32, right: f32, stereo: bool) {
+    if stereo {
+        frame[0] = frame[0].mul_amp(T::Float::from_sample(gain * left));
+        frame[1] = frame[1].mul_amp(T::Float::from_sample(gain * right));
+    } else {
+        let gain = T::Float::from_sample(gain);
+        for sample in frame.iter_mut() {
+            *sample = sample.mul_amp(gain);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use audio_buffer::buffers::compatability::slice::WrapInterleavedMut;
+    use audio_graph::processor::ramp_frames;
+    use time::SampleRate;
+
+    use crate::mixer::{MixerControl, MixerStrip, PanLaw};
+
+    fn sample_rate() -> SampleRate {
+        SampleRate::new(1000.0)
+    }
+
+    // runs `frames` of a constant stereo signal through the strip
+    fn process(strip: &mut MixerStrip, frames: usize) -> Vec<f32> {
+        let mut data = vec![1.0f32; frames * 2];
+        strip.process(&mut WrapInterleavedMut::new(&mut data, 2));
+        data
+    }
+
+    // lets the ramps of earlier changes finish
+    fn settle(strip: &mut MixerStrip) {
+        process(strip, ramp_frames(sample_rate()) + 1);
+    }
+
+    #[test]
+    fn gain_changes_are_ramped() {
+        let mut strip = MixerStrip::new(sample_rate());
+        strip.set(MixerControl::Gain(0.5));
+
+        let ramp = ramp_frames(sample_rate());
+        let data = process(&mut strip, ramp + 4);
+        let left: Vec<f32> = data.iter().step_by(2).copied().collect();
+
+        // every step is smaller than the whole jump and goes the same way
+        assert!(left.windows(2).all(|w| w[1] <= w[0] && w[0] - w[1] < 0.1));
+        assert!(left[0] > 0.5);
+        assert!(left[ramp..].iter().all(|&sample| sample == 0.5));
+    }
+
+    #[test]
+    fn mute_and_solo_mute_silence_the_track() {
+        let mut strip = MixerStrip::new(sample_rate());
+        strip.set(MixerControl::Mute(true));
+        settle(&mut strip);
+        assert!(process(&mut strip, 8).iter().all(|&sample| sample == 0.0));
+
+        strip.set(MixerControl::Mute(false));
+        settle(&mut strip);
+        assert!(process(&mut strip, 8).iter().all(|&sample| sample == 1.0));
+
+        strip.set_solo_muted(true);
+        settle(&mut strip);
+        assert!(process(&mut strip, 8).iter().all(|&sample| sample == 0.0));
+
+        // the fader is kept while the track is muted
+        strip.set(MixerControl::Gain(0.25));
+        strip.set_solo_muted(false);
+        settle(&mut strip);
+        assert!(process(&mut strip, 8).iter().all(|&sample| sample == 0.25));
+    }
+
+    #[test]
+    fn pans_with_the_selected_law() {
+        let cases = [
+            (PanLaw::Linear, 0.0, (0.5, 0.5)),
+            (PanLaw::ConstantPower, 0.0, (0.5f32.sqrt(), 0.5f32.sqrt())),
+            (PanLaw::Balance, 0.0, (1.0, 1.0)),
+            (PanLaw::Balance, 0.5, (0.5, 1.0)),
+            (PanLaw::ConstantPower, -1.0, (1.0, 0.0)),
+        ];
+
+        for (law, pan, (left, right)) in cases {
+            let mut strip = MixerStrip::new(sample_rate());
+            strip.set(MixerControl::PanLaw(law));
+            strip.set(MixerControl::Pan(pan));
+            settle(&mut strip);
+
+            let data = process(&mut strip, 4);
+            for frame in data.chunks(2) {
+                assert!((frame[0] - left).abs() < 1e-6, "{law:?} at {pan}");
+                assert!((frame[1] - right).abs() < 1e-6, "{law:?} at {pan}");
+            }
+        }
+    }
+
+    #[test]
+    fn mono_tracks_arent_panned() {
+        let mut strip = MixerStrip::new(sample_rate());
+        strip.set(MixerControl::Pan(1.0));
+        strip.set(MixerControl::Gain(0.5));
+
+        let mut data = vec![1.0f32; ramp_frames(sample_rate()) + 4];
+        strip.process(&mut WrapInterleavedMut::new(&mut data, 1));
+        assert_eq!(data.last(), Some(&0.5));
+    }
+}
@@ -0,0 +1,939 @@
+use std::ops::Range;
+
+use audio_graph::{
+    Connection, Tap,
+    daggy::{
+        Dag, EdgeIndex, NodeIndex,
+        petgraph::{self, Direction, visit::EdgeRef},
+    },
+    parameter::{ParameterDescriptor, ParameterId},
+    pin_matrix::PinMatrix,
+    processor::ProcessorConfiguration,
+};
+use time::MusicalTime;
+
+use crate::{
+    automation::AutomationLane,
+    input::InputChannels,
+    midi::MidiClip,
+    mixer::{MixerControl, MixerSettings},
+    playlist::{Clip, Playlist},
+    track::{SendTap, TrackKind},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    InvalidTrack(NodeIndex),
+    InvalidProcessor(NodeIndex),
+    InvalidEdge(EdgeIndex),
+    /// The connection was removed and isn't part of the graph anymore
+    InvalidConnection(ConnectionId),
+    /// The matrix doesn't match the output channels of the source
+    /// or the input channels of the destination
+    InvalidPinMatrix,
+    WouldCycle,
+    InvalidRange(Range<MusicalTime>),
+    ClipNotFound(Range<MusicalTime>),
+    MidiClipNotFound(Range<MusicalTime>),
+    InvalidMixerControl(MixerControl),
+    InvalidParameter(ParameterId),
+    InvalidParameterValue(f32),
+    /// Buses don't have a playlist
+    NotAnAudioTrack(NodeIndex),
+    /// Sends and routed tracks can only feed the master track or a bus
+    NotABus(NodeIndex),
+    InvalidSendGain(f32),
+    /// The track is armed but no input channels were assigned to it
+    NoInput(NodeIndex),
+    /// The track can't be removed because the history couldn't restore its processors
+    ProcessorsNotRecorded(NodeIndex),
+}
+
+/// Identifies a connection between two tracks for as long as it exists.
+///
+/// Removing an edge moves the last edge into its index, so the history stores
+/// these ids instead of edge indices. A connection that the history re-adds
+/// gets its old id back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionId(u64);
+
+/// Control-side view of a processor inside a track.
+#[derive(Debug, Clone)]
+pub struct ProcessorModel {
+    config: ProcessorConfiguration,
+    parameters: Vec<ParameterDescriptor>,
+    // the value of each parameter in the same order as `parameters`
+    values: Vec<f32>,
+}
+
+impl ProcessorModel {
+    pub fn new(config: ProcessorConfiguration) -> Self {
+        Self {
+            config,
+            parameters: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn with_parameters(
+        config: ProcessorConfiguration,
+        parameters: Vec<ParameterDescriptor>,
+        values: Vec<f32>,
+    ) -> Self {
+        debug_assert_eq!(parameters.len(), values.len());
+        Self {
+            config,
+            parameters,
+            values,
+        }
+    }
+
+    pub fn config(&self) -> ProcessorConfiguration {
+        self.config
+    }
+
+    pub fn parameters(&self) -> &[ParameterDescriptor] {
+        &self.parameters
+    }
+
+    pub fn parameter(&self, id: ParameterId) -> Option<f32> {
+        self.position(id).map(|index| self.values[index])
+    }
+
+    fn position(&self, id: ParameterId) -> Option<usize> {
+        self.parameters
+            .iter()
+            .position(|descriptor| descriptor.id == id)
+    }
+}
+
+/// Control-side view of a `Track`.
+///
+/// The processor graph is built with the same operations in the same order as
+/// the track on the audio thread, so node and edge indices are identical on both sides.
+pub struct TrackModel<T> {
+    graph: Dag<ProcessorModel, PinMatrix>,
+    kind: TrackKind,
+    input_channels: Option<InputChannels>,
+    monitoring: bool,
+    armed: bool,
+    playlist: Playlist<T>,
+    mixer: MixerSettings,
+    // muted because other tracks are soloed
+    solo_muted: bool,
+    automation: Vec<AutomationLane>,
+    // processors were added or connected, the history can't recreate those
+    processors_edited: bool,
+    input: NodeIndex,
+    output: NodeIndex,
+}
+
+impl<T> TrackModel<T> {
+    /// Mirrors `Track::from_config` and `Track::bus`
+    fn stereo(kind: TrackKind) -> Self {
+        let stereo = ProcessorConfiguration {
+            num_input_channels: 2,
+            num_output_channels: 2,
+        };
+
+        let mut graph = Dag::new();
+        let input = graph.add_node(ProcessorModel::new(stereo));
+        let output = graph.add_node(ProcessorModel::new(stereo));
+        graph
+            .add_edge(input, output, PinMatrix::diagonal(2, 2))
+            .expect("both nodes are new");
+
+        Self {
+            graph,
+            kind,
+            input_channels: None,
+            monitoring: false,
+            armed: false,
+            playlist: Playlist::empty(),
+            mixer: MixerSettings::default(),
+            solo_muted: false,
+            automation: Vec::new(),
+            processors_edited: false,
+            input,
+            output,
+        }
+    }
+
+    pub fn input_index(&self) -> NodeIndex {
+        self.input
+    }
+
+    pub fn output_index(&self) -> NodeIndex {
+        self.output
+    }
+
+    pub fn kind(&self) -> TrackKind {
+        self.kind
+    }
+
+    /// The channels of the input device the track records and monitors
+    pub fn input_channels(&self) -> Option<InputChannels> {
+        self.input_channels
+    }
+
+    pub fn is_monitoring(&self) -> bool {
+        self.monitoring
+    }
+
+    /// Whether the track records when recording is started
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    pub fn playlist(&self) -> &Playlist<T> {
+        &self.playlist
+    }
+
+    pub fn mixer(&self) -> &MixerSettings {
+        &self.mixer
+    }
+
+    /// Whether the track is muted because other tracks are soloed
+    pub fn is_solo_muted(&self) -> bool {
+        self.solo_muted
+    }
+
+    pub fn automation_lane(
+        &self,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Option<&AutomationLane> {
+        self.automation
+            .iter()
+            .find(|lane| lane.processor() == processor && lane.parameter() == parameter)
+    }
+
+    pub fn automation_lanes(&self) -> &[AutomationLane] {
+        &self.automation
+    }
+
+    /// Whether processors were added to the track or connected differently.
+    /// Those edits aren't recorded, so the track can't be removed by an undo.
+    pub fn has_processor_edits(&self) -> bool {
+        self.processors_edited
+    }
+
+    pub fn processor(&self, index: NodeIndex) -> Option<&ProcessorModel> {
+        self.graph.node_weight(index)
+    }
+
+    pub fn processors(&self) -> impl Iterator<Item = (NodeIndex, &ProcessorModel)> {
+        self.graph
+            .graph()
+            .node_indices()
+            .map(|index| (index, &self.graph[index]))
+    }
+
+    pub fn connection(&self, edge: EdgeIndex) -> Option<(NodeIndex, NodeIndex, &PinMatrix)> {
+        connection(&self.graph, edge)
+    }
+
+    pub fn connections(
+        &self,
+    ) -> impl Iterator<Item = (EdgeIndex, NodeIndex, NodeIndex, &PinMatrix)> {
+        connections(&self.graph)
+    }
+
+    pub fn config(&self) -> ProcessorConfiguration {
+        ProcessorConfiguration {
+            num_input_channels: self.graph[self.input].config.num_input_channels,
+            num_output_channels: self.graph[self.output].config.num_output_channels,
+        }
+    }
+}
+
+/// Control-side mirror of the state owned by the `AudioBackend`: tracks, their
+/// processors and connections, the top-level routing and the playlists.
+///
+/// Every change is validated up front and the model is only changed once the
+/// backend accepted the command, so both stay in sync even if it rejects one.
+pub struct EngineModel<T> {
+    graph: Dag<TrackModel<T>, Connection>,
+    master: NodeIndex,
+    // the id of every connection, by edge index
+    connection_ids: Vec<ConnectionId>,
+    next_connection_id: u64,
+}
+
+impl<T> Default for EngineModel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EngineModel<T> {
+    pub fn new() -> Self {
+        let mut graph = Dag::new();
+        let master = graph.add_node(TrackModel::stereo(TrackKind::Bus));
+
+        Self {
+            graph,
+            master,
+            connection_ids: Vec::new(),
+            next_connection_id: 0,
+        }
+    }
+
+    pub fn master_index(&self) -> NodeIndex {
+        self.master
+    }
+
+    pub fn track(&self, index: NodeIndex) -> Option<&TrackModel<T>> {
+        self.graph.node_weight(index)
+    }
+
+    /// Iterates over all tracks including the master track
+    pub fn tracks(&self) -> impl Iterator<Item = (NodeIndex, &TrackModel<T>)> {
+        self.graph
+            .graph()
+            .node_indices()
+            .map(|index| (index, &self.graph[index]))
+    }
+
+    pub fn connection(&self, edge: EdgeIndex) -> Option<(NodeIndex, NodeIndex, &Connection)> {
+        connection(&self.graph, edge)
+    }
+
+    pub fn connections(
+        &self,
+    ) -> impl Iterator<Item = (EdgeIndex, NodeIndex, NodeIndex, &Connection)> {
+        connections(&self.graph)
+    }
+
+    pub fn connection_id(&self, edge: EdgeIndex) -> Option<ConnectionId> {
+        self.connection_ids.get(edge.index()).copied()
+    }
+
+    /// The current edge index of a connection
+    pub fn connection_edge(&self, id: ConnectionId) -> Option<EdgeIndex> {
+        self.connection_ids
+            .iter()
+            .position(|&other| other == id)
+            .map(EdgeIndex::new)
+    }
+
+    /// The connections that carry the output of a track after its fader, as
+    /// opposed to its pre-fader sends, in the order they are removed by `route_track`
+    pub fn outputs(&self, track: NodeIndex) -> Vec<EdgeIndex> {
+        let mut edges: Vec<EdgeIndex> = self
+            .graph
+            .graph()
+            .edges_directed(track, Direction::Outgoing)
+            .filter(|edge| edge.weight().tap() == Tap::Main)
+            .map(|edge| edge.id())
+            .collect();
+        // removing an edge moves the last edge into its index,
+        // so the edges are removed from the highest index down
+        edges.sort_by(|a, b| b.cmp(a));
+        edges
+    }
+}
+
+impl<T> EngineModel<T> {
+    /// The tracks whose solo mute doesn't match the current solos and routing.
+    ///
+    /// While any track is soloed, every track that is neither soloed nor routed
+    /// to or from a soloed track is muted. The master track is never muted by solo.
+    pub(crate) fn solo_changes(&self) -> Vec<(NodeIndex, bool)> {
+        let graph = self.graph.graph();
+        let soloed: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|&index| index != self.master && self.graph[index].mixer.solo)
+            .collect();
+
+        graph
+            .node_indices()
+            .filter(|&index| index != self.master)
+            .filter_map(|index| {
+                let audible = soloed.is_empty()
+                    || soloed.iter().any(|&solo| {
+                        index == solo
+                            || petgraph::algo::has_path_connecting(graph, index, solo, None)
+                            || petgraph::algo::has_path_connecting(graph, solo, index, None)
+                    });
+                (self.graph[index].solo_muted == audible).then_some((index, !audible))
+            })
+            .collect()
+    }
+
+    pub(crate) fn validate_connection(
+        &self,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: &PinMatrix,
+    ) -> Result<(), ModelError> {
+        validate_edge(
+            &self.graph,
+            source,
+            destination,
+            matrix,
+            TrackModel::config,
+            ModelError::InvalidTrack,
+        )
+    }
+
+    pub(crate) fn validate_connection_update(
+        &self,
+        edge: EdgeIndex,
+        matrix: &PinMatrix,
+    ) -> Result<(), ModelError> {
+        validate_edge_update(&self.graph, edge, matrix, TrackModel::config)
+    }
+
+    pub(crate) fn validate_track(&self, track: NodeIndex) -> Result<&TrackModel<T>, ModelError> {
+        self.graph
+            .node_weight(track)
+            .ok_or(ModelError::InvalidTrack(track))
+    }
+
+    /// Sends and routed tracks can only feed the master track or a bus
+    pub(crate) fn validate_bus(&self, bus: NodeIndex) -> Result<(), ModelError> {
+        if self.validate_track(bus)?.kind != TrackKind::Bus {
+            return Err(ModelError::NotABus(bus));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_send(
+        &self,
+        track: NodeIndex,
+        bus: NodeIndex,
+        gain: f32,
+    ) -> Result<(), ModelError> {
+        self.validate_bus(bus)?;
+        validate_send_gain(gain)?;
+        self.validate_connection(track, bus, &PinMatrix::diagonal(2, 2))
+    }
+
+    pub(crate) fn validate_send_update(
+        &self,
+        edge: EdgeIndex,
+        gain: f32,
+    ) -> Result<(), ModelError> {
+        let (_, bus) = self
+            .graph
+            .edge_endpoints(edge)
+            .ok_or(ModelError::InvalidEdge(edge))?;
+        self.validate_bus(bus)?;
+        validate_send_gain(gain)
+    }
+
+    pub(crate) fn validate_route(
+        &self,
+        track: NodeIndex,
+        destination: NodeIndex,
+    ) -> Result<(), ModelError> {
+        if track == self.master {
+            return Err(ModelError::InvalidTrack(track));
+        }
+        self.validate_bus(destination)?;
+        // the outputs that are replaced can't be part of a path from the destination
+        // back to the track, so checking against the current routing is enough
+        self.validate_connection(track, destination, &PinMatrix::diagonal(2, 2))
+    }
+
+    pub(crate) fn validate_track_removal(&self, track: NodeIndex) -> Result<(), ModelError> {
+        if track == self.master {
+            return Err(ModelError::InvalidTrack(track));
+        }
+        if self.validate_track(track)?.has_processor_edits() {
+            return Err(ModelError::ProcessorsNotRecorded(track));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_edge(&self, edge: EdgeIndex) -> Result<(), ModelError> {
+        self.graph
+            .edge_weight(edge)
+            .map(|_| ())
+            .ok_or(ModelError::InvalidEdge(edge))
+    }
+
+    pub(crate) fn validate_processor_connection(
+        &self,
+        track: NodeIndex,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: &PinMatrix,
+    ) -> Result<(), ModelError> {
+        validate_edge(
+            &self.validate_track(track)?.graph,
+            source,
+            destination,
+            matrix,
+            ProcessorModel::config,
+            ModelError::InvalidProcessor,
+        )
+    }
+
+    pub(crate) fn validate_processor_edge(
+        &self,
+        track: NodeIndex,
+        edge: EdgeIndex,
+    ) -> Result<(), ModelError> {
+        self.validate_track(track)?
+            .graph
+            .edge_weight(edge)
+            .map(|_| ())
+            .ok_or(ModelError::InvalidEdge(edge))
+    }
+
+    /// Only audio tracks have an input
+    pub(crate) fn validate_audio_track(&self, track: NodeIndex) -> Result<(), ModelError> {
+        if self.validate_track(track)?.kind != TrackKind::Audio {
+            return Err(ModelError::NotAnAudioTrack(track));
+        }
+        Ok(())
+    }
+
+    /// Returns the armed tracks, which all need an input to be recorded
+    pub(crate) fn validate_recording(&self) -> Result<Vec<NodeIndex>, ModelError> {
+        self.tracks()
+            .filter(|(_, track)| track.armed)
+            .map(|(index, track)| match track.input_channels {
+                Some(_) => Ok(index),
+                None => Err(ModelError::NoInput(index)),
+            })
+            .collect()
+    }
+
+    pub(crate) fn validate_mixer_control(
+        &self,
+        track: NodeIndex,
+        control: MixerControl,
+    ) -> Result<(), ModelError> {
+        self.validate_track(track)?;
+
+        if !control.is_valid() {
+            return Err(ModelError::InvalidMixerControl(control));
+        }
+        Ok(())
+    }
+
+    /// Returns the value clamped to the range of the parameter
+    pub(crate) fn validate_parameter(
+        &self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    ) -> Result<f32, ModelError> {
+        let processor = self
+            .validate_track(track)?
+            .processor(processor)
+            .ok_or(ModelError::InvalidProcessor(processor))?;
+        let index = processor
+            .position(parameter)
+            .ok_or(ModelError::InvalidParameter(parameter))?;
+
+        if !value.is_finite() {
+            return Err(ModelError::InvalidParameterValue(value));
+        }
+        Ok(processor.parameters[index].clamp(value))
+    }
+
+    pub(crate) fn validate_automation_lane(
+        &self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Result<(), ModelError> {
+        self.validate_track(track)?
+            .processor(processor)
+            .ok_or(ModelError::InvalidProcessor(processor))?
+            .position(parameter)
+            .map(|_| ())
+            .ok_or(ModelError::InvalidParameter(parameter))
+    }
+
+    pub(crate) fn validate_clip_insert(
+        &self,
+        track: NodeIndex,
+        range: &Range<MusicalTime>,
+    ) -> Result<(), ModelError> {
+        self.validate_audio_track(track)?;
+
+        if range.start >= range.end {
+            return Err(ModelError::InvalidRange(range.clone()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_clip_removal(
+        &self,
+        track: NodeIndex,
+        range: &Range<MusicalTime>,
+    ) -> Result<(), ModelError> {
+        self.validate_track(track)?
+            .playlist
+            .get(range.clone())
+            .map(|_| ())
+            .ok_or(ModelError::ClipNotFound(range.clone()))
+    }
+
+    pub(crate) fn validate_midi_clip_removal(
+        &self,
+        track: NodeIndex,
+        range: &Range<MusicalTime>,
+    ) -> Result<(), ModelError> {
+        self.validate_track(track)?
+            .playlist
+            .get_midi(range.clone())
+            .map(|_| ())
+            .ok_or(ModelError::MidiClipNotFound(range.clone()))
+    }
+}
+
+// The following methods mirror the handlers in `AudioBackend` and
+// must only be called after the change has been validated.
+impl<T> EngineModel<T> {
+    pub(crate) fn add_track(&mut self, kind: TrackKind) -> NodeIndex {
+        let index = self.graph.add_node(TrackModel::stereo(kind));
+        self.add_connection(
+            index,
+            self.master,
+            Connection::new(PinMatrix::diagonal(2, 2)),
+        );
+        index
+    }
+
+    pub(crate) fn remove_track(&mut self, track: NodeIndex) -> Option<TrackModel<T>> {
+        while let Some(edge) = first_connection(&self.graph, track) {
+            self.remove_connection(edge);
+        }
+        self.graph.remove_node(track)
+    }
+
+    pub(crate) fn add_connection(
+        &mut self,
+        source: NodeIndex,
+        destination: NodeIndex,
+        connection: Connection,
+    ) -> EdgeIndex {
+        let edge = self
+            .graph
+            .add_edge(source, destination, connection)
+            .expect("connection was validated");
+        self.connection_ids
+            .push(ConnectionId(self.next_connection_id));
+        self.next_connection_id += 1;
+        edge
+    }
+
+    /// Gives a connection the id it had before it was removed
+    pub(crate) fn restore_connection_id(&mut self, edge: EdgeIndex, id: ConnectionId) {
+        self.connection_ids[edge.index()] = id;
+    }
+
+    pub(crate) fn update_connection(&mut self, edge: EdgeIndex, matrix: PinMatrix) -> PinMatrix {
+        let connection = &mut self.graph[edge];
+        let old = connection.matrix().clone();
+        *connection = Connection::new(matrix)
+            .with_tap(connection.tap())
+            .with_gain(connection.gain());
+        old
+    }
+
+    /// Returns the previous tap and gain
+    pub(crate) fn set_send(&mut self, edge: EdgeIndex, tap: SendTap, gain: f32) -> (SendTap, f32) {
+        let connection = &mut self.graph[edge];
+        let old = (SendTap::from_tap(connection.tap()), connection.gain());
+        *connection = Connection::new(connection.matrix().clone())
+            .with_tap(tap.tap())
+            .with_gain(gain);
+        old
+    }
+
+    /// Returns the endpoints of the removed connection
+    pub(crate) fn remove_connection(
+        &mut self,
+        edge: EdgeIndex,
+    ) -> Option<(NodeIndex, NodeIndex, Connection)> {
+        let (source, destination) = self.graph.edge_endpoints(edge)?;
+        let connection = self.graph.remove_edge(edge)?;
+        // mirrors the graph, which moves the last edge into the freed index
+        self.connection_ids.swap_remove(edge.index());
+        Some((source, destination, connection))
+    }
+
+    /// Returns the previous value of the control
+    pub(crate) fn set_mixer_control(
+        &mut self,
+        track: NodeIndex,
+        control: MixerControl,
+    ) -> MixerControl {
+        self.graph[track].mixer.apply(control)
+    }
+
+    pub(crate) fn set_solo_muted(&mut self, track: NodeIndex, solo_muted: bool) {
+        self.graph[track].solo_muted = solo_muted;
+    }
+
+    pub(crate) fn set_input_channels(&mut self, track: NodeIndex, input: Option<InputChannels>) {
+        self.graph[track].input_channels = input;
+    }
+
+    pub(crate) fn set_monitoring(&mut self, track: NodeIndex, monitoring: bool) {
+        self.graph[track].monitoring = monitoring;
+    }
+
+    pub(crate) fn set_armed(&mut self, track: NodeIndex, armed: bool) {
+        self.graph[track].armed = armed;
+    }
+
+    pub(crate) fn add_processor(
+        &mut self,
+        track: NodeIndex,
+        processor: ProcessorModel,
+    ) -> NodeIndex {
+        let track = &mut self.graph[track];
+        track.processors_edited = true;
+        track.graph.add_node(processor)
+    }
+
+    /// Returns the previous value of the parameter
+    pub(crate) fn set_parameter(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        value: f32,
+    ) -> f32 {
+        let processor = &mut self.graph[track].graph[processor];
+        let index = processor
+            .position(parameter)
+            .expect("parameter was validated");
+        std::mem::replace(&mut processor.values[index], value)
+    }
+
+    /// Replaces or removes the lane of a parameter and returns the previous one
+    pub(crate) fn set_automation_lane(
+        &mut self,
+        track: NodeIndex,
+        processor: NodeIndex,
+        parameter: ParameterId,
+        lane: Option<AutomationLane>,
+    ) -> Option<AutomationLane> {
+        let automation = &mut self.graph[track].automation;
+        let old = automation
+            .iter()
+            .position(|lane| lane.processor() == processor && lane.parameter() == parameter)
+            .map(|index| automation.remove(index));
+
+        automation.extend(lane);
+        old
+    }
+
+    pub(crate) fn add_processor_connection(
+        &mut self,
+        track: NodeIndex,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    ) -> EdgeIndex {
+        let track = &mut self.graph[track];
+        track.processors_edited = true;
+        track
+            .graph
+            .add_edge(source, destination, matrix)
+            .expect("connection was validated")
+    }
+
+    pub(crate) fn remove_processor_connection(
+        &mut self,
+        track: NodeIndex,
+        edge: EdgeIndex,
+    ) -> Option<PinMatrix> {
+        let track = &mut self.graph[track];
+        track.processors_edited = true;
+        track.graph.remove_edge(edge)
+    }
+
+    pub(crate) fn insert_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: Clip<T>,
+    ) -> Option<Clip<T>> {
+        self.graph[track].playlist.insert(range, clip)
+    }
+
+    pub(crate) fn remove_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> Option<Clip<T>> {
+        self.graph[track].playlist.remove(range)
+    }
+
+    pub(crate) fn insert_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+        clip: MidiClip,
+    ) -> Option<MidiClip> {
+        self.graph[track].playlist.insert_midi(range, clip)
+    }
+
+    pub(crate) fn remove_midi_clip(
+        &mut self,
+        track: NodeIndex,
+        range: Range<MusicalTime>,
+    ) -> Option<MidiClip> {
+        self.graph[track].playlist.remove_midi(range)
+    }
+}
+
+/// Returns any connection `node` takes part in. Removing them one by one in this
+/// order gives the same edge indices on the audio thread and on the control side.
+pub(crate) fn first_connection<N, E>(dag: &Dag<N, E>, node: NodeIndex) -> Option<EdgeIndex> {
+    let graph = dag.graph();
+    graph
+        .first_edge(node, Direction::Outgoing)
+        .or_else(|| graph.first_edge(node, Direction::Incoming))
+}
+
+fn connection<N, E>(dag: &Dag<N, E>, edge: EdgeIndex) -> Option<(NodeIndex, NodeIndex, &E)> {
+    let (source, destination) = dag.edge_endpoints(edge)?;
+    Some((source, destination, dag.edge_weight(edge)?))
+}
+
+fn connections<N, E>(
+    dag: &Dag<N, E>,
+) -> impl Iterator<Item = (EdgeIndex, NodeIndex, NodeIndex, &E)> {
+    dag.graph().edge_indices().filter_map(|edge| {
+        let (source, destination, matrix) = connection(dag, edge)?;
+        Some((edge, source, destination, matrix))
+    })
+}
+
+// Performs the same checks as `AudioGraph::add_connection` without touching the graph
+fn validate_edge<N, E>(
+    dag: &Dag<N, E>,
+    source: NodeIndex,
+    destination: NodeIndex,
+    matrix: &PinMatrix,
+    config: impl Fn(&N) -> ProcessorConfiguration,
+    invalid_node: impl Fn(NodeIndex) -> ModelError,
+) -> Result<(), ModelError> {
+    let source_config = config(dag.node_weight(source).ok_or(invalid_node(source))?);
+    let destination_config = config(
+        dag.node_weight(destination)
+            .ok_or(invalid_node(destination))?,
+    );
+
+    if matrix.input_channels() != source_config.num_output_channels
+        || matrix.output_channels() != destination_config.num_input_channels
+    {
+        return Err(ModelError::InvalidPinMatrix);
+    }
+
+    // a path from the destination back to the source (or a self loop) would close a cycle
+    if petgraph::algo::has_path_connecting(dag.graph(), destination, source, None) {
+        return Err(ModelError::WouldCycle);
+    }
+
+    Ok(())
+}
+
+fn validate_edge_update<N, E>(
+    dag: &Dag<N, E>,
+    edge: EdgeIndex,
+    matrix: &PinMatrix,
+    config: impl Fn(&N) -> ProcessorConfiguration,
+) -> Result<(), ModelError> {
+    let (source, destination) = dag
+        .edge_endpoints(edge)
+        .ok_or(ModelError::InvalidEdge(edge))?;
+
+    if matrix.input_channels() != config(&dag[source]).num_output_channels
+        || matrix.output_channels() != config(&dag[destination]).num_input_channels
+    {
+        return Err(ModelError::InvalidPinMatrix);
+    }
+
+    Ok(())
+}
+
+fn validate_send_gain(gain: f32) -> Result<(), ModelError> {
+    if !gain.is_finite() || gain < 0.0 {
+        return Err(ModelError::InvalidSendGain(gain));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use audio_graph::pin_matrix::PinMatrix;
+
+    use crate::{
+        mixer::MixerControl,
+        model::{EngineModel, ModelError},
+        track::TrackKind,
+    };
+
+    #[test]
+    fn rejects_cycles_and_mismatched_matrices() {
+        let mut model = EngineModel::<f32>::new();
+        let a = model.add_track(TrackKind::Audio);
+        let b = model.add_track(TrackKind::Audio);
+
+        assert!(
+            model
+                .validate_connection(a, b, &PinMatrix::diagonal(2, 2))
+                .is_ok()
+        );
+        model.add_connection(a, b, PinMatrix::diagonal(2, 2).into());
+
+        assert_eq!(
+            model.validate_connection(b, a, &PinMatrix::diagonal(2, 2)),
+            Err(ModelError::WouldCycle)
+        );
+        assert_eq!(
+            model.validate_connection(model.master_index(), a, &PinMatrix::diagonal(2, 2)),
+            Err(ModelError::WouldCycle)
+        );
+        assert_eq!(
+            model.validate_connection(b, model.master_index(), &PinMatrix::diagonal(1, 2)),
+            Err(ModelError::InvalidPinMatrix)
+        );
+    }
+
+    #[test]
+    fn solo_mutes_tracks_that_dont_feed_or_follow_a_soloed_track() {
+        let mut model = EngineModel::<f32>::new();
+        let solo = model.add_track(TrackKind::Audio);
+        let other = model.add_track(TrackKind::Audio);
+        let group = model.add_track(TrackKind::Bus);
+        let reverb = model.add_track(TrackKind::Bus);
+
+        // the soloed track plays through the group, which feeds a reverb
+        for edge in model.outputs(solo) {
+            model.remove_connection(edge);
+        }
+        model.add_connection(solo, group, PinMatrix::diagonal(2, 2).into());
+        model.add_connection(group, reverb, PinMatrix::diagonal(2, 2).into());
+        assert!(model.solo_changes().is_empty());
+
+        model.set_mixer_control(solo, MixerControl::Solo(true));
+        assert_eq!(model.solo_changes(), vec![(other, true)]);
+        model.set_solo_muted(other, true);
+        assert!(model.solo_changes().is_empty());
+
+        // soloing the other track as well makes it audible again
+        model.set_mixer_control(other, MixerControl::Solo(true));
+        assert_eq!(model.solo_changes(), vec![(other, false)]);
+        model.set_solo_muted(other, false);
+
+        model.set_mixer_control(solo, MixerControl::Solo(false));
+        assert_eq!(
+            model.solo_changes(),
+            vec![(solo, true), (group, true), (reverb, true)]
+        );
+    }
+}
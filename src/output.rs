@@ -0,0 +1,255 @@
+use std::num::NonZeroUsize;
+
+use audio_buffer::{SharedSample, dasp::Sample};
+use audio_graph::daggy::NodeIndex;
+
+/// The stereo mixes that can be played on the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputSource {
+    Master,
+    /// A bus that is played on its own outputs, e.g. a headphone mix
+    Cue(NodeIndex),
+}
+
+/// The channels of the output device a stereo mix is played on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputChannels {
+    /// Both sides are mixed down to a single channel
+    Mono(usize),
+    Stereo(usize, usize),
+}
+
+impl OutputChannels {
+    fn highest(self) -> usize {
+        match self {
+            OutputChannels::Mono(channel) => channel,
+            OutputChannels::Stereo(left, right) => left.max(right),
+        }
+    }
+
+    /// Adds a stereo frame to the channels of a device frame
+    fn mix<T: SharedSample>(self, frame: &[T], device_frame: &mut [T]) {
+        let mut add = |channel: usize, sample: T| {
+            let out = &mut device_frame[channel];
+            *out = out.add_amp(sample.to_signed_sample());
+        };
+
+        match self {
+            OutputChannels::Mono(channel) => {
+                let half = T::Float::from_sample(0.5f32);
+                add(channel, frame[0].mul_amp(half));
+                add(channel, frame[1].mul_amp(half));
+            }
+            OutputChannels::Stereo(left, right) => {
+                add(left, frame[0]);
+                add(right, frame[1]);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputRoute {
+    pub source: OutputSource,
+    pub channels: OutputChannels,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputError {
+    /// The device doesn't have the channel
+    InvalidChannel(usize),
+    /// The map was made for a device with a different number of channels
+    DeviceChannels(NonZeroUsize),
+}
+
+/// Maps the master track and cue mixes to the channels of the output device.
+/// Routes that share a device channel are summed, channels without a route are silent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputMap {
+    device_channels: NonZeroUsize,
+    routes: Vec<OutputRoute>,
+}
+
+impl OutputMap {
+    /// Plays master on the first two outputs, or mixed down on a mono device
+    pub fn new(device_channels: NonZeroUsize) -> Self {
+        let channels = match device_channels.get() {
+            1 => OutputChannels::Mono(0),
+            _ => OutputChannels::Stereo(0, 1),
+        };
+
+        Self {
+            device_channels,
+            routes: vec![OutputRoute {
+                source: OutputSource::Master,
+                channels,
+            }],
+        }
+    }
+
+    /// Plays master on every pair of outputs. An odd last output gets the mono mix.
+    pub fn upmixed(device_channels: NonZeroUsize) -> Self {
+        let channels = device_channels.get();
+        let routes = (0..channels)
+            .step_by(2)
+            .map(|channel| OutputRoute {
+                source: OutputSource::Master,
+                channels: if channel + 1 < channels {
+                    OutputChannels::Stereo(channel, channel + 1)
+                } else {
+                    OutputChannels::Mono(channel)
+                },
+            })
+            .collect();
+
+        Self {
+            device_channels,
+            routes,
+        }
+    }
+
+    pub fn device_channels(&self) -> NonZeroUsize {
+        self.device_channels
+    }
+
+    pub fn routes(&self) -> &[OutputRoute] {
+        &self.routes
+    }
+
+    /// The cue buses that are played on the device
+    pub fn cues(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.routes.iter().filter_map(|route| match route.source {
+            OutputSource::Cue(bus) => Some(bus),
+            OutputSource::Master => None,
+        })
+    }
+
+    /// Plays `source` on `channels` in addition to its other routes
+    pub fn add_route(
+        &mut self,
+        source: OutputSource,
+        channels: OutputChannels,
+    ) -> Result<(), OutputError> {
+        if channels.highest() >= self.device_channels.get() {
+            return Err(OutputError::InvalidChannel(channels.highest()));
+        }
+
+        self.routes.push(OutputRoute { source, channels });
+        Ok(())
+    }
+
+    /// Replaces all routes of `source`, an empty slice mutes it
+    pub fn set_routes(
+        &mut self,
+        source: OutputSource,
+        channels: &[OutputChannels],
+    ) -> Result<(), OutputError> {
+        if let Some(channels) = channels
+            .iter()
+            .find(|channels| channels.highest() >= self.device_channels.get())
+        {
+            return Err(OutputError::InvalidChannel(channels.highest()));
+        }
+
+        self.remove_source(source);
+        self.routes.extend(
+            channels
+                .iter()
+                .map(|&channels| OutputRoute { source, channels }),
+        );
+        Ok(())
+    }
+
+    /// Whether the device plays master unchanged, i.e. its only two
+    /// channels are the left and right side of master
+    pub(crate) fn is_direct(&self) -> bool {
+        self.device_channels.get() == 2
+            && self.routes
+                == [OutputRoute {
+                    source: OutputSource::Master,
+                    channels: OutputChannels::Stereo(0, 1),
+                }]
+    }
+
+    pub(crate) fn remove_source(&mut self, source: OutputSource) {
+        self.routes.retain(|route| route.source != source);
+    }
+
+    /// Mirrors a track removal, which moves the last track into the freed index
+    pub(crate) fn remove_track(&mut self, track: NodeIndex, last: NodeIndex) {
+        self.remove_source(OutputSource::Cue(track));
+        for route in &mut self.routes {
+            if route.source == OutputSource::Cue(last) {
+                route.source = OutputSource::Cue(track);
+            }
+        }
+    }
+
+    /// Writes one device frame from the frames of the master track and the cue
+    /// buses. Cues that `cue` has no frame for are silent.
+    pub(crate) fn render<'a, T: SharedSample>(
+        &self,
+        master: &[T],
+        cue: impl Fn(NodeIndex) -> Option<&'a [T]>,
+        device_frame: &mut [T],
+    ) {
+        device_frame.fill(T::EQUILIBRIUM);
+
+        for route in &self.routes {
+            let frame = match route.source {
+                OutputSource::Master => Some(master),
+                OutputSource::Cue(bus) => cue(bus),
+            };
+            if let Some(frame) = frame {
+                route.channels.mix(frame, device_frame);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use audio_graph::daggy::NodeIndex;
+
+    use crate::output::{OutputChannels, OutputError, OutputMap, OutputSource};
+
+    #[test]
+    fn maps_master_and_cues_to_device_channels() {
+        let cue = NodeIndex::new(3);
+        let cue_frame = [0.25f32, -0.25];
+        let render = |map: &OutputMap| {
+            let mut frame = vec![1.0; map.device_channels().get()];
+            map.render(
+                &[0.5f32, 1.0],
+                |bus| (bus == cue).then_some(&cue_frame[..]),
+                &mut frame,
+            );
+            frame
+        };
+
+        assert_eq!(render(&OutputMap::new(NonZero::new(1).unwrap())), [0.75]);
+        assert_eq!(
+            render(&OutputMap::new(NonZero::new(3).unwrap())),
+            [0.5, 1.0, 0.0]
+        );
+        assert_eq!(
+            render(&OutputMap::upmixed(NonZero::new(5).unwrap())),
+            [0.5, 1.0, 0.5, 1.0, 0.75]
+        );
+
+        // master on outputs 3/4 and a headphone mix on 1/2
+        let mut map = OutputMap::new(NonZero::new(4).unwrap());
+        map.set_routes(OutputSource::Master, &[OutputChannels::Stereo(2, 3)])
+            .unwrap();
+        map.add_route(OutputSource::Cue(cue), OutputChannels::Stereo(0, 1))
+            .unwrap();
+        assert_eq!(render(&map), [0.25, -0.25, 0.5, 1.0]);
+
+        assert_eq!(
+            map.add_route(OutputSource::Master, OutputChannels::Stereo(3, 4)),
+            Err(OutputError::InvalidChannel(4))
+        );
+    }
+}
@@ -1,12 +1,20 @@
 use std::{ops::Range, sync::Arc};
 
 use audio_buffer::buffers::interleaved::InterleavedBuffer;
+use audio_graph::event::MidiEvent;
 use interavl::IntervalTree;
 use time::{FrameTime, MusicalTime, SampleRate};
 
+use crate::{midi::MidiClip, transport::SubBlock};
+
 #[derive(Clone)]
 pub struct BlockEvent<T> {
+    /// Offset of the event's first frame inside the block
     pub block_offset: FrameTime,
+    /// Frame inside the event's buffer at which playback starts
+    pub clip_offset: FrameTime,
+    /// Number of frames the event covers inside the block
+    pub frames: FrameTime,
     pub event: Event<T>,
 }
 
@@ -17,28 +25,65 @@ pub struct Event<T> {
 
 pub struct Clip<T> {
     pub buffer: Arc<InterleavedBuffer<T>>,
+    /// Position inside the buffer that lines up with the start of the clip's range
+    pub offset: MusicalTime,
+}
+
+impl<T> Clip<T> {
+    pub fn new(buffer: Arc<InterleavedBuffer<T>>) -> Self {
+        Self {
+            buffer,
+            offset: MusicalTime::ZERO,
+        }
+    }
+
+    /// Returns the clip that plays the same material when its range is changed
+    /// from `range` to `new_range`, or `None` if `new_range` would start before the buffer.
+    pub fn trimmed(
+        &self,
+        range: &Range<MusicalTime>,
+        new_range: &Range<MusicalTime>,
+    ) -> Option<Self> {
+        let offset = match new_range.start.checked_sub(range.start) {
+            Some(delta) => self.offset + delta,
+            None => self
+                .offset
+                .checked_sub(range.start.checked_sub(new_range.start)?)?,
+        };
+
+        Some(Self {
+            buffer: self.buffer.clone(),
+            offset,
+        })
+    }
 }
 
 impl<T> Clone for Clip<T> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer.clone(),
+            offset: self.offset,
         }
     }
 }
 
 pub struct Playlist<T> {
     clips: IntervalTree<MusicalTime, Clip<T>>,
+    midi_clips: IntervalTree<MusicalTime, MidiClip>,
 }
 
 impl<T> Playlist<T> {
     pub fn from_clips(clips: IntervalTree<MusicalTime, Clip<T>>) -> Self {
-        Self { clips }
+        Self {
+            clips,
+            midi_clips: IntervalTree::default(),
+        }
     }
 
     pub fn empty() -> Self {
         Self {
             clips: IntervalTree::default(),
+            midi_clips: IntervalTree::default(),
         }
     }
 }
@@ -65,6 +110,50 @@ impl<T> Playlist<T> {
         self.clips.get(&range).cloned()
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = (&Range<MusicalTime>, &Clip<T>)> {
+        self.clips.iter()
+    }
+
+    /// Insert a `MidiClip` into the `Playlist`. MIDI clips live next to
+    /// the audio clips, so both can occupy the same range.
+    /// Returns the previously existing MIDI clip at this range, or `None` if there wasn't any
+    ///
+    /// # Panics
+    /// Panics if `range.start >= range.end`
+    pub fn insert_midi(&mut self, range: Range<MusicalTime>, clip: MidiClip) -> Option<MidiClip> {
+        assert!(
+            range.start < range.end,
+            "invalid range: start must be less than end"
+        );
+        self.midi_clips.insert(range, clip)
+    }
+
+    pub fn remove_midi(&mut self, range: Range<MusicalTime>) -> Option<MidiClip> {
+        self.midi_clips.remove(&range)
+    }
+
+    pub fn get_midi(&self, range: Range<MusicalTime>) -> Option<MidiClip> {
+        self.midi_clips.get(&range).cloned()
+    }
+
+    pub fn iter_midi(&self) -> impl Iterator<Item = (&Range<MusicalTime>, &MidiClip)> {
+        self.midi_clips.iter()
+    }
+
+    /// Appends the events of all MIDI clips that fall into `sub_block` to `events`.
+    /// The events are not sorted.
+    pub fn get_midi_events(
+        &self,
+        sub_block: &SubBlock,
+        bpm: f64,
+        sample_rate: SampleRate,
+        events: &mut Vec<MidiEvent>,
+    ) {
+        for (range, clip) in self.midi_clips.iter_overlaps(&sub_block.range) {
+            clip.collect_events(range, sub_block, bpm, sample_rate, events);
+        }
+    }
+
     // TODO: currently not needed; maybe remove?
     pub fn iter_blocks(
         &self,
@@ -89,28 +178,41 @@ impl<T> Playlist<T> {
         bpm: f64,
         sample_rate: SampleRate,
     ) -> Vec<BlockEvent<T>> {
-        let mut block_events = Vec::new();
-
-        for (clip_range, clip) in self.clips.iter_overlaps(&block_range_musical) {
-            let event = Event {
-                buffer: clip.buffer.clone(),
-            };
-
-            let offset_musical = clip_range
-                .start
-                .checked_sub(block_range_musical.start)
-                .unwrap_or(MusicalTime::ZERO);
-
-            let offset_frames = offset_musical.to_nearest_frame_round_lossy(bpm, sample_rate);
-
-            block_events.push(BlockEvent {
-                block_offset: offset_frames,
-                event,
-            });
-        }
+        collect_block_events(&self.clips, block_range_musical, bpm, sample_rate)
+    }
+}
 
-        block_events
+fn collect_block_events<T>(
+    clips: &IntervalTree<MusicalTime, Clip<T>>,
+    block_range_musical: Range<MusicalTime>,
+    bpm: f64,
+    sample_rate: SampleRate,
+) -> Vec<BlockEvent<T>> {
+    let mut block_events = Vec::new();
+
+    for (clip_range, clip) in clips.iter_overlaps(&block_range_musical) {
+        let event = Event {
+            buffer: clip.buffer.clone(),
+        };
+
+        let start = clip_range.start.max(block_range_musical.start);
+        let end = clip_range.end.min(block_range_musical.end);
+
+        let to_frames = |from: MusicalTime, to: MusicalTime| {
+            to.checked_sub(from)
+                .unwrap_or(MusicalTime::ZERO)
+                .to_nearest_frame_round_lossy(bpm, sample_rate)
+        };
+
+        block_events.push(BlockEvent {
+            block_offset: to_frames(block_range_musical.start, start),
+            clip_offset: to_frames(clip_range.start, start + clip.offset),
+            frames: to_frames(start, end),
+            event,
+        });
     }
+
+    block_events
 }
 
 /// An Iterator that generates BlockEvents from an IntervalTree
@@ -131,26 +233,8 @@ impl<T> Iterator for BlockIterator<'_, T> {
         let block_end_musical = block_start_musical + self.block_duration_musical;
         let block_range_musical = block_start_musical..block_end_musical;
 
-        let mut block_events = Vec::new();
-
-        for (clip_range, clip) in self.clips.iter_overlaps(&block_range_musical) {
-            let event = Event {
-                buffer: clip.buffer.clone(),
-            };
-
-            let offset_musical = clip_range
-                .start
-                .checked_sub(block_start_musical)
-                .unwrap_or(MusicalTime::ZERO);
-
-            let offset_frames =
-                offset_musical.to_nearest_frame_round_lossy(self.bpm, self.sample_rate);
-
-            block_events.push(BlockEvent {
-                block_offset: offset_frames,
-                event,
-            });
-        }
+        let block_events =
+            collect_block_events(self.clips, block_range_musical, self.bpm, self.sample_rate);
 
         self.current_musical_pos = block_end_musical;
 
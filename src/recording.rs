@@ -0,0 +1,404 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use audio_buffer::{SharedSample, dasp::Sample, loader::error::LoadError};
+use audio_graph::daggy::NodeIndex;
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+use time::{FrameTime, MusicalTime, SampleRate};
+
+use crate::engine::AudioEngineError;
+
+/// Recorded takes are always stereo, like the tracks they are recorded on
+pub const TAKE_CHANNELS: usize = 2;
+
+/// Seconds of audio the writer thread may fall behind before samples are dropped
+const WRITER_HEADROOM: f64 = 2.0;
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Engine(AudioEngineError),
+    Io(io::Error),
+    Load(LoadError),
+}
+
+impl From<AudioEngineError> for RecordingError {
+    fn from(value: AudioEngineError) -> Self {
+        Self::Engine(value)
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<LoadError> for RecordingError {
+    fn from(value: LoadError) -> Self {
+        Self::Load(value)
+    }
+}
+
+/// A take once its writer finished the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedTake {
+    pub path: PathBuf,
+    /// The part of the timeline that was recorded, `None` if
+    /// the playhead never entered the punch region
+    pub range: Option<Range<MusicalTime>>,
+    /// Frames that were lost because the writer thread fell behind
+    pub dropped: u64,
+}
+
+// What the audio thread reports about a take when it ends,
+// read by the `DiskWriter` once the take was dropped
+#[derive(Debug)]
+struct TakeSummary {
+    // in ticks, `NOT_RECORDED` if nothing was recorded
+    start: AtomicU64,
+    end: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl TakeSummary {
+    const NOT_RECORDED: u64 = u64::MAX;
+
+    fn range(&self) -> Option<Range<MusicalTime>> {
+        let start = self.start.load(Ordering::Acquire);
+        (start != Self::NOT_RECORDED).then(|| {
+            MusicalTime::from_total_ticks(start)
+                ..MusicalTime::from_total_ticks(self.end.load(Ordering::Acquire))
+        })
+    }
+}
+
+/// The audio thread's end of a take: the input of an armed track is pushed
+/// into a ring buffer that a `DiskWriter` drains on another thread.
+pub struct Take<T> {
+    track: NodeIndex,
+    producer: HeapProd<T>,
+    summary: Arc<TakeSummary>,
+    // the first recorded frame on the timeline and the number of frames since
+    start: Option<(MusicalTime, FrameTime)>,
+    frames: u64,
+    dropped: u64,
+    // frames that were dropped but not yet made up for with silence
+    missing: u64,
+    // the take ends at the first jump of the playhead, e.g. when the loop wraps
+    closed: bool,
+}
+
+impl<T: SharedSample> Take<T> {
+    pub fn track(&self) -> NodeIndex {
+        self.track
+    }
+
+    pub(crate) fn set_track(&mut self, track: NodeIndex) {
+        self.track = track;
+    }
+
+    /// Records `frame` as the frame at `position` on the timeline,
+    /// which lies `frame_position` frames after the start of the timeline
+    pub(crate) fn record(
+        &mut self,
+        position: MusicalTime,
+        frame_position: FrameTime,
+        frame: [T; 2],
+    ) {
+        if self.closed {
+            return;
+        }
+
+        match self.start {
+            None => self.start = Some((position, frame_position)),
+            Some((_, start)) if start + FrameTime(self.frames) != frame_position => {
+                self.closed = true;
+                return;
+            }
+            Some(_) => {}
+        }
+
+        // dropped frames are replaced by silence once there is room again,
+        // so the rest of the take stays in time
+        while self.missing > 0 && self.producer.vacant_len() >= 2 * TAKE_CHANNELS {
+            self.producer.push_slice(&[T::EQUILIBRIUM; TAKE_CHANNELS]);
+            self.missing -= 1;
+        }
+
+        if self.missing == 0 && self.producer.vacant_len() >= TAKE_CHANNELS {
+            self.producer.push_slice(&frame);
+        } else {
+            self.dropped += 1;
+            self.missing += 1;
+        }
+        self.frames += 1;
+    }
+
+    /// Hands the recorded range to the take's writer. Doesn't allocate,
+    /// so it's called on the audio thread right before the take is dropped.
+    pub(crate) fn finish(&self, bpm: f64, sample_rate: SampleRate) {
+        if let Some((start, _)) = self.start {
+            let end = start + FrameTime(self.frames).to_musical_lossy(bpm, sample_rate);
+            self.summary.end.store(end.total_ticks(), Ordering::Release);
+            self.summary
+                .start
+                .store(start.total_ticks(), Ordering::Release);
+        }
+        self.summary.dropped.store(self.dropped, Ordering::Release);
+    }
+}
+
+/// Writes the samples of a take to a WAV file on a background thread.
+/// The thread finishes the file once the backend dropped the `Take`.
+pub struct DiskWriter {
+    path: PathBuf,
+    thread: JoinHandle<io::Result<()>>,
+    summary: Arc<TakeSummary>,
+}
+
+impl DiskWriter {
+    /// Creates the file at `path` and returns the writer
+    /// together with the take the backend records into
+    pub fn spawn<T: SharedSample>(
+        track: NodeIndex,
+        path: impl AsRef<Path>,
+        sample_rate: SampleRate,
+    ) -> io::Result<(Self, Take<T>)> {
+        let path = path.as_ref().to_owned();
+        let file = WavWriter::create(&path, sample_rate)?;
+
+        let capacity = (sample_rate.as_f64() * WRITER_HEADROOM) as usize * TAKE_CHANNELS;
+        let (producer, consumer) = HeapRb::<T>::new(capacity).split();
+        let thread = std::thread::spawn(move || write_take(file, consumer));
+        let summary = Arc::new(TakeSummary {
+            start: AtomicU64::new(TakeSummary::NOT_RECORDED),
+            end: AtomicU64::new(TakeSummary::NOT_RECORDED),
+            dropped: AtomicU64::new(0),
+        });
+
+        let take = Take {
+            track,
+            producer,
+            summary: summary.clone(),
+            start: None,
+            frames: 0,
+            dropped: 0,
+            missing: 0,
+            closed: false,
+        };
+        Ok((
+            Self {
+                path,
+                thread,
+                summary,
+            },
+            take,
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits until everything that was recorded is on disk and returns what
+    /// the backend reported about the take. Blocks until it dropped the take.
+    pub fn finish(self) -> io::Result<RecordedTake> {
+        self.thread
+            .join()
+            .map_err(|_| io::Error::other("the writer thread panicked"))??;
+        Ok(RecordedTake {
+            path: self.path,
+            range: self.summary.range(),
+            dropped: self.summary.dropped.load(Ordering::Acquire),
+        })
+    }
+}
+
+fn write_take<T: SharedSample>(mut file: WavWriter, mut consumer: HeapCons<T>) -> io::Result<()> {
+    let mut chunk = vec![T::EQUILIBRIUM; 4096 * TAKE_CHANNELS];
+
+    loop {
+        // checked before draining, so nothing pushed before the take was dropped is lost
+        let done = !consumer.write_is_held();
+
+        let samples = consumer.pop_slice(&mut chunk);
+        file.write(&chunk[..samples])?;
+
+        if samples == 0 {
+            if done {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    file.finish()
+}
+
+/// A minimal writer for 32 bit float WAV files
+struct WavWriter {
+    file: BufWriter<File>,
+    samples: u64,
+}
+
+impl WavWriter {
+    const HEADER_LEN: u32 = 44;
+
+    fn create(path: &Path, sample_rate: SampleRate) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        let channels = TAKE_CHANNELS as u16;
+        let sample_rate = sample_rate.as_f64() as u32;
+        let block_align = channels * 4;
+
+        file.write_all(b"RIFF")?;
+        // the sizes are filled in by `finish`
+        file.write_all(&0u32.to_le_bytes())?;
+        file.write_all(b"WAVEfmt ")?;
+        file.write_all(&16u32.to_le_bytes())?;
+        // IEEE float
+        file.write_all(&3u16.to_le_bytes())?;
+        file.write_all(&channels.to_le_bytes())?;
+        file.write_all(&sample_rate.to_le_bytes())?;
+        file.write_all(&(sample_rate * u32::from(block_align)).to_le_bytes())?;
+        file.write_all(&block_align.to_le_bytes())?;
+        file.write_all(&32u16.to_le_bytes())?;
+        file.write_all(b"data")?;
+        file.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self { file, samples: 0 })
+    }
+
+    fn write<T: Sample>(&mut self, samples: &[T]) -> io::Result<()> {
+        for &sample in samples {
+            let sample: f32 = sample.to_float_sample().to_sample();
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+        self.samples += samples.len() as u64;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let data = u32::try_from(self.samples * 4)
+            .map_err(|_| io::Error::other("the take is too long for a WAV file"))?;
+
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file
+            .write_all(&(data + Self::HEADER_LEN - 8).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&data.to_le_bytes())?;
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZero, sync::Arc};
+
+    use audio_buffer::{
+        buffers::interleaved::InterleavedBuffer,
+        core::{Buffer, BufferMut},
+    };
+    use audio_graph::AudioGraph;
+    use ringbuf::{HeapRb, traits::Split};
+    use time::{FrameTime, MusicalTime, SampleRate};
+
+    use crate::{
+        backend::{AudioBackend, BackendChannels},
+        input::{FileInput, Input, InputChannels},
+        message::AudioEngineStatus,
+        recording::DiskWriter,
+        track::Track,
+    };
+
+    #[test]
+    fn records_the_input_inside_of_the_punch_region() {
+        // at 120 bpm a beat is exactly 500 frames
+        let sample_rate = SampleRate::new(1000.0);
+        let block_size = FrameTime(100);
+
+        let mut ramp = InterleavedBuffer::with_shape(NonZero::new(2).unwrap(), FrameTime(2000));
+        ramp.map_frames_mut(
+            |frame, index| {
+                frame[0] = index as f32 / 2000.0;
+                frame[1] = -(index as f32) / 2000.0;
+                Some(())
+            },
+            None,
+        );
+        let ramp = Arc::new(ramp);
+
+        let (_, command_consumer) = HeapRb::new(16).split();
+        let (status_producer, _status_consumer) = HeapRb::new(64).split();
+        let (ack_producer, _ack_consumer) = HeapRb::new(16).split();
+        let (graph, master) =
+            AudioGraph::new(Track::bus(sample_rate, block_size), sample_rate, block_size);
+        let mut backend = AudioBackend::<f32>::new(
+            BackendChannels {
+                commands: command_consumer,
+                statuses: status_producer,
+                acks: ack_producer,
+            },
+            graph,
+            master,
+            block_size,
+            120.0,
+            sample_rate,
+        );
+
+        let track = backend.add_track();
+        backend.set_track_input(track, Some(InputChannels::Stereo(1, 0)));
+        backend.input = Some(Input::new(
+            Box::new(FileInput::new(ramp.clone())),
+            block_size,
+        ));
+        backend
+            .transport
+            .set_punch(MusicalTime::from_beats(1)..MusicalTime::from_beats(2));
+        backend.transport.enable_punch(true);
+
+        let path = std::env::temp_dir().join(format!("take-{}.wav", std::process::id()));
+        let (writer, take) = DiskWriter::spawn(track, &path, sample_rate).unwrap();
+        backend.takes.push(take);
+        backend.running = true;
+
+        let mut output = vec![0.0; 200];
+        for _ in 0..15 {
+            backend.process(&mut output);
+        }
+
+        assert!(matches!(
+            backend.stop_recording(),
+            AudioEngineStatus::RecordingStopped
+        ));
+
+        let take = writer.finish().unwrap();
+        assert_eq!(take.dropped, 0);
+        assert_eq!(
+            take.range,
+            Some(MusicalTime::from_beats(1)..MusicalTime::from_beats(2))
+        );
+        let recorded = audio_buffer::loader::load::<f32>(&take.path).unwrap();
+        std::fs::remove_file(take.path).unwrap();
+
+        assert_eq!(recorded.frames(), 500);
+        for index in 0..500 {
+            let input = ramp.get_frame(500 + index).unwrap();
+            // the channels are swapped by the track's input
+            assert_eq!(recorded.get_frame(index).unwrap(), &[input[1], input[0]]);
+        }
+    }
+}
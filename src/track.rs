@@ -1,20 +1,76 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, num::NonZero};
 
+use audio_buffer::{
+    buffers::{compatability::slice::WrapInterleavedMut, interleaved::InterleavedBuffer},
+    core::{BufferMut, io::mix_buffers},
+};
 use audio_graph::{
-    AudioGraph,
-    daggy::NodeIndex,
-    processor::{AudioProcessor, PassThrough},
+    AudioGraph, Connection, Tap,
+    daggy::{EdgeIndex, NodeIndex},
+    error::GraphError,
+    event::MidiEvent,
+    parameter::{MAX_RAMPS, ParameterId, ParameterRamp},
+    pin_matrix::PinMatrix,
+    processor::{AudioProcessor, PassThrough, ProcessorConfiguration},
 };
 use time::{FrameTime, SampleRate};
 
-use crate::playlist::Playlist;
+use crate::{
+    automation::AutomationLane, input::InputChannels, mixer::MixerStrip, playlist::Playlist,
+    transport::SubBlock,
+};
+
+/// Index of the auxiliary output holding a track's signal before its mixer strip
+pub const PRE_FADER_OUTPUT: usize = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    /// Plays the clips of its playlist
+    Audio,
+    /// Has no playlist and processes the sum of the tracks routed into it,
+    /// e.g. a group or an effect return
+    Bus,
+}
+
+/// Where a send reads the signal of its track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendTap {
+    /// Before the mixer strip, so the send doesn't follow the fader
+    PreFader,
+    PostFader,
+}
+
+impl SendTap {
+    pub fn tap(self) -> Tap {
+        match self {
+            SendTap::PreFader => Tap::Auxiliary(PRE_FADER_OUTPUT),
+            SendTap::PostFader => Tap::Main,
+        }
+    }
+
+    pub fn from_tap(tap: Tap) -> Self {
+        match tap {
+            Tap::Main => SendTap::PostFader,
+            Tap::Auxiliary(_) => SendTap::PreFader,
+        }
+    }
+}
 
 pub struct Track<T>
 where
     T: audio_buffer::dasp::Sample,
 {
     graph: AudioGraph<T, Box<dyn AudioProcessor<T>>>,
+    kind: TrackKind,
     playlist: Playlist<T>,
+    mixer: MixerStrip,
+    // the output of the last block before the mixer strip was applied
+    pre_fader: InterleavedBuffer<T>,
+    input_channels: Option<InputChannels>,
+    monitoring: bool,
+    automation: Vec<AutomationLane>,
+    // reused between blocks so evaluating automation doesn't allocate
+    ramps: Vec<ParameterRamp>,
     // INVARIANT: `input` must never dangle
     input: NodeIndex,
 }
@@ -23,29 +79,89 @@ impl<T> Track<T>
 where
     T: audio_buffer::dasp::Sample + 'static,
 {
-    /// Convinience constructor to create a stereo track from its configuration
+    /// Convinience constructor to create a stereo track from its configuration.
+    /// The track's input is connected straight to its output, processors
+    /// can be inserted in between.
     pub fn from_config(sample_rate: SampleRate, block_size: FrameTime) -> Self {
-        let (graph, input) = AudioGraph::<T, Box<dyn AudioProcessor<T>>>::new(
+        let (mut graph, input) = AudioGraph::<T, Box<dyn AudioProcessor<T>>>::new(
             Box::new(PassThrough::new(2, 2)),
             sample_rate,
             block_size,
         );
 
+        let output = graph.add_node(Box::new(PassThrough::new(2, 2)));
+        graph
+            .set_output_index(output)
+            .expect("output was just added");
+        graph
+            .add_connection(input, output, PinMatrix::diagonal(2, 2))
+            .expect("both nodes are stereo");
+
         Self {
             graph,
             input,
+            kind: TrackKind::Audio,
             playlist: Playlist::empty(),
+            mixer: MixerStrip::new(sample_rate),
+            pre_fader: InterleavedBuffer::with_shape(NonZero::new(2).unwrap(), block_size),
+            input_channels: None,
+            monitoring: false,
+            automation: Vec::new(),
+            ramps: Vec::with_capacity(MAX_RAMPS),
+        }
+    }
+
+    /// A stereo bus, see `TrackKind::Bus`
+    pub fn bus(sample_rate: SampleRate, block_size: FrameTime) -> Self {
+        Self {
+            kind: TrackKind::Bus,
+            ..Self::from_config(sample_rate, block_size)
         }
     }
 
     pub fn from_graph(graph: AudioGraph<T, Box<dyn AudioProcessor<T>>>, input: NodeIndex) -> Self {
+        let mixer = MixerStrip::new(graph.sample_rate());
+        let pre_fader = InterleavedBuffer::with_shape(
+            NonZero::new(graph.get_output().config().num_output_channels)
+                .expect("nodes output at least one channel"),
+            graph.block_size(),
+        );
         Self {
             graph,
             input,
+            kind: TrackKind::Audio,
             playlist: Playlist::empty(),
+            mixer,
+            pre_fader,
+            input_channels: None,
+            monitoring: false,
+            automation: Vec::new(),
+            ramps: Vec::with_capacity(MAX_RAMPS),
         }
     }
 
+    pub fn kind(&self) -> TrackKind {
+        self.kind
+    }
+
+    /// The channels of the input device the track records and monitors
+    pub fn input_channels(&self) -> Option<InputChannels> {
+        self.input_channels
+    }
+
+    pub fn set_input_channels(&mut self, input_channels: Option<InputChannels>) {
+        self.input_channels = input_channels;
+    }
+
+    /// Whether the input is played through the track's processors
+    pub fn is_monitoring(&self) -> bool {
+        self.monitoring
+    }
+
+    pub fn set_monitoring(&mut self, monitoring: bool) {
+        self.monitoring = monitoring;
+    }
+
     pub fn get_playlist(&self) -> &Playlist<T> {
         &self.playlist
     }
@@ -53,6 +169,89 @@ where
     pub fn get_playlist_mut(&mut self) -> &mut Playlist<T> {
         &mut self.playlist
     }
+
+    pub fn mixer(&self) -> &MixerStrip {
+        &self.mixer
+    }
+
+    pub fn mixer_mut(&mut self) -> &mut MixerStrip {
+        &mut self.mixer
+    }
+
+    pub fn input_index(&self) -> NodeIndex {
+        self.input
+    }
+
+    pub fn output_index(&self) -> NodeIndex {
+        self.graph.get_output_index()
+    }
+
+    pub fn add_processor(&mut self, processor: Box<dyn AudioProcessor<T>>) -> NodeIndex {
+        self.graph.add_node(processor)
+    }
+
+    /// Returns `false` if there is no such processor or parameter
+    pub fn set_parameter(&mut self, processor: NodeIndex, id: ParameterId, value: f32) -> bool {
+        self.graph
+            .get_node_mut(processor)
+            .is_some_and(|processor| processor.set_parameter(id, value))
+    }
+
+    pub fn automation_lanes(&self) -> &[AutomationLane] {
+        &self.automation
+    }
+
+    /// Inserts a lane, replacing the lane of the same parameter
+    pub fn set_automation_lane(&mut self, lane: AutomationLane) -> Option<AutomationLane> {
+        match self.automation.iter_mut().find(|existing| {
+            existing.processor() == lane.processor() && existing.parameter() == lane.parameter()
+        }) {
+            Some(existing) => Some(std::mem::replace(existing, lane)),
+            None => {
+                self.automation.push(lane);
+                None
+            }
+        }
+    }
+
+    pub fn remove_automation_lane(
+        &mut self,
+        processor: NodeIndex,
+        parameter: ParameterId,
+    ) -> Option<AutomationLane> {
+        let index = self
+            .automation
+            .iter()
+            .position(|lane| lane.processor() == processor && lane.parameter() == parameter)?;
+        Some(self.automation.remove(index))
+    }
+
+    /// Hands the automation of the next block to the processors
+    pub fn automate(&mut self, sub_blocks: &[SubBlock], bpm: f64, sample_rate: SampleRate) {
+        for lane in self.automation.iter().filter(|lane| lane.is_playing()) {
+            self.ramps.clear();
+            for sub_block in sub_blocks {
+                lane.ramps(sub_block, bpm, sample_rate, &mut self.ramps);
+            }
+
+            if let Some(processor) = self.graph.get_node_mut(lane.processor()) {
+                processor.automate_parameter(lane.parameter(), &self.ramps);
+            }
+        }
+    }
+
+    pub fn add_connection(
+        &mut self,
+        source: NodeIndex,
+        destination: NodeIndex,
+        matrix: PinMatrix,
+    ) -> Result<EdgeIndex, GraphError> {
+        self.graph.add_connection(source, destination, matrix)
+    }
+
+    pub fn remove_connection(&mut self, edge: EdgeIndex) -> Option<Connection> {
+        self.graph.remove_connection(edge)
+    }
 }
 
 impl<T> AudioProcessor<T> for Track<T>
@@ -61,17 +260,123 @@ where
 {
     fn process_unchecked(
         &mut self,
-        input: &audio_buffer::buffers::interleaved::InterleavedBuffer<T>,
-        output: &mut audio_buffer::buffers::interleaved::InterleavedBuffer<T>,
+        input: &InterleavedBuffer<T>,
+        output: &mut WrapInterleavedMut<'_, T>,
     ) {
         let mut inputs = HashMap::new();
         inputs.insert(self.input, input);
         self.graph.process_block(&inputs, output);
+
+        self.pre_fader.set_to_equilibrium();
+        mix_buffers(output, &mut self.pre_fader, None)
+            .expect("both buffers have the track's shape");
+        self.mixer.process(output);
     }
 
-    fn config(&self) -> audio_graph::processor::ProcessorConfiguration {
-        self.graph
+    /// Every processor of the track receives the events of the track
+    fn schedule_events(&mut self, events: &[MidiEvent]) {
+        for index in 0..self.graph.get_dag().node_count() {
+            self.graph
+                .get_node_mut(NodeIndex::new(index))
+                .expect("index is in bounds")
+                .schedule_events(events);
+        }
+    }
+
+    fn latency(&self) -> usize {
+        self.graph.latency()
+    }
+
+    fn auxiliary_outputs(&self) -> usize {
+        1
+    }
+
+    fn auxiliary_output(&self, index: usize) -> Option<&InterleavedBuffer<T>> {
+        (index == PRE_FADER_OUTPUT).then_some(&self.pre_fader)
+    }
+
+    fn config(&self) -> ProcessorConfiguration {
+        let input = self
+            .graph
             .get_node_config(self.input)
-            .expect("invariant: input must always be valid")
+            .expect("invariant: input must always be valid");
+        let output = self.graph.get_output().config();
+
+        ProcessorConfiguration {
+            num_input_channels: input.num_input_channels,
+            num_output_channels: output.num_output_channels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, num::NonZero};
+
+    use audio_buffer::{
+        buffers::interleaved::InterleavedBuffer,
+        core::{Buffer, BufferMut},
+    };
+    use audio_graph::{AudioGraph, Connection, pin_matrix::PinMatrix};
+    use time::{FrameTime, SampleRate};
+
+    use crate::{
+        mixer::{MixerControl, PanLaw},
+        track::{SendTap, Track},
+    };
+
+    #[test]
+    fn pre_fader_sends_ignore_the_fader() {
+        let sample_rate = SampleRate::new(1000.0);
+        let block_size = FrameTime(64);
+        let stereo = || PinMatrix::diagonal(2, 2);
+
+        let (mut graph, master) = AudioGraph::new(
+            Track::<f32>::bus(sample_rate, block_size),
+            sample_rate,
+            block_size,
+        );
+        let track = graph.add_node(Track::from_config(sample_rate, block_size));
+        let bus = graph.add_node(Track::bus(sample_rate, block_size));
+        graph.add_connection(track, master, stereo()).unwrap();
+        graph.add_connection(bus, master, stereo()).unwrap();
+        graph
+            .connect(
+                track,
+                bus,
+                Connection::new(stereo())
+                    .with_tap(SendTap::PreFader.tap())
+                    .with_gain(0.5),
+            )
+            .unwrap();
+
+        for index in [master, track, bus] {
+            let mixer = graph.get_node_mut(index).unwrap().mixer_mut();
+            mixer.set(MixerControl::PanLaw(PanLaw::Balance));
+        }
+        graph
+            .get_node_mut(track)
+            .unwrap()
+            .mixer_mut()
+            .set(MixerControl::Gain(0.0));
+
+        let channels = NonZero::new(2).unwrap();
+        let mut input = InterleavedBuffer::with_shape(channels, block_size);
+        input.map_frames_mut(
+            |frame, _| {
+                frame.fill(1.0);
+                Some(())
+            },
+            None,
+        );
+        let inputs = HashMap::from([(track, &input)]);
+        let mut output = InterleavedBuffer::with_shape(channels, block_size);
+
+        // the first block ramps the fader down
+        graph.process_block(&inputs, &mut output.wrap_mut());
+        output.set_to_equilibrium();
+        graph.process_block(&inputs, &mut output.wrap_mut());
+
+        assert!(output.iter_frames().flatten().all(|&s| s == 0.5));
     }
 }
@@ -0,0 +1,287 @@
+use std::ops::Range;
+
+use time::{FrameTime, MusicalTime, SampleRate};
+
+/// A contiguous part of a block that maps onto a single range of the timeline.
+/// A block is split into multiple sub-blocks when the loop region wraps inside of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubBlock {
+    /// Offset of the first frame of this sub-block inside the block
+    pub offset: FrameTime,
+    /// Number of frames this sub-block spans
+    pub frames: FrameTime,
+    /// The part of the timeline this sub-block covers
+    pub range: Range<MusicalTime>,
+}
+
+pub struct Transport {
+    playhead: MusicalTime,
+    block_size: FrameTime,
+    bpm: f64,
+    sample_rate: SampleRate,
+
+    loop_range: Option<Range<MusicalTime>>,
+    loop_enabled: bool,
+    punch_range: Option<Range<MusicalTime>>,
+    punch_enabled: bool,
+
+    // reused between blocks so computing the sub-blocks doesn't allocate. The loop
+    // is at least a block long, so a block wraps once and has at most two sub-blocks
+    sub_blocks: Vec<SubBlock>,
+}
+
+impl Transport {
+    pub fn new(block_size: FrameTime, bpm: f64, sample_rate: SampleRate) -> Self {
+        Self {
+            playhead: MusicalTime::ZERO,
+            block_size,
+            bpm,
+            sample_rate,
+            loop_range: None,
+            loop_enabled: false,
+            punch_range: None,
+            punch_enabled: false,
+            sub_blocks: Vec::with_capacity(2),
+        }
+    }
+
+    pub fn playhead(&self) -> MusicalTime {
+        self.playhead
+    }
+
+    pub fn set_playhead(&mut self, playhead: MusicalTime) {
+        self.playhead = playhead;
+    }
+
+    /// Sets the loop region. Ranges that are shorter than a block are rejected,
+    /// because every wrap inside of a block splits off another sub-block.
+    ///
+    /// Returns `false` if the range was rejected.
+    pub fn set_loop(&mut self, range: Range<MusicalTime>) -> bool {
+        if self.frames_between(range.start, range.end) < self.block_size.max(FrameTime(1)) {
+            return false;
+        }
+
+        self.loop_range = Some(range);
+        true
+    }
+
+    pub fn enable_loop(&mut self, enabled: bool) {
+        self.loop_enabled = enabled;
+    }
+
+    pub fn loop_range(&self) -> Option<&Range<MusicalTime>> {
+        self.loop_range.as_ref()
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.loop_enabled && self.loop_range.is_some()
+    }
+
+    /// Sets the punch-in/punch-out region used for recording.
+    ///
+    /// Returns `false` if the range was rejected because `range.start >= range.end`.
+    pub fn set_punch(&mut self, range: Range<MusicalTime>) -> bool {
+        if range.start >= range.end {
+            return false;
+        }
+
+        self.punch_range = Some(range);
+        true
+    }
+
+    pub fn enable_punch(&mut self, enabled: bool) {
+        self.punch_enabled = enabled;
+    }
+
+    pub fn punch_range(&self) -> Option<&Range<MusicalTime>> {
+        self.punch_range.as_ref()
+    }
+
+    pub fn is_punching(&self) -> bool {
+        self.punch_enabled && self.punch_range.is_some()
+    }
+
+    /// Returns the frames of `sub_block` (relative to the start of the block) that lie
+    /// inside the punch region, or `None` if they don't overlap. While punching is disabled
+    /// every frame is considered punched in, while it is enabled without a punch region
+    /// none are.
+    pub fn punched_frames(&self, sub_block: &SubBlock) -> Option<Range<FrameTime>> {
+        let block_frames = sub_block.offset..sub_block.offset + sub_block.frames;
+
+        if !self.punch_enabled {
+            return Some(block_frames);
+        }
+
+        let punch = self.punch_range.as_ref()?;
+        let start = punch.start.max(sub_block.range.start);
+        let end = punch.end.min(sub_block.range.end);
+
+        if start >= end {
+            return None;
+        }
+
+        let first = sub_block.offset + self.frames_between(sub_block.range.start, start);
+        let last = (sub_block.offset + self.frames_between(sub_block.range.start, end))
+            .min(block_frames.end);
+
+        (first < last).then_some(first..last)
+    }
+
+    /// Compute the sub-blocks of the next block and advance the playhead past it.
+    ///
+    /// When looping is enabled and the end of the loop region falls inside the block,
+    /// the block is split at the frame closest to the loop end and the remainder
+    /// continues at the start of the loop region.
+    pub fn advance(&mut self) -> &[SubBlock] {
+        self.sub_blocks.clear();
+
+        let mut offset = FrameTime(0);
+        while offset < self.block_size {
+            let remaining = self.block_size - offset;
+
+            let loop_end = match (&self.loop_range, self.loop_enabled) {
+                (Some(range), true) if self.playhead <= range.end => Some(range.clone()),
+                _ => None,
+            };
+
+            match loop_end {
+                Some(range) if self.frames_between(self.playhead, range.end) <= remaining => {
+                    let frames = self.frames_between(self.playhead, range.end);
+                    if frames > FrameTime(0) {
+                        self.sub_blocks.push(SubBlock {
+                            offset,
+                            frames,
+                            range: self.playhead..range.end,
+                        });
+                    }
+
+                    offset += frames;
+                    self.playhead = range.start;
+                }
+                _ => {
+                    let end =
+                        self.playhead + remaining.to_musical_lossy(self.bpm, self.sample_rate);
+                    self.sub_blocks.push(SubBlock {
+                        offset,
+                        frames: remaining,
+                        range: self.playhead..end,
+                    });
+
+                    offset += remaining;
+                    self.playhead = end;
+                }
+            }
+        }
+
+        &self.sub_blocks
+    }
+
+    /// The sub-blocks computed by the last call to `advance`
+    pub fn sub_blocks(&self) -> &[SubBlock] {
+        &self.sub_blocks
+    }
+
+    fn frames_between(&self, start: MusicalTime, end: MusicalTime) -> FrameTime {
+        end.checked_sub(start)
+            .unwrap_or(MusicalTime::ZERO)
+            .to_nearest_frame_round_lossy(self.bpm, self.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{FrameTime, MusicalTime, SampleRate};
+
+    use crate::transport::Transport;
+
+    fn transport() -> Transport {
+        Transport::new(FrameTime(256), 120.0, SampleRate::new(48_000.0))
+    }
+
+    #[test]
+    fn advance_without_loop_is_single_sub_block() {
+        let mut transport = transport();
+
+        let sub_blocks = transport.advance().to_vec();
+        assert_eq!(sub_blocks.len(), 1);
+        assert_eq!(sub_blocks[0].offset, FrameTime(0));
+        assert_eq!(sub_blocks[0].frames, FrameTime(256));
+        assert_eq!(sub_blocks[0].range.start, MusicalTime::ZERO);
+        assert_eq!(transport.playhead(), sub_blocks[0].range.end);
+    }
+
+    #[test]
+    fn loop_wrap_splits_block() {
+        let mut transport = transport();
+        // one beat at 120 bpm and 48kHz are 24_000 frames
+        assert!(transport.set_loop(MusicalTime::ZERO..MusicalTime::from_beats(1)));
+        transport.enable_loop(true);
+        transport
+            .set_playhead(FrameTime(23_900).to_musical_lossy(120.0, SampleRate::new(48_000.0)));
+
+        let sub_blocks = transport.advance().to_vec();
+        assert_eq!(sub_blocks.len(), 2);
+        assert_eq!(sub_blocks[0].frames, FrameTime(100));
+        assert_eq!(sub_blocks[0].range.end, MusicalTime::from_beats(1));
+        assert_eq!(sub_blocks[1].offset, FrameTime(100));
+        assert_eq!(sub_blocks[1].frames, FrameTime(156));
+        assert_eq!(sub_blocks[1].range.start, MusicalTime::ZERO);
+    }
+
+    #[test]
+    fn loop_shorter_than_block_is_rejected() {
+        let mut transport = transport();
+        let frames = |frames| FrameTime(frames).to_musical_lossy(120.0, SampleRate::new(48_000.0));
+        assert!(!transport.set_loop(MusicalTime::ZERO..frames(100)));
+        assert_eq!(transport.loop_range(), None);
+
+        // a loop of exactly one block wraps once per block
+        assert!(transport.set_loop(MusicalTime::ZERO..frames(256)));
+        transport.enable_loop(true);
+        transport.set_playhead(frames(100));
+        for _ in 0..4 {
+            let sub_blocks = transport.advance();
+            assert!(sub_blocks.len() <= 2);
+            assert_eq!(
+                sub_blocks
+                    .iter()
+                    .map(|sub_block| sub_block.frames.0)
+                    .sum::<u64>(),
+                256
+            );
+        }
+    }
+
+    #[test]
+    fn punched_frames_are_clipped_to_punch_region() {
+        let mut transport = transport();
+        let sample_rate = SampleRate::new(48_000.0);
+        assert!(transport.set_punch(
+            FrameTime(64).to_musical_lossy(120.0, sample_rate)
+                ..FrameTime(128).to_musical_lossy(120.0, sample_rate)
+        ));
+
+        let sub_block = transport.advance()[0].clone();
+        assert_eq!(
+            transport.punched_frames(&sub_block),
+            Some(FrameTime(0)..FrameTime(256))
+        );
+
+        transport.enable_punch(true);
+        assert_eq!(
+            transport.punched_frames(&sub_block),
+            Some(FrameTime(64)..FrameTime(128))
+        );
+    }
+
+    #[test]
+    fn enabled_punch_without_a_region_punches_nothing() {
+        let mut transport = transport();
+        let sub_block = transport.advance()[0].clone();
+
+        transport.enable_punch(true);
+        assert!(!transport.is_punching());
+        assert_eq!(transport.punched_frames(&sub_block), None);
+    }
+}